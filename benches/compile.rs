@@ -0,0 +1,59 @@
+use criterion::{BenchmarkId, Criterion, black_box, criterion_group, criterion_main};
+use vibelang::compiler::{codegen::CodeGenerator, compile, parser::parse_source};
+
+/// Builds a program with `count` semantic type aliases and `count` functions using them.
+fn synthetic_program(count: usize) -> String {
+    let mut source = String::new();
+    for i in 0..count {
+        source.push_str(&format!(
+            "type Value{i} = Meaning<Int>(\"measured value number {i}\");\n"
+        ));
+    }
+    for i in 0..count {
+        source.push_str(&format!(
+            "fn get_value_{i}(subject: String) -> Value{i} {{\n    prompt \"What is value {i} of {{subject}}?\";\n}}\n"
+        ));
+    }
+    source
+}
+
+fn bench_parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_source");
+    for count in [100, 500] {
+        let source = synthetic_program(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), &source, |b, source| {
+            b.iter(|| parse_source(black_box(source)).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn bench_generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate");
+    for count in [100, 500] {
+        let ast = parse_source(&synthetic_program(count)).unwrap();
+        group.bench_with_input(BenchmarkId::from_parameter(count), &ast, |b, ast| {
+            b.iter(|| {
+                CodeGenerator::new()
+                    .generate(black_box(ast), false)
+                    .unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_compile_500_functions(c: &mut Criterion) {
+    let source = synthetic_program(500);
+    c.bench_function("compile_500_functions", |b| {
+        b.iter(|| compile(black_box(&source), false).unwrap())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_parse,
+    bench_generate,
+    bench_compile_500_functions
+);
+criterion_main!(benches);
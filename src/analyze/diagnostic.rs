@@ -0,0 +1,219 @@
+use crate::compiler::SourceError;
+use crate::compiler::parser::Rule;
+use std::error::Error as _;
+use std::fmt;
+
+/// How serious a `Diagnostic` is. Errors stop the program from compiling or running as meant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A problem found in VibeLang source, at a 1-based line and column when it has them.
+///
+/// Syntax errors, template rendering errors and codegen errors all convert into one with
+/// `From`, so that tools such as `lint` report them alike.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// A diagnostic without a column.
+    pub fn new(severity: Severity, line: Option<usize>, message: String) -> Self {
+        Self {
+            severity,
+            line,
+            column: None,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "{} at line {}, column {}: {}",
+                severity, line, column, self.message
+            ),
+            (Some(line), None) => write!(f, "{} at line {}: {}", severity, line, self.message),
+            _ => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+impl From<pest::error::Error<Rule>> for Diagnostic {
+    fn from(error: pest::error::Error<Rule>) -> Self {
+        let (line, column) = match error.line_col {
+            pest::error::LineColLocation::Pos(position) => position,
+            pest::error::LineColLocation::Span(start, _) => start,
+        };
+        Self {
+            severity: Severity::Error,
+            line: Some(line),
+            column: Some(column),
+            message: error.variant.message().into_owned(),
+        }
+    }
+}
+
+impl From<SourceError> for Diagnostic {
+    fn from(error: SourceError) -> Self {
+        Self {
+            severity: Severity::Error,
+            line: Some(error.line),
+            column: error.column,
+            message: error.message,
+        }
+    }
+}
+
+impl From<tera::Error> for Diagnostic {
+    fn from(error: tera::Error) -> Self {
+        Self::new(Severity::Error, None, tera_message(&error))
+    }
+}
+
+impl From<anyhow::Error> for Diagnostic {
+    /// Converts an error from parsing, rendering or code generation, keeping the location of
+    /// those that have one.
+    fn from(error: anyhow::Error) -> Self {
+        let error = match error.downcast::<pest::error::Error<Rule>>() {
+            Ok(pest_error) => return pest_error.into(),
+            Err(error) => error,
+        };
+        let error = match error.downcast::<SourceError>() {
+            Ok(source_error) => return source_error.into(),
+            Err(error) => error,
+        };
+        if let Some(tera_error) = error.downcast_ref::<tera::Error>() {
+            return Self::new(Severity::Error, None, tera_message(tera_error));
+        }
+        Self::new(Severity::Error, None, error.to_string())
+    }
+}
+
+/// The message of a Tera error followed by its causes, where Tera puts the detail, such as
+/// the variable that was not found.
+fn tera_message(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+/// Collects the diagnostics of one run, dropping any already reported at the same line.
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a diagnostic, or anything that converts into one, unless it repeats the line and
+    /// message of one already added.
+    pub fn push(&mut self, diagnostic: impl Into<Diagnostic>) {
+        let diagnostic = diagnostic.into();
+        if !self.items.iter().any(|existing| {
+            existing.line == diagnostic.line && existing.message == diagnostic.message
+        }) {
+            self.items.push(diagnostic);
+        }
+    }
+
+    /// Whether any collected diagnostic is an error.
+    pub fn has_errors(&self) -> bool {
+        self.items
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compiler::codegen::CodeGenerator;
+    use crate::compiler::parser::parse_source;
+
+    #[test]
+    fn test_parse_and_render_errors_become_diagnostics() {
+        let parse_error = parse_source("fn f(x Int) -> String {}").unwrap_err();
+        let ast = parse_source(r#"type Year = Int;"#).unwrap();
+        let render_error = CodeGenerator::new()
+            .with_template("{{ functions.0.name }}")
+            .unwrap()
+            .generate(&ast, true)
+            .unwrap_err();
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(parse_error);
+        diagnostics.push(render_error);
+        assert!(diagnostics.has_errors());
+        let diagnostics = diagnostics.into_vec();
+
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(
+            (diagnostics[0].line, diagnostics[0].column),
+            (Some(1), Some(6))
+        );
+        assert_eq!(diagnostics[1].severity, Severity::Error);
+        assert_eq!(diagnostics[1].line, None);
+        assert!(
+            diagnostics[1].message.contains("functions.0.name"),
+            "{}",
+            diagnostics[1].message
+        );
+    }
+
+    #[test]
+    fn test_codegen_error_keeps_its_location() {
+        let ast = parse_source("fn f(a: Int, a: Int) -> String { prompt \"Say {a}.\"; }").unwrap();
+        let error = CodeGenerator::new().generate(&ast, true).unwrap_err();
+
+        let diagnostic = Diagnostic::from(error);
+
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(1), Some(6)));
+        assert_eq!(
+            diagnostic.message,
+            "Parameter `a` of `f` is declared twice, again at line 1, column 14"
+        );
+    }
+
+    #[test]
+    fn test_repeats_are_dropped_only_at_the_same_line() {
+        let unused = || "Parameter `x` of `f` is not used in its prompt".to_string();
+
+        let mut diagnostics = Diagnostics::new();
+        diagnostics.push(Diagnostic::new(Severity::Warning, Some(2), unused()));
+        diagnostics.push(Diagnostic::new(Severity::Warning, Some(2), unused()));
+        diagnostics.push(Diagnostic::new(Severity::Warning, Some(5), unused()));
+
+        let lines: Vec<Option<usize>> = diagnostics
+            .into_vec()
+            .iter()
+            .map(|diagnostic| diagnostic.line)
+            .collect();
+        assert_eq!(lines, [Some(2), Some(5)]);
+    }
+}
@@ -0,0 +1,247 @@
+use super::diagnostic::{Diagnostic, Diagnostics, Severity};
+use super::program_placeholder_style;
+use crate::compiler::codegen::CodeGenerator;
+use crate::compiler::parser::parse_source;
+use crate::runtime::placeholder::PlaceholderStyle;
+use crate::utils::ast::{AstNode, AstNodeType};
+use std::collections::HashMap;
+
+// Prompts with fewer words than this rarely say enough to get a usable answer.
+const MIN_PROMPT_WORDS: usize = 3;
+
+/// Checks VibeLang source without generating a project, returning every issue found.
+///
+/// Errors are syntax errors, placeholders that name no parameter, `let` binding or constant, and
+/// anything code generation rejects. Warnings are unused parameters, prompts too short to
+/// be specific, and non-String return types without a meaning.
+pub fn lint(source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostics::new();
+    let ast = match parse_source(source) {
+        Ok(ast) => ast,
+        Err(e) => {
+            diagnostics.push(e);
+            return diagnostics.into_vec();
+        }
+    };
+    let style = match program_placeholder_style(&ast) {
+        Ok(style) => style.unwrap_or_default(),
+        Err(e) => {
+            diagnostics.push(e);
+            return diagnostics.into_vec();
+        }
+    };
+
+    let type_decls: HashMap<&str, &AstNode> = ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::TypeDecl)
+        .map(|node| (node.get_string("name").unwrap().as_str(), &**node))
+        .collect();
+    let constants: Vec<&String> = ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::ConstDecl)
+        .filter_map(|node| node.get_string("name"))
+        .collect();
+
+    for func in ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::FunctionDecl)
+    {
+        lint_function(func, &type_decls, &constants, style, &mut diagnostics);
+    }
+
+    // Reported last: codegen stops at its first error, often one already listed above.
+    if let Err(e) = CodeGenerator::new().generate(&ast, false) {
+        diagnostics.push(e);
+    }
+    diagnostics.into_vec()
+}
+
+fn lint_function(
+    func: &AstNode,
+    type_decls: &HashMap<&str, &AstNode>,
+    constants: &[&String],
+    style: PlaceholderStyle,
+    issues: &mut Diagnostics,
+) {
+    let name = func.get_string("name").unwrap();
+    let line = Some(func.line);
+    let params: Vec<&String> = func
+        .children
+        .iter()
+        .filter(|child| child.node_type == AstNodeType::ParamList)
+        .flat_map(|list| list.children.iter())
+        .filter_map(|param| param.get_string("name"))
+        .collect();
+    let statements: Vec<&AstNode> = func
+        .children
+        .iter()
+        .filter(|child| child.node_type == AstNodeType::Block)
+        .flat_map(|block| block.children.iter().map(|stmt| &**stmt))
+        .collect();
+    let Some(prompt_index) = statements
+        .iter()
+        .position(|stmt| stmt.node_type == AstNodeType::PromptBlock)
+    else {
+        return;
+    };
+    let prompt = statements[prompt_index];
+    let template = prompt.get_string("template").unwrap();
+    // Only bindings before the prompt are substituted into it.
+    let locals: Vec<&String> = statements[..prompt_index]
+        .iter()
+        .filter(|stmt| stmt.node_type == AstNodeType::VarDecl)
+        .filter_map(|stmt| stmt.get_string("name"))
+        .collect();
+    let used = style.names_in(template);
+
+    for placeholder in &used {
+        if !params.contains(&placeholder)
+            && !locals.contains(&placeholder)
+            && !constants.contains(&placeholder)
+        {
+            // Worded as codegen words it, so that the two reports are merged.
+            issues.push(issue(
+                Severity::Error,
+                Some(prompt.line),
+                format!(
+                    "The prompt of `{}` uses `{}`, which is not a parameter or a `let` binding declared before it",
+                    name,
+                    style.placeholder(placeholder)
+                ),
+            ));
+        }
+    }
+    for param in &params {
+        if !used.contains(param) {
+            issues.push(issue(
+                Severity::Warning,
+                line,
+                format!(
+                    "Parameter `{}` of `{}` is not used in its prompt",
+                    param, name
+                ),
+            ));
+        }
+    }
+    if template.split_whitespace().count() < MIN_PROMPT_WORDS {
+        issues.push(issue(
+            Severity::Warning,
+            line,
+            format!(
+                "The prompt of `{}` is too short to be specific: \"{}\"",
+                name, template
+            ),
+        ));
+    }
+
+    let return_type = func.children.iter().find(|child| {
+        matches!(
+            child.node_type,
+            AstNodeType::BasicType | AstNodeType::MeaningType
+        )
+    });
+    if let Some(return_type) = return_type
+        && let Some(type_name) = type_without_meaning(return_type, type_decls)
+    {
+        issues.push(issue(
+            Severity::Warning,
+            line,
+            format!(
+                "`{}` returns `{}` without a meaning, so its answer is parsed generically",
+                name, type_name
+            ),
+        ));
+    }
+}
+
+/// The name of a non-String type that has no meaning, following declared aliases.
+fn type_without_meaning<'a>(
+    type_node: &'a AstNode,
+    type_decls: &HashMap<&str, &'a AstNode>,
+) -> Option<&'a str> {
+    let mut current = type_node;
+    // Bounded, so that an alias cycle (reported by codegen) cannot loop forever.
+    for _ in 0..=type_decls.len() {
+        if current.node_type != AstNodeType::BasicType {
+            return None;
+        }
+        let name = current.get_string("type")?.as_str();
+        match type_decls.get(name) {
+            Some(decl) => current = &decl.children[0],
+            None if name == "String" || name == "()" => return None,
+            None => return Some(name),
+        }
+    }
+    None
+}
+
+fn issue(severity: Severity, line: Option<usize>, message: String) -> Diagnostic {
+    Diagnostic::new(severity, line, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lint_reports_each_kind_of_issue() {
+        let source = r#"
+            type Year = Int;
+
+            fn get_year(calendar: String) -> Year {
+                prompt "Year?";
+            }
+
+            fn greet(name: String) -> String {
+                prompt "Say hello to {nmae} politely.";
+            }
+        "#;
+
+        let issues: Vec<String> = lint(source).iter().map(ToString::to_string).collect();
+
+        assert_eq!(
+            issues,
+            [
+                "warning at line 4: Parameter `calendar` of `get_year` is not used in its prompt",
+                "warning at line 4: The prompt of `get_year` is too short to be specific: \"Year?\"",
+                "warning at line 4: `get_year` returns `Int` without a meaning, so its answer is parsed generically",
+                "error at line 9: The prompt of `greet` uses `{nmae}`, which is not a parameter or a `let` binding declared before it",
+                "warning at line 8: Parameter `name` of `greet` is not used in its prompt",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_accepts_clean_source_and_reports_syntax_errors() {
+        let clean = r#"
+            const PLACE = "Oslo";
+            type Year = Meaning<Int>("the current year");
+            fn get_year() -> Year { prompt "What year is it now in {PLACE}?"; }
+        "#;
+        assert!(lint(clean).is_empty());
+
+        let issues = lint("fn broken( {");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_lint_reports_an_unknown_parameter_type() {
+        let issues = lint(r#"fn f(x: Undeclared) -> Int { prompt "num {x}"; }"#);
+
+        let errors: Vec<String> = issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "error at line 1, column 6: Parameter `x` of `f` has the unknown type `Undeclared`. Declare it with `type Undeclared = ...;` first."
+            ]
+        );
+    }
+}
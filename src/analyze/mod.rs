@@ -0,0 +1,240 @@
+pub mod diagnostic;
+pub mod lint;
+
+use crate::compiler::parser::parse_source;
+use crate::runtime::placeholder::PlaceholderStyle;
+use crate::utils::ast::{AstNode, AstNodeType};
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+
+/// Lists the `{...}` placeholders used by each function's prompt, or those of the style set
+/// with `@placeholders(...)`.
+///
+/// The returned map is keyed by function name. Placeholders are listed in the order they first
+/// appear in the prompt, without duplicates. Functions without a prompt map to an empty list.
+///
+/// # Arguments
+/// * `source` - A string slice containing the VibeLang source code.
+pub fn placeholders(source: &str) -> Result<HashMap<String, Vec<String>>> {
+    let ast = parse_source(source)?;
+    let style = program_placeholder_style(&ast)?.unwrap_or_default();
+    let mut map = HashMap::new();
+    collect_function_placeholders(&ast, style, &mut map);
+    Ok(map)
+}
+
+/// The placeholder style a program sets with `@placeholders(...)`, if it sets one.
+pub(crate) fn program_placeholder_style(ast: &AstNode) -> Result<Option<PlaceholderStyle>> {
+    let Some(syntax) = ast.get_string("placeholders") else {
+        return Ok(None);
+    };
+    PlaceholderStyle::from_syntax(syntax).map(Some).ok_or_else(|| {
+        anyhow!(
+            "Unknown placeholder style `{}`: expected `{{name}}`, `{{{{name}}}}` or `${{name}}`",
+            syntax
+        )
+    })
+}
+
+/// A function declared in VibeLang source, as listed by `functions`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionInfo {
+    pub name: String,
+    /// Each parameter's name and type, in declaration order.
+    pub params: Vec<(String, String)>,
+    /// The return type as written in the source, without meaning constraints such as
+    /// `range:`. `None` when the function declares no return type.
+    pub return_type: Option<String>,
+    /// The meaning of the return type, written inline or on the declared type it names.
+    pub meaning: Option<String>,
+    /// The prompt template, with its `{...}` placeholders unfilled.
+    pub prompt: Option<String>,
+}
+
+/// Lists the functions declared in `source`, in declaration order, for tools such as
+/// editor tooltips and generated documentation.
+///
+/// Types are given as written, e.g. `Weather` or `Meaning<Int>("temperature in Celsius")`,
+/// without resolving aliases; a function returning an alias still gets the alias's meaning.
+///
+/// # Arguments
+/// * `source` - A string slice containing the VibeLang source code.
+pub fn functions(source: &str) -> Result<Vec<FunctionInfo>> {
+    let ast = parse_source(source)?;
+    let type_decls: HashMap<&str, &AstNode> = ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::TypeDecl)
+        .map(|node| {
+            (
+                node.get_string("name").unwrap().as_str(),
+                &*node.children[0],
+            )
+        })
+        .collect();
+
+    let infos = ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::FunctionDecl)
+        .map(|func| {
+            let params = func
+                .children
+                .iter()
+                .filter(|child| child.node_type == AstNodeType::ParamList)
+                .flat_map(|list| list.children.iter())
+                .map(|param| {
+                    (
+                        param.get_string("name").cloned().unwrap_or_default(),
+                        type_text(&param.children[0]),
+                    )
+                })
+                .collect();
+            let return_type = func.children.iter().find(|child| {
+                matches!(
+                    child.node_type,
+                    AstNodeType::BasicType
+                        | AstNodeType::MeaningType
+                        | AstNodeType::UnionType
+                        | AstNodeType::TupleType
+                )
+            });
+            FunctionInfo {
+                name: func.get_string("name").cloned().unwrap_or_default(),
+                params,
+                return_type: return_type.map(|node| type_text(node)),
+                meaning: return_type.and_then(|node| type_meaning(node, &type_decls)),
+                prompt: prompt_template(func).cloned(),
+            }
+        })
+        .collect();
+    Ok(infos)
+}
+
+/// A type node written back as VibeLang source, without meaning constraints.
+fn type_text(type_node: &AstNode) -> String {
+    let inner = || type_node.children.iter().map(|child| type_text(child));
+    match type_node.node_type {
+        AstNodeType::MeaningType => format!(
+            "Meaning<{}>({:?})",
+            inner().next().unwrap_or_default(),
+            type_node.get_string("meaning").cloned().unwrap_or_default()
+        ),
+        AstNodeType::UnionType => type_node
+            .children
+            .iter()
+            .map(|variant| {
+                format!(
+                    "{:?}",
+                    variant.get_string("value").cloned().unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" | "),
+        AstNodeType::TupleType => format!("({})", inner().collect::<Vec<_>>().join(", ")),
+        _ => type_node.get_string("type").cloned().unwrap_or_default(),
+    }
+}
+
+/// The meaning of a type, following declared aliases.
+fn type_meaning(type_node: &AstNode, type_decls: &HashMap<&str, &AstNode>) -> Option<String> {
+    let mut current = type_node;
+    // Bounded, so that an alias cycle (reported by codegen) cannot loop forever.
+    for _ in 0..=type_decls.len() {
+        match current.node_type {
+            AstNodeType::MeaningType => return current.get_string("meaning").cloned(),
+            AstNodeType::BasicType => {
+                current = type_decls.get(current.get_string("type")?.as_str())?;
+            }
+            _ => return None,
+        }
+    }
+    None
+}
+
+fn collect_function_placeholders(
+    node: &AstNode,
+    style: PlaceholderStyle,
+    map: &mut HashMap<String, Vec<String>>,
+) {
+    for child in &node.children {
+        if child.node_type != AstNodeType::FunctionDecl {
+            collect_function_placeholders(child, style, map);
+            continue;
+        }
+
+        let Some(name) = child.get_string("name") else {
+            continue;
+        };
+        let names = prompt_template(child)
+            .map(|template| style.names_in(template))
+            .unwrap_or_default();
+        map.insert(name.clone(), names);
+    }
+}
+
+fn prompt_template(func_node: &AstNode) -> Option<&String> {
+    func_node
+        .children
+        .iter()
+        .filter(|child| child.node_type == AstNodeType::Block)
+        .flat_map(|block| block.children.iter())
+        .find(|stmt| stmt.node_type == AstNodeType::PromptBlock)
+        .and_then(|stmt| stmt.get_string("template"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_functions_lists_the_weather_sample() {
+        let source = include_str!("../../tests/fixtures/weather.vibe");
+
+        let infos = functions(source).unwrap();
+
+        assert_eq!(
+            infos,
+            [
+                FunctionInfo {
+                    name: "get_weather".to_string(),
+                    params: vec![("city".to_string(), "String".to_string())],
+                    return_type: Some("Weather".to_string()),
+                    meaning: Some("weather description".to_string()),
+                    prompt: Some(
+                        "What is the weather like in {city}? Provide a brief description."
+                            .to_string()
+                    ),
+                },
+                FunctionInfo {
+                    name: "get_temperature".to_string(),
+                    params: vec![("city".to_string(), "String".to_string())],
+                    return_type: Some("Meaning<Int>(\"temperature in Celsius\")".to_string()),
+                    meaning: Some("temperature in Celsius".to_string()),
+                    prompt: Some(
+                        "What is the current temperature in {city}? Reply with just the number."
+                            .to_string()
+                    ),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_placeholders_are_listed_per_function() {
+        let source = r#"
+            fn greet(name: String, city: String) -> String {
+                prompt "Say hello to {name} from {city}, and use {name} twice.";
+            }
+            fn get_year() -> Int {
+                prompt "What year is it?";
+            }
+        "#;
+
+        let map = placeholders(source).unwrap();
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map["greet"], vec!["name".to_string(), "city".to_string()]);
+        assert!(map["get_year"].is_empty());
+    }
+}
@@ -1,16 +1,37 @@
+use crate::analyze::program_placeholder_style;
+use crate::compiler::SourceError;
+use crate::compiler::template_vars::referenced_variables;
+use crate::runtime::placeholder::PlaceholderStyle;
+use crate::runtime::retry::DEFAULT_NUMBER_RETRIES;
+use crate::runtime::transform::enhance_prompt;
+use crate::runtime::types::{FloatFormat, NumberStyle};
+use crate::runtime::validation::Bounds;
 use crate::utils::ast::{AstNode, AstNodeType};
-use anyhow::Result;
+use anyhow::{Context as _, Result, anyhow, bail};
 use once_cell::sync::Lazy;
-use serde::Serialize;
-use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
 use tera::{Context, Tera};
 
 pub static TEMPLATES: Lazy<Tera> = Lazy::new(|| {
     let mut tera = Tera::default();
+    tera.add_raw_template(
+        "runtime.rs.tera",
+        include_str!("../../templates/runtime.rs.tera"),
+    )
+    .expect("Failed to parse runtime template");
     tera.add_raw_template("main.rs.tera", include_str!("../../templates/main.rs.tera"))
         .expect("Failed to parse main template");
     tera.add_raw_template("lib.rs.tera", include_str!("../../templates/lib.rs.tera"))
         .expect("Failed to parse lib template");
+    tera.add_raw_template(
+        "modules.rs.tera",
+        include_str!("../../templates/modules.rs.tera"),
+    )
+    .expect("Failed to parse modules template");
     tera
 });
 #[derive(Serialize)]
@@ -18,18 +39,26 @@ struct TypeAlias {
     name: String,
     base_type: String,
     meaning: Option<String>,
+    // The unit declared with `unit: "..."`, if any.
+    unit: Option<String>,
+    // The lines of the `///` comment above the declaration.
+    doc: Vec<String>,
 }
 
 #[derive(Serialize)]
 struct SemanticHandler {
     meaning: String,
     normalized_name: String,
+    // "percentage" or "currency" for numeric meanings that name one, see `NumberStyle`.
+    number_style: Option<&'static str>,
 }
 
 #[derive(Serialize)]
 struct SemanticTypeGroup {
     rust_type: String,
     handlers: Vec<SemanticHandler>,
+    // Set for types registered with `CodeGenerator::with_type_mapping`.
+    parser: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -37,6 +66,70 @@ struct FunctionParam {
     name: String,
     rust_type: String,
     test_value: String,
+    // The text `test_value` puts in a prompt, for `example_prompts`.
+    test_text: String,
+    // Inline `Meaning<...>(...)` on the parameter, passed to the model as context.
+    meaning: Option<String>,
+    // The `where` checks as a Rust condition, and as written for the panic message.
+    guard: Option<String>,
+    guard_text: Option<String>,
+}
+
+/// A `let` binding in a function body, emitted before the prompt is built.
+#[derive(Serialize)]
+struct LocalBinding {
+    name: String,
+    rust_type: Option<String>,
+    value: String,
+    // The text a literal `value` puts in a prompt, or the binding it copies, for
+    // `example_prompts`.
+    text: String,
+    copies: Option<String>,
+}
+
+/// A program rendered as a `pub mod` by `CodeGenerator::generate_modules`.
+#[derive(Serialize)]
+struct Module {
+    name: String,
+    // Paths of the types used from other modules, e.g. `weather::City`.
+    imports: Vec<String>,
+    code: String,
+    // The module's `functions` context variable, called from `main`.
+    functions: tera::Value,
+}
+
+#[derive(Serialize)]
+struct ClassField {
+    name: String,
+    rust_type: String,
+    // The declared default as a Rust expression, or `Default::default()`.
+    default: String,
+    doc: Vec<String>,
+}
+
+/// A struct generated for a `class`, with one public field per member.
+#[derive(Serialize)]
+struct Class {
+    name: String,
+    fields: Vec<ClassField>,
+    // Whether any field declares a default, in which case a `Default` impl is generated.
+    has_defaults: bool,
+    doc: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct InlineEnumVariant {
+    name: String,
+    value: String,
+    // The value as matched against a normalized response: lowercase, no outer punctuation.
+    match_key: String,
+}
+
+/// An enum generated for a return type written as a union of string literals.
+#[derive(Serialize)]
+struct InlineEnum {
+    name: String,
+    variants: Vec<InlineEnumVariant>,
 }
 
 #[derive(Serialize)]
@@ -46,14 +139,324 @@ struct Function {
     return_type: String,
     return_base_type: String,
     semantic_meaning: Option<String>,
+    // Parameters referenced as `{name}` in the meaning, substituted at runtime.
+    meaning_params: Vec<String>,
+    has_param_meanings: bool,
     prompt_template: String,
+    // Bindings declared with `let` before the prompt; usable as `{name}` placeholders.
+    locals: Vec<LocalBinding>,
+    // Sampling temperature set with `temperature <value>;`, as an f64 literal.
+    temperature: Option<String>,
+    // A `Bounds` expression checked against the result, from `range:`, `min:` or `max:`.
+    bounds: Option<String>,
+    // The unit the result is asked for in, from `unit:`.
+    unit: Option<String>,
+    is_async: bool,
+    inline_enum: Option<InlineEnum>,
+    // The base type of each element of a tuple return type, read from a JSON array.
+    tuple_types: Vec<String>,
+    // From `@with_confidence`: the type of the answer, which is returned in a `Confident`.
+    confidence_type: Option<String>,
+    // Builder calls applying the `FunctionSettings`, `@timeout(secs)` and `@retries(n)` of the
+    // function to a clone of the client, e.g. `.with_model("mistral")`. Empty without any.
+    client_overrides: String,
+    // Converts the response text for return types registered with `with_type_mapping`.
+    return_parser: Option<String>,
+    // The lines of the `///` comment above the declaration, emitted as Rust doc comments.
+    doc: Vec<String>,
+}
+
+/// Describes how a declared VibeLang type maps to the generated Rust code.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct TypeInfo {
+    /// The name of the type alias in the VibeLang source.
+    pub name: String,
+    /// The Rust type the alias resolves to.
+    pub rust_type: String,
+    /// The semantic meaning attached with `Meaning<...>(...)`, if any.
+    pub meaning: Option<String>,
+    /// The generated extractor function used to parse responses for this meaning.
+    pub extractor: Option<String>,
+}
+
+/// The context variables available to the templates, as inserted by `CodeGenerator::generate`.
+pub const TEMPLATE_VARIABLES: &[&str] = &[
+    "type_aliases",
+    "classes",
+    "functions",
+    "has_async_functions",
+    "has_confident_functions",
+    "semantic_type_groups",
+    "as_lib",
+    "header",
+    "raw_responses",
+    "meta_functions",
+    "number_retries",
+    "uses",
+    "external_runtime",
+    "placeholder_open",
+    "placeholder_close",
+];
+
+// Name under which a template set with `CodeGenerator::with_template` is registered.
+const CUSTOM_TEMPLATE_NAME: &str = "custom.rs.tera";
+
+/// How a VibeLang base type registered with `CodeGenerator::with_type_mapping` is generated.
+///
+/// All three parts are Rust source code inserted into the generated program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeMapping {
+    /// The Rust type, e.g. `chrono::NaiveDate`.
+    pub rust_type: String,
+    /// An expression of that type used as the argument in generated example calls.
+    pub test_value: String,
+    /// An expression converting the response text, bound to `text: &str`, into the type.
+    pub parser: String,
+}
+
+impl TypeMapping {
+    pub fn new(rust_type: &str, test_value: &str, parser: &str) -> Self {
+        Self {
+            rust_type: rust_type.to_string(),
+            test_value: test_value.to_string(),
+            parser: parser.to_string(),
+        }
+    }
+}
+
+/// Client settings for one function, overriding the program's defaults. Set with
+/// `CodeGenerator::with_function_settings`, e.g. from a `<file>.vibe.json` sidecar file.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FunctionSettings {
+    /// The model asked instead of the configured one.
+    pub model: Option<String>,
+    /// Replaces the temperature set in the source, if any.
+    pub temperature: Option<f64>,
+    /// Sequences at which the model stops generating.
+    #[serde(default)]
+    pub stop: Vec<String>,
+}
+
+/// Indentation used in the generated Rust code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndentStyle {
+    /// Four spaces per level, as written in the templates.
+    #[default]
+    Spaces,
+    /// One tab per level.
+    Tabs,
+}
+
+/// Whether generated functions call the LLM synchronously or as `async fn`s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuntimeMode {
+    /// Plain functions using the blocking client; the binary needs no tokio runtime.
+    Blocking,
+    /// `async fn`s using the async client, awaited from a `#[tokio::main]`.
+    Async,
 }
 
-pub struct CodeGenerator {}
+impl std::str::FromStr for RuntimeMode {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "blocking" => Ok(RuntimeMode::Blocking),
+            "async" => Ok(RuntimeMode::Async),
+            _ => Err(anyhow!(
+                "Unsupported runtime `{}`: expected blocking or async",
+                name
+            )),
+        }
+    }
+}
+
+/// Traceability information written at the top of a generated file.
+#[derive(Serialize, Debug, Clone)]
+struct GeneratedHeader {
+    version: String,
+    source_name: String,
+    source_hash: String,
+    timestamp: u64,
+}
+
+#[derive(Default)]
+pub struct CodeGenerator {
+    indent_style: IndentStyle,
+    header: Option<GeneratedHeader>,
+    raw_responses: bool,
+    meta_functions: bool,
+    strict_types: bool,
+    // Set by `with_external_runtime`: the program brings its own `vibe_execute_prompt`.
+    external_runtime: bool,
+    // The built-in templates plus a user template, set by `with_template`.
+    custom_templates: Option<Tera>,
+    // Extra base types, keyed by their VibeLang name.
+    type_mappings: HashMap<String, TypeMapping>,
+    float_format: FloatFormat,
+    // How often a numeric answer without a number is asked again; see `with_number_retries`.
+    number_retries: Option<usize>,
+    // Client settings for individual functions, keyed by function name.
+    function_settings: HashMap<String, FunctionSettings>,
+    // Used unless the program sets a style with `@placeholders(...)`.
+    placeholder_style: PlaceholderStyle,
+    // Overrides the `async` of every function declaration when set.
+    runtime_mode: Option<RuntimeMode>,
+}
 
 impl CodeGenerator {
     pub fn new() -> Self {
-        Self {}
+        Self::default()
+    }
+
+    /// Sets the indentation style of the generated code.
+    pub fn with_indent_style(mut self, indent_style: IndentStyle) -> Self {
+        self.indent_style = indent_style;
+        self
+    }
+
+    /// Generates a `<name>_raw` companion returning the unparsed LLM response for each
+    /// function, and logs raw responses when `VIBELANG_DEBUG` is set at runtime.
+    pub fn with_raw_responses(mut self, raw_responses: bool) -> Self {
+        self.raw_responses = raw_responses;
+        self
+    }
+
+    /// Generates a `<name>_with_meta` companion for each function, returning the value with
+    /// a `CallMeta` that records the call's latency and which provider and model answered.
+    pub fn with_meta_functions(mut self, meta_functions: bool) -> Self {
+        self.meta_functions = meta_functions;
+        self
+    }
+
+    /// Leaves out the bundled runtime helpers, for programs that maintain their own runtime.
+    ///
+    /// The generated code keeps the type definitions and the public functions, which build
+    /// their prompts as usual and then call a `vibe_execute_prompt` function the program must
+    /// define; a comment in the generated code gives its signature. The extractors, the
+    /// semantic parser dispatch and the number retries are not generated. This cannot be
+    /// combined with `with_raw_responses`, whose companions use the bundled helpers.
+    pub fn with_external_runtime(mut self, external_runtime: bool) -> Self {
+        self.external_runtime = external_runtime;
+        self
+    }
+
+    /// Requires every non-String return type to carry a meaning.
+    pub fn with_strict_types(mut self, strict_types: bool) -> Self {
+        self.strict_types = strict_types;
+        self
+    }
+
+    /// Sets how float test values are written in the generated code. Float literals from the
+    /// source are always written exactly.
+    pub fn with_float_format(mut self, float_format: FloatFormat) -> Self {
+        self.float_format = float_format;
+        self
+    }
+
+    /// Sets how many times generated code asks again, with "Reply with ONLY a number."
+    /// appended, when an Int or Float answer contains no number. Zero disables the retries;
+    /// the default is `DEFAULT_NUMBER_RETRIES`.
+    pub fn with_number_retries(mut self, retries: usize) -> Self {
+        self.number_retries = Some(retries);
+        self
+    }
+
+    /// Overrides the client settings of individual functions, keyed by function name.
+    ///
+    /// A function with a model or stop sequences calls the LLM through a copy of the client
+    /// configured with them; a temperature replaces the one set in the source.
+    pub fn with_function_settings(mut self, settings: HashMap<String, FunctionSettings>) -> Self {
+        self.function_settings = settings;
+        self
+    }
+
+    /// Sets how prompts mark placeholders, e.g. `${name}` for prompts that contain JSON.
+    ///
+    /// A program's own `@placeholders(...)` annotation takes precedence.
+    pub fn with_placeholder_style(mut self, style: PlaceholderStyle) -> Self {
+        self.placeholder_style = style;
+        self
+    }
+
+    /// Generates every function as blocking or as `async`, whatever its declaration says.
+    ///
+    /// Without a mode, functions declared `async fn` are async and the others blocking.
+    pub fn with_runtime_mode(mut self, runtime_mode: RuntimeMode) -> Self {
+        self.runtime_mode = Some(runtime_mode);
+        self
+    }
+
+    // The placeholder style of `ast`: its annotation's, or the configured one.
+    fn placeholder_style(&self, ast: &AstNode) -> Result<PlaceholderStyle> {
+        Ok(program_placeholder_style(ast)?.unwrap_or(self.placeholder_style))
+    }
+
+    /// Registers an additional VibeLang base type, e.g. `Date` as `chrono::NaiveDate`.
+    ///
+    /// The type can then be used like `Int` or `String`: in type aliases, `Meaning<...>`,
+    /// parameters and return types. Registering a built-in name replaces its mapping.
+    ///
+    /// # Arguments
+    /// * `vibe_type` - The name of the type in VibeLang source.
+    /// * `mapping` - The Rust type, example value and response parser to generate.
+    pub fn with_type_mapping(mut self, vibe_type: &str, mapping: TypeMapping) -> Self {
+        self.type_mappings.insert(vibe_type.to_string(), mapping);
+        self
+    }
+
+    /// Renders the generated code with a custom Tera template instead of the built-in ones.
+    ///
+    /// The template can `{% include "runtime.rs.tera" %}` to reuse the built-in runtime code.
+    /// It is checked before use: reading a variable outside `TEMPLATE_VARIABLES` is an error
+    /// naming the unknown variables, rather than an opaque failure while rendering.
+    ///
+    /// # Arguments
+    /// * `template` - The source of the Tera template.
+    pub fn with_template(mut self, template: &str) -> Result<Self> {
+        let mut tera = TEMPLATES.clone();
+        tera.add_raw_template(CUSTOM_TEMPLATE_NAME, template)
+            .context("Failed to parse the custom template")?;
+
+        let custom = tera.get_template(CUSTOM_TEMPLATE_NAME)?;
+        let unknown: Vec<String> = referenced_variables(&custom.ast)
+            .into_iter()
+            .filter(|name| !TEMPLATE_VARIABLES.contains(&name.as_str()))
+            .map(|name| format!("`{}`", name))
+            .collect();
+        if !unknown.is_empty() {
+            bail!(
+                "The custom template uses unknown variables: {}. Available variables: {}",
+                unknown.join(", "),
+                TEMPLATE_VARIABLES.join(", ")
+            );
+        }
+
+        self.custom_templates = Some(tera);
+        Ok(self)
+    }
+
+    /// Enables the `// Generated by vibelang` header comment.
+    ///
+    /// # Arguments
+    /// * `source_name` - The name of the `.vibe` file the code is generated from.
+    /// * `source` - The VibeLang source code, used to compute the source hash.
+    pub fn with_header(mut self, source_name: &str, source: &str) -> Self {
+        let mut hasher = DefaultHasher::new();
+        source.hash(&mut hasher);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.header = Some(GeneratedHeader {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            source_name: source_name.to_string(),
+            source_hash: format!("{:016x}", hasher.finish()),
+            timestamp,
+        });
+        self
     }
 
     /// Generates Rust code from the AST.
@@ -62,96 +465,464 @@ impl CodeGenerator {
     /// * `ast` - The Abstract Syntax Tree to generate code from.
     /// * `as_lib` - If true, generates library code; if false, generates binary code with main function.
     pub fn generate(&self, ast: &AstNode, as_lib: bool) -> Result<String> {
+        let context = self.build_context(ast, as_lib, &[])?;
+
+        let (templates, template_name) = match &self.custom_templates {
+            Some(tera) => (tera, CUSTOM_TEMPLATE_NAME),
+            None if as_lib => (&*TEMPLATES, "lib.rs.tera"),
+            None => (&*TEMPLATES, "main.rs.tera"),
+        };
+        let rendered = render_template(templates, template_name, &context)?;
+        Ok(self.apply_indent_style(rendered))
+    }
+
+    /// Generates a single Rust file with one `pub mod` per VibeLang program.
+    ///
+    /// Each module holds the code `generate` would produce for its program, without `main`.
+    /// A type declared in one module can be used in the others, which import it with
+    /// `use super::<module>::<Type>;`; a type declared locally takes precedence.
+    ///
+    /// # Arguments
+    /// * `modules` - The module names, e.g. the source file stems, and their ASTs.
+    /// * `as_lib` - If true, generates library code; if false, a `main` calls every function.
+    pub fn generate_modules(&self, modules: &[(String, AstNode)], as_lib: bool) -> Result<String> {
+        if self.custom_templates.is_some() {
+            bail!("Custom templates cannot be used when generating modules");
+        }
+
+        let mut rendered_modules = Vec::new();
+        let mut has_async_functions = false;
+        for (name, ast) in modules {
+            let imports = imported_types(name, ast, modules);
+            let context = self.build_context(ast, as_lib, &imports)?;
+            has_async_functions |= context
+                .get("has_async_functions")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false);
+
+            rendered_modules.push(Module {
+                name: name.clone(),
+                imports: imports
+                    .iter()
+                    .map(|(module, decl)| {
+                        format!("{}::{}", module, decl.get_string("name").unwrap())
+                    })
+                    .collect(),
+                code: render_template(&TEMPLATES, "runtime.rs.tera", &context)?,
+                functions: context.get("functions").cloned().unwrap_or_default(),
+            });
+        }
+
+        let mut context = Context::new();
+        context.insert("modules", &rendered_modules);
+        context.insert("has_async_functions", &has_async_functions);
+        context.insert("as_lib", &as_lib);
+        context.insert("header", &self.header);
+        let rendered = render_template(&TEMPLATES, "modules.rs.tera", &context)?;
+        Ok(self.apply_indent_style(rendered))
+    }
+
+    /// Builds the template context for a program.
+    ///
+    /// `imports` are type declarations from other modules, usable in this one but not emitted.
+    fn build_context(
+        &self,
+        ast: &AstNode,
+        as_lib: bool,
+        imports: &[(&str, &AstNode)],
+    ) -> Result<Context> {
+        if self.external_runtime && self.raw_responses {
+            bail!(
+                "Raw response companions use the bundled runtime helpers, so they cannot be generated with an external runtime"
+            );
+        }
+        let style = self.placeholder_style(ast)?;
+        let folded = fold_constants(ast, style)?;
+        let ast = &folded;
         let mut context = Context::new();
         let mut type_aliases: Vec<TypeAlias> = Vec::new();
-        let mut semantic_meanings: HashMap<String, (String, String)> = HashMap::new();
+        let mut semantic_meanings: BTreeMap<String, (String, String)> = BTreeMap::new();
         let mut type_alias_map: HashMap<String, String> = HashMap::new();
         let mut type_meaning_map: HashMap<String, String> = HashMap::new();
+        let mut type_bounds_map: HashMap<String, Bounds> = HashMap::new();
 
-        // Process type declarations
+        // A single pass over the declarations checks the type names and sorts the declarations
+        // by kind. Functions are processed afterwards, since they may use types declared after them.
+        let mut local_type_decls: Vec<&AstNode> = Vec::new();
+        let mut function_nodes: Vec<&AstNode> = Vec::new();
+        let mut class_nodes: Vec<&AstNode> = Vec::new();
+        let mut uses: Vec<String> = Vec::new();
         for node in &ast.children {
-            if let AstNodeType::TypeDecl = node.node_type {
-                self.process_type_decl_node(
-                    node,
-                    &mut type_aliases,
-                    &mut semantic_meanings,
-                    &mut type_alias_map,
-                    &mut type_meaning_map,
-                );
+            match node.node_type {
+                AstNodeType::TypeDecl => {
+                    check_type_name(node, "type")?;
+                    local_type_decls.push(node);
+                }
+                AstNodeType::ClassDecl => {
+                    check_type_name(node, "class")?;
+                    class_nodes.push(node);
+                }
+                AstNodeType::FunctionDecl => function_nodes.push(node),
+                AstNodeType::Import => uses.push(use_path(node)?),
+                _ => {}
             }
         }
+        let type_decls: Vec<&AstNode> = local_type_decls
+            .iter()
+            .copied()
+            .chain(imports.iter().map(|(_, decl)| *decl))
+            .collect();
+        type_alias_map.extend(self.resolve_alias_bases(&type_decls)?);
+        let type_unit_map = type_units(&type_decls);
+        // The type maps are filled once the base of every alias is known.
+        for node in local_type_decls {
+            self.process_type_decl_node(
+                node,
+                &mut type_aliases,
+                &mut semantic_meanings,
+                &mut type_alias_map,
+                &mut type_meaning_map,
+                &mut type_bounds_map,
+            );
+        }
 
-        // Process function declarations
-        let mut functions: Vec<Function> = Vec::new();
-        for node in &ast.children {
-            if let AstNodeType::FunctionDecl = node.node_type {
-                functions.push(self.process_function_node(
+        // Imported meanings are kept for functions returning an imported type; the aliases are
+        // declared by the module that owns them.
+        for (_, decl) in imports {
+            self.process_type_decl_node(
+                decl,
+                &mut Vec::new(),
+                &mut semantic_meanings,
+                &mut type_alias_map,
+                &mut type_meaning_map,
+                &mut type_bounds_map,
+            );
+        }
+
+        self.check_call_argument_types(ast, &type_alias_map)?;
+
+        let classes = class_nodes
+            .into_iter()
+            .map(|node| self.process_class_node(node, &type_alias_map))
+            .collect::<Result<Vec<Class>>>()?;
+
+        let functions = function_nodes
+            .into_iter()
+            .map(|node| {
+                self.check_no_class_params(node, &classes, &type_alias_map)?;
+                self.process_function_node(
                     node,
                     &type_alias_map,
                     &type_meaning_map,
-                )?);
-            }
-        }
+                    &type_bounds_map,
+                    &type_unit_map,
+                    style,
+                )
+            })
+            .collect::<Result<Vec<Function>>>()?;
+
+        // Only meanings returned by some function are dispatched at runtime, so extractors for
+        // the others would be dead code. Meanings filled in from parameters never match one.
+        semantic_meanings.retain(|meaning, _| {
+            functions.iter().any(|function| {
+                function.meaning_params.is_empty()
+                    && function.semantic_meaning.as_deref() == Some(meaning.as_str())
+            })
+        });
 
         // Process semantic type groups
-        let mut grouped_semantics: HashMap<String, Vec<SemanticHandler>> = HashMap::new();
+        let mut grouped_semantics: BTreeMap<String, Vec<SemanticHandler>> = BTreeMap::new();
         for (meaning, (rust_type, normalized_name)) in semantic_meanings {
+            let number_style = if is_numeric(&rust_type) {
+                NumberStyle::for_meaning(&meaning).name()
+            } else {
+                None
+            };
             let group = grouped_semantics.entry(rust_type).or_default();
             group.push(SemanticHandler {
                 meaning,
                 normalized_name,
+                number_style,
             });
         }
 
         let semantic_type_groups: Vec<SemanticTypeGroup> = grouped_semantics
             .into_iter()
             .map(|(rust_type, handlers)| SemanticTypeGroup {
+                parser: self.custom_parser(&rust_type),
                 rust_type,
                 handlers,
             })
             .collect();
 
         context.insert("type_aliases", &type_aliases);
+        context.insert("classes", &classes);
+        let has_async_functions = functions.iter().any(|f| f.is_async);
         context.insert("functions", &functions);
+        context.insert("has_async_functions", &has_async_functions);
+        let has_confident_functions = functions.iter().any(|f| f.confidence_type.is_some());
+        context.insert("has_confident_functions", &has_confident_functions);
         context.insert("semantic_type_groups", &semantic_type_groups);
         context.insert("as_lib", &as_lib);
-
-        let template_name = if as_lib {
-            "lib.rs.tera"
+        context.insert("header", &self.header);
+        context.insert("raw_responses", &self.raw_responses);
+        context.insert("meta_functions", &self.meta_functions);
+        // Only programs with numeric results get the retry logic.
+        let has_numeric_functions = functions.iter().any(|f| is_numeric(&f.return_base_type));
+        let number_retries = if has_numeric_functions {
+            self.number_retries.unwrap_or(DEFAULT_NUMBER_RETRIES)
         } else {
-            "main.rs.tera"
+            0
         };
-        let rendered = match TEMPLATES.render(template_name, &context) {
-            Ok(code) => code,
-            Err(e) => {
-                eprintln!("Template rendering error: {}", e);
-                eprintln!("Template name: {}", template_name);
-                eprintln!(
-                    "Context variables: type_aliases={}, functions={}, semantic_type_groups={}",
-                    type_aliases.len(),
-                    functions.len(),
-                    semantic_type_groups.len()
+        context.insert("number_retries", &number_retries);
+        context.insert("uses", &uses);
+        context.insert("external_runtime", &self.external_runtime);
+        let (placeholder_open, placeholder_close) = style.delimiters();
+        context.insert("placeholder_open", placeholder_open);
+        context.insert("placeholder_close", placeholder_close);
+        Ok(context)
+    }
+
+    /// Whether any function of the program is generated as `async`, which makes the
+    /// generated code run on a tokio runtime.
+    pub fn has_async_functions(&self, ast: &AstNode) -> bool {
+        ast.children
+            .iter()
+            .filter(|node| node.node_type == AstNodeType::FunctionDecl)
+            .any(|node| self.is_async(node))
+    }
+
+    // Whether a function is generated as `async`: as declared, unless a runtime mode is set.
+    fn is_async(&self, func: &AstNode) -> bool {
+        match self.runtime_mode {
+            Some(mode) => mode == RuntimeMode::Async,
+            None => func.get_bool("async").unwrap_or(false),
+        }
+    }
+
+    /// Describes every type declared in the program, in declaration order.
+    pub fn describe_types(&self, ast: &AstNode) -> Vec<TypeInfo> {
+        let mut type_aliases: Vec<TypeAlias> = Vec::new();
+        let mut semantic_meanings: BTreeMap<String, (String, String)> = BTreeMap::new();
+        let mut type_alias_map: HashMap<String, String> = HashMap::new();
+        let mut type_meaning_map: HashMap<String, String> = HashMap::new();
+
+        let type_decls: Vec<&AstNode> = ast
+            .children
+            .iter()
+            .map(|node| &**node)
+            .filter(|node| node.node_type == AstNodeType::TypeDecl)
+            .collect();
+        // A cycle is reported by `generate`; here the aliases in it keep their own names.
+        type_alias_map.extend(self.resolve_alias_bases(&type_decls).unwrap_or_default());
+
+        for node in &ast.children {
+            if let AstNodeType::TypeDecl = node.node_type {
+                self.process_type_decl_node(
+                    node,
+                    &mut type_aliases,
+                    &mut semantic_meanings,
+                    &mut type_alias_map,
+                    &mut type_meaning_map,
+                    &mut HashMap::new(),
                 );
-                return Err(e.into());
             }
+        }
+
+        type_aliases
+            .into_iter()
+            .map(|alias| {
+                let extractor = alias.meaning.as_ref().map(|m| {
+                    let (_, normalized_name) = &semantic_meanings[m];
+                    format!("extract_{}_value", normalized_name)
+                });
+                TypeInfo {
+                    name: alias.name,
+                    rust_type: alias.base_type,
+                    meaning: alias.meaning,
+                    extractor,
+                }
+            })
+            .collect()
+    }
+
+    /// Describes every declared type with a meaning as a JSON document, for services that
+    /// consume the semantic types, such as a UI generating forms.
+    ///
+    /// The document has the shape
+    /// `{"types": [{"name", "base_type", "meaning", "extractor"}, ...]}`, in declaration order.
+    pub fn schema(&self, ast: &AstNode) -> serde_json::Value {
+        let types: Vec<serde_json::Value> = self
+            .describe_types(ast)
+            .into_iter()
+            .filter_map(|info| {
+                Some(serde_json::json!({
+                    "name": info.name,
+                    "base_type": info.rust_type,
+                    "meaning": info.meaning?,
+                    "extractor": info.extractor,
+                }))
+            })
+            .collect();
+        serde_json::json!({ "types": types })
+    }
+
+    /// Renders a human-readable explanation of how each declared type maps to Rust.
+    pub fn explain(&self, ast: &AstNode) -> String {
+        let mut explanation = String::new();
+        for info in self.describe_types(ast) {
+            explanation.push_str(&format!("{} -> {}\n", info.name, info.rust_type));
+            explanation.push_str(&format!(
+                "    meaning:   {}\n",
+                info.meaning.as_deref().unwrap_or("(none)")
+            ));
+            explanation.push_str(&format!(
+                "    extractor: {}\n",
+                info.extractor
+                    .as_deref()
+                    .unwrap_or("(generic extraction for the base type)")
+            ));
+        }
+        explanation
+    }
+
+    /// Renders each function's prompt with the example arguments used in the generated `main`.
+    ///
+    /// Returns the function names and prompts in declaration order, so the prompts can be
+    /// tried against a model by hand. Functions without a prompt are left out.
+    pub fn example_prompts(&self, ast: &AstNode) -> Result<Vec<(String, String)>> {
+        let style = self.placeholder_style(ast)?;
+        let mut prompts = Vec::new();
+        for func in self.function_values(ast)? {
+            let texts = binding_texts(&func, &HashMap::new());
+            if let Some(prompt) = filled_prompt(&func, &texts, style) {
+                let name = func["name"].as_str().unwrap_or_default().to_string();
+                prompts.push((name, prompt));
+            }
+        }
+        Ok(prompts)
+    }
+
+    /// Renders the prompt `function` hands to the LLM client when called with `args`,
+    /// including the meaning context added by the generated runtime.
+    ///
+    /// Arguments are given as the text they are substituted with; parameters without one
+    /// take the example values of `example_prompts`.
+    pub fn render_prompt(
+        &self,
+        ast: &AstNode,
+        function: &str,
+        args: &HashMap<String, String>,
+    ) -> Result<String> {
+        let functions = self.function_values(ast)?;
+        let func = functions
+            .iter()
+            .find(|func| func["name"].as_str() == Some(function))
+            .ok_or_else(|| {
+                let names: Vec<&str> = functions
+                    .iter()
+                    .filter_map(|func| func["name"].as_str())
+                    .collect();
+                anyhow!(
+                    "There is no function `{}`. Available functions: {}",
+                    function,
+                    names.join(", ")
+                )
+            })?;
+
+        let params = func["params"].as_array().cloned().unwrap_or_default();
+        for name in args.keys() {
+            if !params
+                .iter()
+                .any(|param| param["name"].as_str() == Some(name))
+            {
+                bail!("`{}` has no parameter `{}`", function, name);
+            }
+        }
+        let style = self.placeholder_style(ast)?;
+        let texts = binding_texts(func, args);
+        let prompt = filled_prompt(func, &texts, style)
+            .ok_or_else(|| anyhow!("`{}` has no prompt to render", function))?;
+
+        let Some(meaning) = func["semantic_meaning"].as_str() else {
+            return Ok(prompt);
         };
-        Ok(rendered)
+        let meaning = func["meaning_params"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|name| name.as_str())
+            .fold(meaning.to_string(), |meaning, name| {
+                meaning.replace(&style.placeholder(name), &texts[name])
+            });
+        Ok(enhance_prompt(&prompt, &meaning))
     }
 
-    fn generate_test_value(&self, base_rust_type: &str) -> String {
-        match base_rust_type {
-            "i32" => "123".to_string(),
-            "f64" => "45.6".to_string(),
-            "bool" => "true".to_string(),
-            "String" => "\"Test Topic\".to_string()".to_string(),
-            // Fallback for unknown types.
-            _ => panic!("Cannot generate test value for this type"),
+    // The `functions` context variable, one JSON object per function.
+    fn function_values(&self, ast: &AstNode) -> Result<Vec<tera::Value>> {
+        let context = self.build_context(ast, false, &[])?;
+        Ok(context
+            .get("functions")
+            .and_then(|value| value.as_array())
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Re-indents the rendered code, which the templates write with four spaces.
+    fn apply_indent_style(&self, code: String) -> String {
+        match self.indent_style {
+            IndentStyle::Spaces => code,
+            IndentStyle::Tabs => code
+                .split('\n')
+                .map(|line| {
+                    let content = line.trim_start_matches(' ');
+                    let levels = (line.len() - content.len()) / 4;
+                    format!("{}{}", "\t".repeat(levels), &line[levels * 4..])
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
         }
     }
 
-    // ... (other helper functions like normalize_meaning_to_function_name, map_to_rust_type, get_type_info_from_node, and process_type_decl_node are unchanged) ...
+    /// The argument of generated example calls for a parameter whose Rust base type is
+    /// `base_rust_type`, and the text it puts in a prompt.
+    fn generate_test_value(
+        &self,
+        param_node: &AstNode,
+        param_name: &str,
+        base_rust_type: &str,
+        func_name: &str,
+    ) -> Result<(String, String)> {
+        if let Some(mapping) = self.mapping_for_rust_type(base_rust_type) {
+            return Ok((mapping.test_value.clone(), mapping.test_value.clone()));
+        }
+        let value = match base_rust_type {
+            "i32" | "i64" | "u32" => ("123".to_string(), "123".to_string()),
+            "f64" => (self.float_format.format_literal(45.6), 45.6.to_string()),
+            "bool" => ("true".to_string(), "true".to_string()),
+            "String" => (
+                "\"Test Topic\".to_string()".to_string(),
+                "Test Topic".to_string(),
+            ),
+            _ => {
+                let (vibe_type, _, _) = self.get_type_info_from_node(&param_node.children[0]);
+                bail!(
+                    SourceError::new(
+                        param_node.line,
+                        format!(
+                            "Parameter `{}` of `{}` has the unknown type `{}`. Declare it with `type {} = ...;` first.",
+                            param_name, func_name, vibe_type, vibe_type
+                        )
+                    )
+                    .with_column(param_node.column)
+                );
+            }
+        };
+        Ok(value)
+    }
+
     fn normalize_meaning_to_function_name(&self, meaning: &str) -> String {
-        meaning
+        let normalized = meaning
             .to_lowercase()
             .chars()
             .map(|c| if c.is_alphanumeric() { c } else { '_' })
@@ -159,12 +930,80 @@ impl CodeGenerator {
             .split('_')
             .filter(|s| !s.is_empty() && !["a", "an", "the", "of", "in"].contains(s))
             .collect::<Vec<_>>()
-            .join("_")
+            .join("_");
+
+        if normalized.is_empty() {
+            // The meaning only contains stopwords or symbols: derive a stable name from its text.
+            let mut hasher = DefaultHasher::new();
+            meaning.hash(&mut hasher);
+            return format!("meaning_{:016x}", hasher.finish());
+        }
+        normalized
+    }
+
+    /// Builds the enum for an inline union return type such as `"yes" | "no"`.
+    ///
+    /// The enum is named after the function, e.g. `classify` returns `ClassifyOutput`.
+    fn build_inline_enum(&self, func_name: &str, union_node: &AstNode) -> Result<InlineEnum> {
+        let enum_name = format!("{}Output", to_pascal_case(func_name));
+        let mut variants: Vec<InlineEnumVariant> = Vec::new();
+
+        for literal in &union_node.children {
+            let value = literal.get_string("value").unwrap().clone();
+            let variant_name = to_pascal_case(&value);
+            if variant_name.is_empty() {
+                bail!(SourceError::new(
+                    union_node.line,
+                    format!(
+                        "Return type of `{}`: \"{}\" cannot be used as an enum variant",
+                        func_name, value
+                    )
+                ));
+            }
+            if let Some(existing) = variants.iter().find(|v| v.name == variant_name) {
+                bail!(SourceError::new(
+                    union_node.line,
+                    format!(
+                        "Return type of `{}`: \"{}\" and \"{}\" both map to the variant `{}`",
+                        func_name, existing.value, value, variant_name
+                    )
+                ));
+            }
+            let match_key = value
+                .trim_matches(|c: char| !c.is_alphanumeric())
+                .to_lowercase();
+            variants.push(InlineEnumVariant {
+                name: variant_name,
+                value,
+                match_key,
+            });
+        }
+
+        Ok(InlineEnum {
+            name: enum_name,
+            variants,
+        })
+    }
+
+    fn mapping_for_rust_type(&self, rust_type: &str) -> Option<&TypeMapping> {
+        self.type_mappings
+            .values()
+            .find(|mapping| mapping.rust_type == rust_type)
+    }
+
+    fn custom_parser(&self, rust_type: &str) -> Option<String> {
+        self.mapping_for_rust_type(rust_type)
+            .map(|mapping| mapping.parser.clone())
     }
 
     fn map_to_rust_type(&self, vibe_type: &str) -> String {
+        if let Some(mapping) = self.type_mappings.get(vibe_type) {
+            return mapping.rust_type.clone();
+        }
         match vibe_type {
             "Int" => "i32".to_string(),
+            "Long" => "i64".to_string(),
+            "UInt" => "u32".to_string(),
             "Float" => "f64".to_string(),
             "String" => "String".to_string(),
             "Bool" => "bool".to_string(),
@@ -189,17 +1028,62 @@ impl CodeGenerator {
         }
     }
 
+    /// Resolves each declared alias to its Rust base type, following aliases of aliases such
+    /// as `type Celsius = Degrees; type Degrees = Int;` in any declaration order.
+    fn resolve_alias_bases(&self, type_decls: &[&AstNode]) -> Result<HashMap<String, String>> {
+        let by_name: HashMap<&str, &AstNode> = type_decls
+            .iter()
+            .map(|decl| (decl.get_string("name").unwrap().as_str(), *decl))
+            .collect();
+
+        let mut alias_bases = HashMap::new();
+        for &decl in type_decls {
+            let name = decl.get_string("name").unwrap();
+            check_no_tuple(&decl.children[0], "type alias", decl.line)?;
+            let mut chain = vec![name.to_string()];
+            let mut current = decl;
+            loop {
+                let (target, base_type, _) = self.get_type_info_from_node(&current.children[0]);
+                let Some(&next) = by_name.get(target.as_str()) else {
+                    alias_bases.insert(name.to_string(), base_type);
+                    break;
+                };
+                let is_cycle = chain.contains(&target);
+                chain.push(target);
+                if is_cycle {
+                    bail!(SourceError::new(
+                        decl.line,
+                        format!(
+                            "Type alias `{}` refers to itself: {}",
+                            name,
+                            chain.join(" -> ")
+                        )
+                    ));
+                }
+                current = next;
+            }
+        }
+        Ok(alias_bases)
+    }
+
     fn process_type_decl_node(
         &self,
         node: &AstNode,
         type_aliases: &mut Vec<TypeAlias>,
-        semantic_meanings: &mut HashMap<String, (String, String)>,
+        semantic_meanings: &mut BTreeMap<String, (String, String)>,
         type_alias_map: &mut HashMap<String, String>,
         type_meaning_map: &mut HashMap<String, String>,
+        type_bounds_map: &mut HashMap<String, Bounds>,
     ) {
         let name = node.get_string("name").unwrap().clone();
         let type_def_node = &node.children[0];
         let (_, base_type, meaning) = self.get_type_info_from_node(type_def_node);
+        // Filled in beforehand by `resolve_alias_bases`, which follows aliases of aliases.
+        let base_type = type_alias_map.get(&name).cloned().unwrap_or(base_type);
+
+        if let Some(bounds) = bounds_of(type_def_node) {
+            type_bounds_map.insert(name.clone(), bounds);
+        }
 
         if let Some(m) = &meaning {
             let normalized = self.normalize_meaning_to_function_name(m);
@@ -212,77 +1096,408 @@ impl CodeGenerator {
             name,
             base_type,
             meaning,
+            unit: unit_of(type_def_node),
+            doc: doc_lines(node),
         });
     }
 
+    /// Resolves a type node to its Rust base type, following declared aliases.
+    fn resolve_base_type(
+        &self,
+        type_node: &AstNode,
+        type_alias_map: &HashMap<String, String>,
+    ) -> String {
+        let (alias, base_type, _) = self.get_type_info_from_node(type_node);
+        type_alias_map.get(&alias).cloned().unwrap_or(base_type)
+    }
+
+    /// Returns the `(name, base type)` pairs of a function's parameters.
+    fn param_base_types(
+        &self,
+        func_node: &AstNode,
+        type_alias_map: &HashMap<String, String>,
+    ) -> Vec<(String, String)> {
+        func_node
+            .children
+            .iter()
+            .filter(|child| child.node_type == AstNodeType::ParamList)
+            .flat_map(|param_list| param_list.children.iter())
+            .map(|param| {
+                (
+                    param.get_string("name").unwrap().clone(),
+                    self.resolve_base_type(&param.children[0], type_alias_map),
+                )
+            })
+            .collect()
+    }
+
+    /// Checks call arguments in function bodies against the called function's parameter types.
+    ///
+    /// This is a light pass: only literals and parameters of the enclosing function are
+    /// checked, so obvious mismatches are reported instead of generating invalid Rust.
+    fn check_call_argument_types(
+        &self,
+        ast: &AstNode,
+        type_alias_map: &HashMap<String, String>,
+    ) -> Result<()> {
+        let functions = ast
+            .children
+            .iter()
+            .filter(|node| node.node_type == AstNodeType::FunctionDecl);
+
+        let signatures: HashMap<String, Vec<(String, String)>> = functions
+            .clone()
+            .map(|func| {
+                (
+                    func.get_string("name").unwrap().clone(),
+                    self.param_base_types(func, type_alias_map),
+                )
+            })
+            .collect();
+
+        for func in functions {
+            let scope: HashMap<String, String> = self
+                .param_base_types(func, type_alias_map)
+                .into_iter()
+                .collect();
+            for child in &func.children {
+                if child.node_type == AstNodeType::Block {
+                    self.check_calls_in_node(child, &signatures, &scope)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_calls_in_node(
+        &self,
+        node: &AstNode,
+        signatures: &HashMap<String, Vec<(String, String)>>,
+        scope: &HashMap<String, String>,
+    ) -> Result<()> {
+        if node.node_type == AstNodeType::CallExpr {
+            let callee = node.get_string("name").unwrap();
+            if let Some(params) = signatures.get(callee) {
+                if params.len() != node.children.len() {
+                    bail!(SourceError::new(
+                        node.line,
+                        format!(
+                            "Type mismatch: `{}` expects {} argument(s), found {}",
+                            callee,
+                            params.len(),
+                            node.children.len()
+                        )
+                    ));
+                }
+                for (arg, (param_name, expected)) in node.children.iter().zip(params) {
+                    let found = match arg.node_type {
+                        AstNodeType::IntLiteral => Some(("i32", "an Int literal".to_string())),
+                        AstNodeType::FloatLiteral => Some(("f64", "a Float literal".to_string())),
+                        AstNodeType::StringLiteral => {
+                            Some(("String", "a String literal".to_string()))
+                        }
+                        AstNodeType::BoolLiteral => Some(("bool", "a Bool literal".to_string())),
+                        AstNodeType::Identifier => {
+                            let name = arg.get_string("name").unwrap();
+                            scope.get(name).map(|found_type| {
+                                (
+                                    found_type.as_str(),
+                                    format!("`{}` of type `{}`", name, found_type),
+                                )
+                            })
+                        }
+                        _ => None,
+                    };
+
+                    if let Some((found_type, description)) = found {
+                        // An integer literal is accepted where any number is expected.
+                        let compatible = found_type == expected
+                            || (found_type == "i32"
+                                && is_numeric(expected)
+                                && arg.node_type == AstNodeType::IntLiteral);
+                        if !compatible {
+                            bail!(SourceError::new(
+                                arg.line,
+                                format!(
+                                    "Type mismatch: argument `{}` of `{}` expects `{}`, found {}",
+                                    param_name, callee, expected, description
+                                )
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+
+        for child in &node.children {
+            self.check_calls_in_node(child, signatures, scope)?;
+        }
+        Ok(())
+    }
+
+    /// Fails when a parameter of `func` has the type of one of `classes`: every parameter is
+    /// put in the prompt as text, which a class has none of.
+    fn check_no_class_params(
+        &self,
+        func: &AstNode,
+        classes: &[Class],
+        type_alias_map: &HashMap<String, String>,
+    ) -> Result<()> {
+        for param_node in func
+            .children
+            .iter()
+            .filter(|child| child.node_type == AstNodeType::ParamList)
+            .flat_map(|list| list.children.iter())
+        {
+            let (param_alias, param_base, _) =
+                self.get_type_info_from_node(&param_node.children[0]);
+            let (_, base_type) = resolve_type(&param_alias, param_base, type_alias_map);
+            if classes.iter().any(|class| class.name == base_type) {
+                bail!(
+                    SourceError::new(
+                        param_node.line,
+                        format!(
+                            "Parameter `{}` of `{}` has the class type `{}`, which cannot be put in a prompt. Pass the fields it needs as parameters instead.",
+                            param_node.get_string("name").unwrap(),
+                            func.get_string("name").unwrap(),
+                            param_alias
+                        )
+                    )
+                    .with_column(param_node.column)
+                );
+            }
+        }
+        Ok(())
+    }
+
     fn process_function_node(
         &self,
         node: &AstNode,
         type_alias_map: &HashMap<String, String>,
         type_meaning_map: &HashMap<String, String>,
+        type_bounds_map: &HashMap<String, Bounds>,
+        type_unit_map: &HashMap<String, String>,
+        style: PlaceholderStyle,
     ) -> Result<Function> {
         let name = node.get_string("name").unwrap().clone();
+        check_rust_identifier(&name, "function", node.line)?;
         let mut params = Vec::new();
         let mut return_type = "()".to_string();
         let mut return_base_type = "()".to_string();
         let mut semantic_meaning = None;
         let mut prompt_template = String::new();
+        let mut inline_enum = None;
+        let mut tuple_types = Vec::new();
+        let mut locals = Vec::new();
+        let mut temperature = None;
+        let mut bounds = None;
+        let mut unit = None;
+        let mut has_prompt = false;
+        let mut has_return_value = false;
 
         for child in &node.children {
             match child.node_type {
                 AstNodeType::ParamList => {
-                    for param_node in &child.children {
+                    for (index, param_node) in child.children.iter().enumerate() {
                         let param_name = param_node.get_string("name").unwrap().clone();
-                        let (param_alias, param_base, _) =
+                        let earlier = child.children[..index]
+                            .iter()
+                            .find(|other| other.get_string("name") == Some(&param_name));
+                        if let Some(earlier) = earlier {
+                            bail!(
+                                SourceError::new(
+                                    earlier.line,
+                                    format!(
+                                        "Parameter `{}` of `{}` is declared twice, again at line {}, column {}",
+                                        param_name, name, param_node.line, param_node.column
+                                    )
+                                )
+                                .with_column(earlier.column)
+                            );
+                        }
+                        check_rust_identifier(&param_name, "parameter", param_node.line)?;
+                        check_no_tuple(&param_node.children[0], "parameter", param_node.line)?;
+                        let (param_alias, param_base, param_meaning) =
                             self.get_type_info_from_node(&param_node.children[0]);
 
-                        let param_rust_type = if type_alias_map.contains_key(&param_alias) {
-                            param_alias
-                        } else {
-                            param_base.clone()
-                        };
-
-                        // UPDATED: Generate a test value for the parameter.
-                        let test_value = self.generate_test_value(&param_base);
+                        let (param_rust_type, test_base) =
+                            resolve_type(&param_alias, param_base, type_alias_map);
+                        let (guard, guard_text) =
+                            self.param_guard(param_node, &param_name, &test_base, &name)?;
+                        // Generate a test value from the resolved base type, so aliases work too.
+                        let (test_value, test_text) =
+                            self.guarded_test_value(param_node, &param_name, &test_base, &name)?;
 
                         params.push(FunctionParam {
                             name: param_name,
                             rust_type: param_rust_type,
                             test_value, // Add the generated value here.
+                            test_text,
+                            meaning: param_meaning,
+                            guard,
+                            guard_text,
                         });
                     }
                 }
-                // ... (rest of the function processing is unchanged) ...
+                AstNodeType::TupleType => {
+                    let mut element_types = Vec::new();
+                    for element in &child.children {
+                        check_no_tuple(element, "tuple element", node.line)?;
+                        let (alias, base, _) = self.get_type_info_from_node(element);
+                        let (rust_type, base_type) = resolve_type(&alias, base, type_alias_map);
+                        if !matches!(
+                            base_type.as_str(),
+                            "i32" | "i64" | "u32" | "f64" | "bool" | "String"
+                        ) {
+                            bail!(SourceError::new(
+                                node.line,
+                                format!(
+                                    "Function `{}` returns a tuple containing `{}`: tuple elements must be Int, Long, UInt, Float, Bool or String",
+                                    name, alias
+                                )
+                            ));
+                        }
+                        element_types.push(rust_type);
+                        tuple_types.push(base_type);
+                    }
+                    // The response is read as text, then split into the elements.
+                    return_type = format!("({})", element_types.join(", "));
+                    return_base_type = "String".to_string();
+                }
                 AstNodeType::BasicType | AstNodeType::MeaningType => {
+                    check_no_tuple(child, "meaning", node.line)?;
                     let (vibe_type_name, initial_base_type, mut direct_meaning) =
                         self.get_type_info_from_node(child);
 
-                    let final_base_type = type_alias_map
-                        .get(&vibe_type_name)
-                        .cloned()
-                        .unwrap_or(initial_base_type);
-
-                    let signature_type = if type_alias_map.contains_key(&vibe_type_name) {
-                        vibe_type_name.clone()
-                    } else {
-                        final_base_type.clone()
-                    };
+                    let (signature_type, final_base_type) =
+                        resolve_type(&vibe_type_name, initial_base_type, type_alias_map);
 
                     return_type = signature_type;
                     return_base_type = final_base_type;
 
                     if direct_meaning.is_none() {
-                        if let Some(inherited_meaning) = type_meaning_map.get(&return_type) {
-                            direct_meaning = Some(inherited_meaning.clone());
-                        }
+                        direct_meaning = type_meaning_map.get(&return_type).cloned();
+                    }
+
+                    // A bare non-String return falls back to generic extraction, which strict
+                    // mode rejects so that a semantic extractor is always selected.
+                    if self.strict_types
+                        && direct_meaning.is_none()
+                        && return_base_type != "String"
+                        && return_base_type != "()"
+                    {
+                        bail!(
+                            SourceError::new(
+                                child.line,
+                                format!(
+                                    "Function `{}` returns `{}` without a meaning, which is not allowed with strict types. Declare it as `Meaning<{}>(\"...\")` or use a type alias that has a meaning.",
+                                    name, vibe_type_name, vibe_type_name
+                                )
+                            )
+                            .with_column(child.column)
+                        );
                     }
                     semantic_meaning = direct_meaning;
+
+                    let return_bounds =
+                        bounds_of(child).or_else(|| type_bounds_map.get(&vibe_type_name).copied());
+                    if let Some(return_bounds) = return_bounds {
+                        if !is_numeric(&return_base_type) {
+                            bail!(
+                                SourceError::new(
+                                    child.line,
+                                    format!(
+                                        "Function `{}` returns `{}`, which has range constraints but is not an Int or Float",
+                                        name, vibe_type_name
+                                    )
+                                )
+                                .with_column(child.column)
+                            );
+                        }
+                        bounds = Some(self.bounds_expression(return_bounds));
+                    }
+
+                    unit = unit_of(child).or_else(|| type_unit_map.get(&vibe_type_name).cloned());
+                    if unit.is_some() && !is_numeric(&return_base_type) {
+                        bail!(
+                            SourceError::new(
+                                child.line,
+                                format!(
+                                    "Function `{}` returns `{}`, which has a unit but is not an Int or Float",
+                                    name, vibe_type_name
+                                )
+                            )
+                            .with_column(child.column)
+                        );
+                    }
+                }
+                AstNodeType::UnionType => {
+                    let union = self.build_inline_enum(&name, child)?;
+                    let values: Vec<&str> =
+                        union.variants.iter().map(|v| v.value.as_str()).collect();
+
+                    // The enum is parsed from the String response; the meaning lists the
+                    // accepted answers for the model.
+                    return_type = union.name.clone();
+                    return_base_type = "String".to_string();
+                    semantic_meaning = Some(format!("one of: {}", values.join(", ")));
+                    inline_enum = Some(union);
                 }
                 AstNodeType::Block => {
+                    // Names a statement can use: the parameters, then each `let` once bound.
+                    let mut declared: Vec<String> =
+                        params.iter().map(|param| param.name.clone()).collect();
                     for stmt in &child.children {
-                        if stmt.node_type == AstNodeType::PromptBlock {
-                            prompt_template = stmt.get_string("template").unwrap().clone();
-                            break;
+                        match stmt.node_type {
+                            AstNodeType::VarDecl => {
+                                check_declared(stmt.children.last().unwrap(), &declared, &name)?;
+                                // Only bindings before the prompt are substituted into it.
+                                if !has_prompt {
+                                    locals.push(self.process_local_binding(stmt, type_alias_map)?);
+                                }
+                                declared.push(stmt.get_string("name").unwrap().clone());
+                            }
+                            AstNodeType::PromptBlock if !has_prompt => {
+                                let template = stmt.get_string("template").unwrap();
+                                for placeholder in style.names_in(template) {
+                                    if !declared.contains(&placeholder) {
+                                        bail!(SourceError::new(
+                                            stmt.line,
+                                            format!(
+                                                "The prompt of `{}` uses `{}`, which is not a parameter or a `let` binding declared before it",
+                                                name,
+                                                style.placeholder(&placeholder)
+                                            )
+                                        ));
+                                    }
+                                }
+                                // Triple-quoted prompts may contain quotes and backslashes, which
+                                // must be escaped in the generated string literal.
+                                prompt_template =
+                                    template.replace('\\', "\\\\").replace('"', "\\\"");
+                                has_prompt = true;
+                            }
+                            AstNodeType::ReturnStmt if !stmt.children.is_empty() => {
+                                check_declared(&stmt.children[0], &declared, &name)?;
+                                has_return_value = true;
+                            }
+                            AstNodeType::ExprStmt => {
+                                check_declared(&stmt.children[0], &declared, &name)?;
+                            }
+                            AstNodeType::TempStmt => {
+                                let value = stmt.get_float("value").unwrap();
+                                if !(0.0..=2.0).contains(&value) {
+                                    bail!(SourceError::new(
+                                        stmt.line,
+                                        format!(
+                                            "Invalid temperature {} in `{}`: expected a value between 0 and 2",
+                                            value, name
+                                        )
+                                    ));
+                                }
+                                temperature = Some(format!("{:?}", value));
+                            }
+                            _ => {}
                         }
                     }
                 }
@@ -290,13 +1505,994 @@ impl CodeGenerator {
             }
         }
 
+        if has_prompt && let Some(length) = node.get_string("length") {
+            prompt_template.push(' ');
+            prompt_template.push_str(length_instruction(length));
+        }
+
+        if has_prompt && let Some(unit) = &unit {
+            prompt_template.push_str(&format!(" Reply in {}.", unit));
+        }
+
+        if has_prompt && !tuple_types.is_empty() {
+            let descriptions: Vec<&str> = tuple_types
+                .iter()
+                .map(|base_type| match base_type.as_str() {
+                    "i32" | "i64" => "an integer",
+                    "u32" => "a whole number of 0 or more",
+                    "f64" => "a number",
+                    "bool" => "true or false",
+                    _ => "a string",
+                })
+                .collect();
+            prompt_template.push_str(&format!(
+                " Reply with only a JSON array of {} values: {}.",
+                descriptions.len(),
+                descriptions.join(", then ")
+            ));
+        }
+
+        // Without either there is nothing to produce a value, and the generated function
+        // would send an empty prompt.
+        if !has_prompt && !has_return_value {
+            bail!(SourceError::new(
+                node.line,
+                format!(
+                    "Function `{}` has no prompt and no return expression. Add a `prompt \"...\";` statement to its body.",
+                    name
+                )
+            ));
+        }
+
+        let mut confidence_type = None;
+        if node.get_bool("with_confidence").unwrap_or(false) {
+            if !has_prompt || return_base_type == "()" {
+                bail!(SourceError::new(
+                    node.line,
+                    format!(
+                        "`@with_confidence` on `{}` needs a prompt and a return type: the confidence is asked of the model along with its answer",
+                        name
+                    )
+                ));
+            }
+            if self.external_runtime {
+                bail!(SourceError::new(
+                    node.line,
+                    format!(
+                        "`@with_confidence` on `{}` cannot be used with an external runtime, which has no `vibe_execute_prompt_with_confidence`",
+                        name
+                    )
+                ));
+            }
+            prompt_template.push_str(
+                " On a last line of its own, write Confidence: and how sure you are of the answer, from 0 to 1.",
+            );
+            let confident = format!("vibelang::runtime::confidence::Confident<{}>", return_type);
+            confidence_type = Some(std::mem::replace(&mut return_type, confident));
+        }
+
+        let meaning_params = semantic_meaning
+            .as_deref()
+            .map(|meaning| style.names_in(meaning))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|placeholder| params.iter().any(|param| &param.name == placeholder))
+            .collect();
+
+        let has_param_meanings = params.iter().any(|param| param.meaning.is_some());
+
+        let settings = self
+            .function_settings
+            .get(&name)
+            .cloned()
+            .unwrap_or_default();
+        if let Some(value) = settings.temperature {
+            if !(0.0..=2.0).contains(&value) {
+                bail!(SourceError::new(
+                    node.line,
+                    format!(
+                        "Invalid temperature {} in the settings of `{}`: expected a value between 0 and 2",
+                        value, name
+                    )
+                ));
+            }
+            // Written exactly, like a `temperature` statement.
+            temperature = Some(format!("{:?}", value));
+        }
+        let timeout_secs = node.get_int("timeout");
+        if let Some(secs) = timeout_secs
+            && secs < 1
+        {
+            bail!(SourceError::new(
+                node.line,
+                format!(
+                    "Invalid timeout {} in `{}`: expected at least 1 second",
+                    secs, name
+                )
+            ));
+        }
+        let retries = node.get_int("retries");
+        if let Some(retries) = retries
+            && retries < 0
+        {
+            bail!(SourceError::new(
+                node.line,
+                format!(
+                    "Invalid retries {} in `{}`: expected 0 or more",
+                    retries, name
+                )
+            ));
+        }
+
+        let mut client_overrides = String::new();
+        if let Some(model) = &settings.model {
+            client_overrides.push_str(&format!(".with_model({:?})", model));
+        }
+        if !settings.stop.is_empty() {
+            let stop: Vec<String> = settings
+                .stop
+                .iter()
+                .map(|sequence| format!("{:?}", sequence))
+                .collect();
+            client_overrides.push_str(&format!(".with_stop(&[{}])", stop.join(", ")));
+        }
+        if let Some(secs) = timeout_secs {
+            client_overrides.push_str(&format!(".with_timeout_secs({})", secs));
+        }
+        if let Some(retries) = retries {
+            client_overrides.push_str(&format!(".with_retries({})", retries));
+        }
+
         Ok(Function {
             name,
             params,
             return_type,
-            return_base_type,
             semantic_meaning,
+            meaning_params,
+            has_param_meanings,
             prompt_template,
+            locals,
+            temperature,
+            bounds,
+            unit,
+            is_async: self.is_async(node),
+            inline_enum,
+            return_parser: self.custom_parser(&return_base_type),
+            tuple_types,
+            confidence_type,
+            client_overrides,
+            return_base_type,
+            doc: doc_lines(node),
+        })
+    }
+
+    /// Writes `bounds` as a Rust expression for the generated code, with the bounds exactly
+    /// as declared.
+    fn bounds_expression(&self, bounds: Bounds) -> String {
+        format!(
+            "vibelang::runtime::validation::Bounds {{ min: {:?}, max: {:?} }}",
+            bounds.min, bounds.max
+        )
+    }
+
+    /// Builds a `let` binding, typed like a parameter: a declared alias keeps its name and
+    /// anything else uses the resolved Rust type.
+    fn process_local_binding(
+        &self,
+        node: &AstNode,
+        type_alias_map: &HashMap<String, String>,
+    ) -> Result<LocalBinding> {
+        let name = node.get_string("name").unwrap().clone();
+        check_rust_identifier(&name, "variable", node.line)?;
+        let (type_node, value_node) = match node.children.as_slice() {
+            [value] => (None, value),
+            [type_node, value] => (Some(type_node), value),
+            _ => bail!(SourceError::new(
+                node.line,
+                format!("Malformed `let {}`", name)
+            )),
+        };
+
+        let mut rust_type = None;
+        let mut base_type = None;
+        if let Some(type_node) = type_node {
+            let (vibe_type, initial_base_type, _) = self.get_type_info_from_node(type_node);
+            if !self.is_known_type(&vibe_type, type_alias_map) {
+                bail!(SourceError::new(
+                    node.line,
+                    format!(
+                        "Unknown type `{}` for `let {}`. Declare it with `type {} = ...;` first.",
+                        vibe_type, name, vibe_type
+                    )
+                ));
+            }
+            let (signature_type, resolved) =
+                resolve_type(&vibe_type, initial_base_type, type_alias_map);
+            rust_type = Some(signature_type);
+            base_type = Some(resolved);
+        }
+
+        let copies = match value_node.node_type {
+            AstNodeType::Identifier => value_node.get_string("name").cloned(),
+            _ => None,
+        };
+        let value = match &copies {
+            Some(copied) => format!("{}.clone()", copied),
+            None => match self.literal_value(value_node, base_type.as_deref()) {
+                Some(value) => value,
+                None => bail!(SourceError::new(
+                    node.line,
+                    format!(
+                        "Unsupported value for `let {}`: only literals and variables can be bound before the prompt",
+                        name
+                    )
+                )),
+            },
+        };
+
+        Ok(LocalBinding {
+            name,
+            rust_type,
+            value,
+            text: constant_text(value_node),
+            copies,
+        })
+    }
+
+    /// Builds the struct for a `class`, with a `Default` impl when any field has a default.
+    fn process_class_node(
+        &self,
+        node: &AstNode,
+        type_alias_map: &HashMap<String, String>,
+    ) -> Result<Class> {
+        let class_name = node.get_string("name").unwrap().clone();
+        let mut fields = Vec::new();
+        let mut has_defaults = false;
+
+        for member in &node.children {
+            if member.node_type == AstNodeType::FunctionDecl {
+                bail!(SourceError::new(
+                    member.line,
+                    format!(
+                        "Methods are not supported yet: `fn {}` in class `{}`. Declare it at the top level instead.",
+                        member.get_string("name").unwrap(),
+                        class_name
+                    )
+                ));
+            }
+
+            let name = member.get_string("name").unwrap().clone();
+            check_rust_identifier(&name, "field", member.line)?;
+            let type_node = &member.children[0];
+            check_no_tuple(type_node, "class field", member.line)?;
+            let (vibe_type, initial_base_type, _) = self.get_type_info_from_node(type_node);
+            if !self.is_known_type(&vibe_type, type_alias_map) {
+                bail!(SourceError::new(
+                    member.line,
+                    format!(
+                        "Unknown type `{}` for field `{}` of class `{}`. Declare it with `type {} = ...;` first.",
+                        vibe_type, name, class_name, vibe_type
+                    )
+                ));
+            }
+            let (rust_type, base_type) =
+                resolve_type(&vibe_type, initial_base_type, type_alias_map);
+
+            let default = match member.children.get(1) {
+                Some(value_node) => {
+                    has_defaults = true;
+                    self.literal_value(value_node, Some(&base_type))
+                        .ok_or_else(|| {
+                            SourceError::new(
+                                member.line,
+                                format!(
+                                    "Unsupported default for field `{}` of class `{}`: only literals can be used",
+                                    name, class_name
+                                ),
+                            )
+                        })?
+                }
+                None => "Default::default()".to_string(),
+            };
+
+            fields.push(ClassField {
+                name,
+                rust_type,
+                default,
+                doc: doc_lines(member),
+            });
+        }
+
+        Ok(Class {
+            name: class_name,
+            fields,
+            has_defaults,
+            doc: doc_lines(node),
         })
     }
+
+    /// Whether a type written in a declaration is built in, declared or registered.
+    fn is_known_type(&self, vibe_type: &str, type_alias_map: &HashMap<String, String>) -> bool {
+        type_alias_map.contains_key(vibe_type)
+            || self.type_mappings.contains_key(vibe_type)
+            || matches!(
+                vibe_type,
+                "Int" | "Long" | "UInt" | "Float" | "String" | "Bool"
+            )
+    }
+
+    /// Renders a literal as a Rust expression of `base_type`, or `None` for other nodes.
+    fn literal_value(&self, value_node: &AstNode, base_type: Option<&str>) -> Option<String> {
+        let value = match value_node.node_type {
+            AstNodeType::StringLiteral => {
+                format!(
+                    "\"{}\".to_string()",
+                    value_node.get_string("value").unwrap()
+                )
+            }
+            // An integer literal is accepted where a float is expected.
+            AstNodeType::IntLiteral if base_type == Some("f64") => {
+                format!("{}.0", value_node.get_int("value").unwrap())
+            }
+            AstNodeType::IntLiteral => value_node.get_int("value").unwrap().to_string(),
+            // Written exactly, so that the value parses back unchanged whatever the FloatFormat.
+            AstNodeType::FloatLiteral => format!("{:?}", value_node.get_float("value").unwrap()),
+            AstNodeType::BoolLiteral => value_node.get_bool("value").unwrap().to_string(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    /// Renders the `where` comparisons of a parameter whose Rust base type is `base_type` as a
+    /// condition, e.g. `age >= 0 && age < 150`, and as written in the source.
+    fn param_guard(
+        &self,
+        param_node: &AstNode,
+        param_name: &str,
+        base_type: &str,
+        func_name: &str,
+    ) -> Result<(Option<String>, Option<String>)> {
+        let mut conditions = Vec::new();
+        let mut texts = Vec::new();
+        for comparison in param_node
+            .children
+            .iter()
+            .filter(|child| child.node_type == AstNodeType::Comparison)
+        {
+            let op = comparison.get_string("op").unwrap();
+            let subject = comparison.children[0].get_string("name").unwrap();
+            let value = &comparison.children[1];
+            let text = match value.node_type {
+                AstNodeType::StringLiteral => {
+                    format!("{} {} \"{}\"", subject, op, constant_text(value))
+                }
+                _ => format!("{} {} {}", subject, op, constant_text(value)),
+            };
+            if subject != param_name {
+                bail!(SourceError::new(
+                    comparison.line,
+                    format!(
+                        "The check `{}` on parameter `{}` of `{}` must compare `{}` itself",
+                        text, param_name, func_name, param_name
+                    )
+                ));
+            }
+            let comparable = match value.node_type {
+                AstNodeType::IntLiteral => is_numeric(base_type),
+                AstNodeType::FloatLiteral => base_type == "f64",
+                AstNodeType::StringLiteral => {
+                    base_type == "String" && matches!(op.as_str(), "==" | "!=")
+                }
+                AstNodeType::BoolLiteral => {
+                    base_type == "bool" && matches!(op.as_str(), "==" | "!=")
+                }
+                _ => false,
+            };
+            if !comparable {
+                bail!(SourceError::new(
+                    comparison.line,
+                    format!(
+                        "The check `{}` on parameter `{}` of `{}` does not fit its type: numbers can be ordered, strings and booleans only compared with `==` or `!=`",
+                        text, param_name, func_name
+                    )
+                ));
+            }
+            // A String is compared with the literal as written, without allocating.
+            let literal = match value.node_type {
+                AstNodeType::StringLiteral => format!("\"{}\"", constant_text(value)),
+                _ => self.literal_value(value, Some(base_type)).unwrap(),
+            };
+            conditions.push(format!("{} {} {}", param_name, op, literal));
+            texts.push(text);
+        }
+        if conditions.is_empty() {
+            return Ok((None, None));
+        }
+        // The text goes into the format string of the generated `assert!`.
+        let text = texts
+            .join(" && ")
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('{', "{{")
+            .replace('}', "}}");
+        Ok((Some(conditions.join(" && ")), Some(text)))
+    }
+
+    /// The test value of a parameter, as `generate_test_value` gives it, unless the parameter's
+    /// `where` checks reject it: then the first value close to the compared literals that
+    /// passes them, so that the generated example calls do not panic.
+    fn guarded_test_value(
+        &self,
+        param_node: &AstNode,
+        param_name: &str,
+        base_type: &str,
+        func_name: &str,
+    ) -> Result<(String, String)> {
+        let checks: Vec<(&str, &AstNode)> = param_node
+            .children
+            .iter()
+            .filter(|child| child.node_type == AstNodeType::Comparison)
+            .map(|comparison| {
+                let op = comparison.get_string("op").unwrap().as_str();
+                (op, comparison.children[1].as_ref())
+            })
+            .collect();
+        let default = self.generate_test_value(param_node, param_name, base_type, func_name)?;
+        if checks.is_empty() {
+            return Ok(default);
+        }
+
+        let found = match base_type {
+            "String" => {
+                let passes = |text: &String| {
+                    checks
+                        .iter()
+                        .all(|(op, value)| compares(text, op, &constant_text(value)))
+                };
+                let candidates = ["Test Topic", "Other Topic"]
+                    .into_iter()
+                    .map(str::to_string)
+                    .chain(checks.iter().map(|(_, value)| constant_text(value)));
+                candidates
+                    .filter(passes)
+                    .map(|text| (format!("{:?}.to_string()", text), text))
+                    .next()
+            }
+            "bool" => [true, false]
+                .into_iter()
+                .find(|candidate| {
+                    checks.iter().all(|(op, value)| {
+                        compares(candidate, op, &value.get_bool("value").unwrap())
+                    })
+                })
+                .map(|value| (value.to_string(), value.to_string())),
+            _ => {
+                let literals: Vec<f64> = checks
+                    .iter()
+                    .map(|(_, value)| match value.node_type {
+                        AstNodeType::FloatLiteral => value.get_float("value").unwrap(),
+                        _ => value.get_int("value").unwrap() as f64,
+                    })
+                    .collect();
+                let (default_number, min, max) = match base_type {
+                    "f64" => (45.6, f64::MIN, f64::MAX),
+                    "i64" => (123.0, i64::MIN as f64, i64::MAX as f64),
+                    "u32" => (123.0, 0.0, u32::MAX as f64),
+                    _ => (123.0, i32::MIN as f64, i32::MAX as f64),
+                };
+                // Next to each literal, and for floats between any two, lies a passing value
+                // whenever there is one.
+                let mut candidates = vec![default_number];
+                for &literal in &literals {
+                    candidates.extend([literal, literal + 1.0, literal - 1.0]);
+                    if base_type == "f64" {
+                        candidates.extend(literals.iter().map(|other| (literal + other) / 2.0));
+                    }
+                }
+                candidates
+                    .into_iter()
+                    .find(|candidate| {
+                        (min..=max).contains(candidate)
+                            && checks
+                                .iter()
+                                .zip(&literals)
+                                .all(|((op, _), literal)| compares(candidate, op, literal))
+                    })
+                    .map(|number| match base_type {
+                        "f64" => (format!("{:?}", number), number.to_string()),
+                        _ => ((number as i64).to_string(), (number as i64).to_string()),
+                    })
+            }
+        };
+        match found {
+            Some(value) if value.1 == default.1 => Ok(default),
+            Some(value) => Ok(value),
+            None => bail!(SourceError::new(
+                param_node.line,
+                format!(
+                    "No value passes the checks on parameter `{}` of `{}`: they contradict each other",
+                    param_name, func_name
+                )
+            )),
+        }
+    }
+}
+
+/// Whether `value` compares to `literal` as `op`, one of the operators of a `where` check.
+fn compares<T: PartialOrd>(value: &T, op: &str, literal: &T) -> bool {
+    match op {
+        "<" => value < literal,
+        "<=" => value <= literal,
+        ">" => value > literal,
+        ">=" => value >= literal,
+        "==" => value == literal,
+        _ => value != literal,
+    }
+}
+
+/// Whether values of the Rust base type are numbers: `Int`, `Long`, `UInt` or `Float`.
+fn is_numeric(rust_type: &str) -> bool {
+    matches!(rust_type, "i32" | "i64" | "u32" | "f64")
+}
+
+/// Fails when `expr` uses a variable that is not in `declared`, pointing at the use, rather
+/// than generating Rust that cannot find it.
+fn check_declared(expr: &AstNode, declared: &[String], func_name: &str) -> Result<()> {
+    if expr.node_type == AstNodeType::Identifier {
+        let name = expr.get_string("name").unwrap();
+        if !declared.contains(name) {
+            bail!(
+                SourceError::new(
+                    expr.line,
+                    format!(
+                        "`{}` is used in `{}` before it is declared: only parameters and earlier `let` bindings can be used",
+                        name, func_name
+                    )
+                )
+                .with_column(expr.column)
+            );
+        }
+    }
+    for child in &expr.children {
+        check_declared(child, declared, func_name)?;
+    }
+    Ok(())
+}
+
+/// Fails when `name` cannot be written as a Rust identifier, e.g. a parameter declared as
+/// `r#type`: VibeLang reserved words can be escaped, but Rust keywords are still reserved.
+fn check_rust_identifier(name: &str, kind: &str, line: usize) -> Result<()> {
+    if syn::parse_str::<syn::Ident>(name).is_err() {
+        bail!(SourceError::new(
+            line,
+            format!(
+                "The {} `{}` is a reserved word in Rust; choose another name, such as `{}_`",
+                kind,
+                name.trim_start_matches("r#"),
+                name.trim_start_matches("r#")
+            )
+        ));
+    }
+    Ok(())
+}
+
+// Types the generated code uses by name, which a declared type of the same name would shadow.
+const SHADOWED_TYPES: &[&str] = &[
+    "String",
+    "Option",
+    "Result",
+    "Vec",
+    "Box",
+    "Default",
+    "LlmClient",
+    "VibeValue",
+];
+
+/// Fails when a declared type or class cannot be used as a Rust type name in the generated
+/// code: a Rust keyword, or a type the generated code itself refers to.
+fn check_type_name(node: &AstNode, kind: &str) -> Result<()> {
+    let name = node.get_string("name").unwrap();
+    check_rust_identifier(name, kind, node.line)?;
+    if SHADOWED_TYPES.contains(&name.as_str()) {
+        bail!(SourceError::new(
+            node.line,
+            format!(
+                "The {} `{}` would shadow Rust's `{}` in the generated code; choose another name, such as `My{}`",
+                kind, name, name, name
+            )
+        ));
+    }
+    Ok(())
+}
+
+/// Fails if a tuple type is used other than as a function's return type.
+fn check_no_tuple(type_node: &AstNode, kind: &str, line: usize) -> Result<()> {
+    if type_node.node_type == AstNodeType::TupleType {
+        bail!(SourceError::new(
+            line,
+            format!(
+                "A tuple type is not allowed as a {}: tuples are only supported as function return types",
+                kind
+            )
+        ));
+    }
+    type_node
+        .children
+        .iter()
+        .try_for_each(|child| check_no_tuple(child, kind, line))
+}
+
+/// The instruction appended to the prompt of a function annotated `@concise` or `@detailed`.
+fn length_instruction(length: &str) -> &'static str {
+    match length {
+        "concise" => "Answer in one sentence.",
+        _ => "Answer in detail, over a few paragraphs.",
+    }
+}
+
+/// The Rust path of an `import` declaration, checked so that it makes a valid `use`.
+fn use_path(node: &AstNode) -> Result<String> {
+    let path = node.get_string("path").cloned().unwrap_or_default();
+    if syn::parse_str::<syn::Path>(&path).is_err() {
+        bail!(SourceError::new(
+            node.line,
+            format!(
+                "The import `{}` is not a Rust path such as `std::collections::HashMap`",
+                path
+            )
+        ));
+    }
+    Ok(path)
+}
+
+/// A copy of `ast` with each `const` folded into the places that use it: a `{NAME}`
+/// placeholder in a prompt becomes the constant's text, and a `NAME` expression its literal.
+/// Within a function, a parameter or `let` binding of the same name takes precedence.
+fn fold_constants(ast: &AstNode, style: PlaceholderStyle) -> Result<AstNode> {
+    let mut constants: HashMap<&str, AstNode> = HashMap::new();
+    for node in ast
+        .children
+        .iter()
+        .filter(|node| node.node_type == AstNodeType::ConstDecl)
+    {
+        let name = node.get_string("name").unwrap();
+        let mut value = (*node.children[0]).clone();
+        if value.node_type == AstNodeType::Identifier {
+            let other = value.get_string("name").unwrap();
+            value = constants.get(other.as_str()).cloned().ok_or_else(|| {
+                SourceError::new(
+                    node.line,
+                    format!(
+                        "The constant `{}` refers to `{}`, which is not a constant declared before it",
+                        name, other
+                    ),
+                )
+            })?;
+        }
+        if constants.insert(name, value).is_some() {
+            bail!(SourceError::new(
+                node.line,
+                format!("The constant `{}` is declared twice", name)
+            ));
+        }
+    }
+
+    let mut folded = ast.clone();
+    if constants.is_empty() {
+        return Ok(folded);
+    }
+    for node in &mut folded.children {
+        match node.node_type {
+            AstNodeType::FunctionDecl => fold_function_constants(node, &constants, style),
+            AstNodeType::ClassDecl => fold_expression_constants(node, &constants),
+            _ => {}
+        }
+    }
+    Ok(folded)
+}
+
+fn fold_function_constants(
+    func: &mut AstNode,
+    constants: &HashMap<&str, AstNode>,
+    style: PlaceholderStyle,
+) {
+    let bindings: Vec<String> = func
+        .children
+        .iter()
+        .flat_map(|child| child.children.iter())
+        .filter(|node| {
+            matches!(
+                node.node_type,
+                AstNodeType::Parameter | AstNodeType::VarDecl
+            )
+        })
+        .filter_map(|node| node.get_string("name").cloned())
+        .collect();
+    let visible: HashMap<&str, AstNode> = constants
+        .iter()
+        .filter(|(name, _)| !bindings.iter().any(|binding| binding == *name))
+        .map(|(name, value)| (*name, value.clone()))
+        .collect();
+
+    for block in func
+        .children
+        .iter_mut()
+        .filter(|child| child.node_type == AstNodeType::Block)
+    {
+        for stmt in &mut block.children {
+            if stmt.node_type != AstNodeType::PromptBlock {
+                fold_expression_constants(stmt, &visible);
+                continue;
+            }
+            let mut template = stmt.get_string("template").unwrap().clone();
+            for placeholder in style.names_in(&template) {
+                if let Some(value) = visible.get(placeholder.as_str()) {
+                    template =
+                        template.replace(&style.placeholder(&placeholder), &constant_text(value));
+                }
+            }
+            stmt.set_string("template", &template);
+        }
+    }
+}
+
+/// Replaces each identifier below `node` that names a constant with the constant's literal.
+fn fold_expression_constants(node: &mut AstNode, constants: &HashMap<&str, AstNode>) {
+    for child in &mut node.children {
+        let value = match child.node_type {
+            AstNodeType::Identifier => constants.get(child.get_string("name").unwrap().as_str()),
+            _ => None,
+        };
+        match value {
+            Some(value) => {
+                let (line, column) = (child.line, child.column);
+                **child = value.clone();
+                child.line = line;
+                child.column = column;
+            }
+            None => fold_expression_constants(child, constants),
+        }
+    }
+}
+
+/// The text a literal puts in a prompt, as a constant or `let` binding.
+fn constant_text(value: &AstNode) -> String {
+    match value.node_type {
+        AstNodeType::StringLiteral => value.get_string("value").cloned().unwrap_or_default(),
+        AstNodeType::IntLiteral => value.get_int("value").unwrap().to_string(),
+        AstNodeType::FloatLiteral => value.get_float("value").unwrap().to_string(),
+        AstNodeType::BoolLiteral => value.get_bool("value").unwrap().to_string(),
+        _ => String::new(),
+    }
+}
+
+/// The text each parameter and `let` binding of `func` puts in its prompt: the argument in
+/// `args` if there is one, and otherwise the example value.
+fn binding_texts(func: &tera::Value, args: &HashMap<String, String>) -> HashMap<String, String> {
+    let mut texts: HashMap<String, String> = HashMap::new();
+    let params = func["params"].as_array().cloned().unwrap_or_default();
+    let locals = func["locals"].as_array().cloned().unwrap_or_default();
+    for param in &params {
+        let name = param["name"].as_str().unwrap_or_default().to_string();
+        let text = match args.get(&name) {
+            Some(arg) => arg.clone(),
+            None => param["test_text"].as_str().unwrap_or_default().to_string(),
+        };
+        texts.insert(name, text);
+    }
+    for local in &locals {
+        let name = local["name"].as_str().unwrap_or_default().to_string();
+        let text = match local["copies"].as_str() {
+            Some(copied) => texts.get(copied).cloned().unwrap_or_default(),
+            None => local["text"].as_str().unwrap_or_default().to_string(),
+        };
+        texts.insert(name, text);
+    }
+    texts
+}
+
+/// The prompt of `func` with `texts` substituted and its parameter meanings appended, as the
+/// generated code builds it. `None` for functions without a prompt.
+fn filled_prompt(
+    func: &tera::Value,
+    texts: &HashMap<String, String>,
+    style: PlaceholderStyle,
+) -> Option<String> {
+    let template = func["prompt_template"].as_str().unwrap_or_default();
+    if template.is_empty() {
+        return None;
+    }
+    // Undoes the escaping of the generated string literal.
+    let mut prompt = String::with_capacity(template.len());
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        prompt.push(if c == '\\' {
+            chars.next().unwrap_or(c)
+        } else {
+            c
+        });
+    }
+    let params = func["params"].as_array().cloned().unwrap_or_default();
+    let locals = func["locals"].as_array().cloned().unwrap_or_default();
+    for binding in params.iter().chain(&locals) {
+        let name = binding["name"].as_str().unwrap_or_default();
+        prompt = prompt.replace(&style.placeholder(name), &texts[name]);
+    }
+
+    let meanings: Vec<String> = params
+        .iter()
+        .filter_map(|param| {
+            let name = param["name"].as_str()?;
+            let meaning = param["meaning"].as_str()?;
+            Some(format!("\n- {} ({}): {}", name, meaning, texts[name]))
+        })
+        .collect();
+    if !meanings.is_empty() {
+        prompt.push_str("\n\nParameter meanings:");
+        prompt.push_str(&meanings.concat());
+    }
+    Some(prompt)
+}
+
+/// Renders a template, reporting the template and context on failure.
+fn render_template(templates: &Tera, template_name: &str, context: &Context) -> Result<String> {
+    templates.render(template_name, context).map_err(|e| {
+        let count = |name: &str| {
+            context
+                .get(name)
+                .and_then(|value| value.as_array())
+                .map_or(0, Vec::len)
+        };
+        eprintln!("Template rendering error: {}", e);
+        eprintln!("Template name: {}", template_name);
+        eprintln!(
+            "Context variables: type_aliases={}, functions={}, semantic_type_groups={}",
+            count("type_aliases"),
+            count("functions"),
+            count("semantic_type_groups")
+        );
+        e.into()
+    })
+}
+
+/// The type declarations of other modules that `ast` uses without declaring them itself.
+fn imported_types<'a>(
+    module_name: &str,
+    ast: &AstNode,
+    modules: &'a [(String, AstNode)],
+) -> Vec<(&'a str, &'a AstNode)> {
+    let mut used = Vec::new();
+    collect_type_names(ast, &mut used);
+
+    // An imported alias of another alias needs that one too, to resolve its base type.
+    let mut imports: Vec<(&str, &AstNode)> = Vec::new();
+    let mut next = 0;
+    while next < used.len() {
+        let type_name = used[next].clone();
+        next += 1;
+        if find_type_decl(ast, &type_name).is_some() {
+            continue;
+        }
+        let owner = modules
+            .iter()
+            .filter(|(name, _)| name != module_name)
+            .find_map(|(name, program)| {
+                find_type_decl(program, &type_name).map(|decl| (name.as_str(), decl))
+            });
+        if let Some((owner_name, decl)) = owner {
+            collect_type_names(decl, &mut used);
+            imports.push((owner_name, decl));
+        }
+    }
+    imports
+}
+
+fn find_type_decl<'a>(program: &'a AstNode, type_name: &str) -> Option<&'a AstNode> {
+    program.children.iter().map(|node| &**node).find(|node| {
+        node.node_type == AstNodeType::TypeDecl
+            && node.get_string("name").map(String::as_str) == Some(type_name)
+    })
+}
+
+// Collects the names written as basic types, e.g. `City` in `fn f(c: City)`, in source order.
+fn collect_type_names(node: &AstNode, names: &mut Vec<String>) {
+    if node.node_type == AstNodeType::BasicType
+        && let Some(name) = node.get_string("type")
+        && !names.contains(name)
+    {
+        names.push(name.clone());
+    }
+    for child in &node.children {
+        collect_type_names(child, names);
+    }
+}
+
+/// Returns the bounds declared on a `Meaning<...>(..., range: ...)` type node, if any.
+fn bounds_of(type_node: &AstNode) -> Option<Bounds> {
+    if type_node.node_type != AstNodeType::MeaningType {
+        return None;
+    }
+    let bounds = Bounds {
+        min: type_node.get_float("min"),
+        max: type_node.get_float("max"),
+    };
+    (bounds != Bounds::default()).then_some(bounds)
+}
+
+/// The lines of the `///` comment the parser attached to a declaration, if any.
+fn doc_lines(node: &AstNode) -> Vec<String> {
+    node.get_string("doc")
+        .map(|doc| doc.lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// The unit declared on a meaning with `unit: "..."`.
+fn unit_of(type_node: &AstNode) -> Option<String> {
+    if type_node.node_type != AstNodeType::MeaningType {
+        return None;
+    }
+    type_node.get_string("unit").cloned()
+}
+
+/// Maps each declared alias to the unit of its meaning, following aliases of aliases.
+fn type_units(type_decls: &[&AstNode]) -> HashMap<String, String> {
+    let by_name: HashMap<&str, &AstNode> = type_decls
+        .iter()
+        .map(|decl| (decl.get_string("name").unwrap().as_str(), *decl))
+        .collect();
+
+    let mut units = HashMap::new();
+    for &decl in type_decls {
+        let mut current = decl;
+        // Cycles are rejected by `resolve_alias_bases`; the limit only guards against them.
+        for _ in 0..type_decls.len() {
+            let type_node = &current.children[0];
+            if let Some(unit) = unit_of(type_node) {
+                units.insert(decl.get_string("name").unwrap().clone(), unit);
+                break;
+            }
+            let target = type_node.get_string("type").map(String::as_str);
+            match target.and_then(|target| by_name.get(target)) {
+                Some(&next) if type_node.node_type == AstNodeType::BasicType => current = next,
+                _ => break,
+            }
+        }
+    }
+    units
+}
+
+/// Resolves a VibeLang type to the type written in generated signatures and its base type.
+///
+/// A declared alias keeps its name, since a `pub type` is emitted for it; any other type is
+/// written as its mapped Rust type.
+fn resolve_type(
+    vibe_type: &str,
+    base_type: String,
+    type_alias_map: &HashMap<String, String>,
+) -> (String, String) {
+    match type_alias_map.get(vibe_type) {
+        Some(resolved) => (vibe_type.to_string(), resolved.clone()),
+        None => (base_type.clone(), base_type),
+    }
+}
+
+/// Converts text such as `very_positive` or `not sure` into `VeryPositive` and `NotSure`.
+///
+/// A leading digit gets a `V` prefix so the result is a valid identifier.
+fn to_pascal_case(text: &str) -> String {
+    let pascal: String = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_uppercase();
+            first.chain(chars).collect::<String>()
+        })
+        .collect();
+
+    if pascal.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("V{}", pascal)
+    } else {
+        pascal
+    }
 }
@@ -0,0 +1,241 @@
+use crate::compiler::SourceError;
+use anyhow::{Result, bail};
+use std::borrow::Cow;
+
+/// The kind of a lexical token in VibeLang source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A reserved word such as `fn`, `type` or `prompt`.
+    Keyword,
+    Identifier,
+    /// A `"..."` or `"""..."""` literal, including its quotes.
+    String,
+    Int,
+    Float,
+    /// `true` or `false`.
+    Bool,
+    /// Punctuation such as `{`, `->` or `..`.
+    Punct,
+    /// A `//` comment, up to the end of the line.
+    Comment,
+}
+
+/// A token with its text and the 1-based line and column where it starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+const KEYWORDS: &[&str] = &[
+    "fn",
+    "async",
+    "type",
+    "class",
+    "import",
+    "const",
+    "let",
+    "where",
+    "return",
+    "prompt",
+    "temperature",
+    "Meaning",
+];
+
+// Longer punctuation first, so `->` is not read as `-` and `>`.
+const PUNCTUATION: &[&str] = &[
+    "->", "..", ">=", "<=", "==", "!=", "&&", "{", "}", "(", ")", "<", ">", ",", ";", ":", "=",
+    "|", "@",
+];
+
+/// Splits VibeLang source into tokens, keeping comments and dropping whitespace.
+///
+/// Tokens are lexical only: the source is not checked against the grammar, which is what
+/// `parse_source` does. Fails on a character that starts no token or an unterminated string.
+pub fn tokenize(source: &str) -> Result<Vec<Token>> {
+    let source = normalize_line_endings(source);
+    let mut tokens = Vec::new();
+    let mut rest = source.as_ref();
+    let (mut line, mut column) = (1, 1);
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            advance(&mut rest, c.len_utf8(), &mut line, &mut column);
+            continue;
+        }
+
+        let (kind, len) = if rest.starts_with("//") {
+            (TokenKind::Comment, rest.find('\n').unwrap_or(rest.len()))
+        } else if let Some(body) = rest.strip_prefix("\"\"\"") {
+            match body.find("\"\"\"") {
+                Some(end) => (TokenKind::String, end + 6),
+                None => bail!(SourceError::new(line, "Unterminated string").with_column(column)),
+            }
+        } else if let Some(body) = rest.strip_prefix('"') {
+            match body.find('"') {
+                Some(end) => (TokenKind::String, end + 2),
+                None => bail!(SourceError::new(line, "Unterminated string").with_column(column)),
+            }
+        } else if let Some(len) = number_len(rest) {
+            let kind = if rest[..len].contains('.') {
+                TokenKind::Float
+            } else {
+                TokenKind::Int
+            };
+            (kind, len)
+        } else if let Some(len) = raw_identifier_len(rest) {
+            (TokenKind::Identifier, len)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = word_len(rest);
+            let kind = match &rest[..len] {
+                "true" | "false" => TokenKind::Bool,
+                word if KEYWORDS.contains(&word) => TokenKind::Keyword,
+                _ => TokenKind::Identifier,
+            };
+            (kind, len)
+        } else if let Some(punct) = PUNCTUATION.iter().find(|punct| rest.starts_with(**punct)) {
+            (TokenKind::Punct, punct.len())
+        } else {
+            bail!(
+                SourceError::new(line, format!("Unexpected character `{}`", c)).with_column(column)
+            );
+        };
+
+        tokens.push(Token {
+            kind,
+            text: rest[..len].to_string(),
+            line,
+            column,
+        });
+        advance(&mut rest, len, &mut line, &mut column);
+    }
+
+    Ok(tokens)
+}
+
+/// Rewrites Windows (`\r\n`) and old Mac (`\r`) line endings as `\n`, so that string
+/// literals, comments and positions come out the same whichever endings a file uses.
+pub fn normalize_line_endings(source: &str) -> Cow<'_, str> {
+    if source.contains('\r') {
+        Cow::Owned(source.replace("\r\n", "\n").replace('\r', "\n"))
+    } else {
+        Cow::Borrowed(source)
+    }
+}
+
+// The length of the word made of identifier characters at the start of `text`.
+fn word_len(text: &str) -> usize {
+    text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(text.len())
+}
+
+/// The length of an escaped identifier such as `r#prompt` at the start of `text`, which is
+/// an identifier even when the word after `r#` is reserved.
+fn raw_identifier_len(text: &str) -> Option<usize> {
+    let word = text.strip_prefix("r#")?;
+    let starts_word = word.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+    starts_word.then(|| 2 + word_len(word))
+}
+
+/// The length of the number at the start of `text`, as accepted by the grammar: an optional
+/// `-`, then digits with an optional fraction, or a fraction alone such as `.5`.
+fn number_len(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut len = usize::from(bytes.first() == Some(&b'-'));
+    let digits = |from: usize| {
+        bytes[from..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+
+    let whole = digits(len);
+    len += whole;
+    // A dot followed by another dot starts a range, as in `0..150`.
+    if bytes.get(len) == Some(&b'.') && bytes.get(len + 1) != Some(&b'.') {
+        let fraction = digits(len + 1);
+        if whole > 0 || fraction > 0 {
+            len += 1 + fraction;
+        }
+    }
+    (len > 0 && bytes[..len].iter().any(u8::is_ascii_digit)).then_some(len)
+}
+
+fn advance(rest: &mut &str, len: usize, line: &mut usize, column: &mut usize) {
+    for c in rest[..len].chars() {
+        if c == '\n' {
+            *line += 1;
+            *column = 1;
+        } else {
+            *column += 1;
+        }
+    }
+    *rest = &rest[len..];
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_function_declaration() {
+        let source = "// Ages\nfn age(p: String) -> Meaning<Int>(\"age\", range: 0..150) {\n    prompt \"How old is {p}?\";\n}";
+
+        let tokens = tokenize(source).unwrap();
+        let kinds_and_text: Vec<(TokenKind, &str)> = tokens
+            .iter()
+            .map(|token| (token.kind, token.text.as_str()))
+            .collect();
+
+        assert_eq!(
+            &kinds_and_text[..4],
+            &[
+                (TokenKind::Comment, "// Ages"),
+                (TokenKind::Keyword, "fn"),
+                (TokenKind::Identifier, "age"),
+                (TokenKind::Punct, "("),
+            ]
+        );
+        assert!(kinds_and_text.contains(&(TokenKind::Punct, "->")));
+        assert!(kinds_and_text.contains(&(TokenKind::Int, "0")));
+        assert!(kinds_and_text.contains(&(TokenKind::Punct, "..")));
+        assert!(kinds_and_text.contains(&(TokenKind::Int, "150")));
+        assert!(kinds_and_text.contains(&(TokenKind::String, "\"How old is {p}?\"")));
+
+        let prompt = tokens.iter().find(|token| token.text == "prompt").unwrap();
+        assert_eq!((prompt.line, prompt.column), (3, 5));
+    }
+
+    #[test]
+    fn test_tokenize_crlf_source_like_lf_source() {
+        let lf = "// Ages\nfn age(p: String) -> Int {\n    prompt \"\"\"\n    How old is {p}?\n    \"\"\";\n}\n";
+        let crlf = lf.replace('\n', "\r\n");
+        let mixed = lf.replacen('\n', "\r\n", 2);
+
+        let expected = tokenize(lf).unwrap();
+
+        assert_eq!(tokenize(&crlf).unwrap(), expected);
+        assert_eq!(tokenize(&mixed).unwrap(), expected);
+        assert_eq!(expected[0].text, "// Ages");
+        let brace = expected.last().unwrap();
+        assert_eq!((brace.line, brace.column), (6, 1));
+    }
+
+    #[test]
+    fn test_tokenize_rejects_unknown_characters() {
+        let error = tokenize("fn f() {\n  $\n}").unwrap_err().to_string();
+
+        assert_eq!(error, "Line 2, column 3: Unexpected character `$`");
+    }
+
+    #[test]
+    fn test_tokenize_escaped_keyword_as_identifier() {
+        let tokens = tokenize("let r#prompt = prompt;").unwrap();
+
+        assert_eq!(tokens[1].kind, TokenKind::Identifier);
+        assert_eq!(tokens[1].text, "r#prompt");
+        assert_eq!(tokens[3].kind, TokenKind::Keyword);
+    }
+}
@@ -1,10 +1,52 @@
 pub mod codegen;
+pub mod lexer;
 pub mod parser;
 pub mod project_builder;
+mod template_vars;
 
 use anyhow::Result;
 use codegen::CodeGenerator;
 use parser::parse_source;
+use std::fmt;
+
+/// An error in VibeLang source found by the lexer, the parser or code generation, at a 1-based
+/// line, and column when it has one.
+///
+/// The message does not repeat the location, which `Display` puts before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceError {
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl SourceError {
+    /// An error at `line`, without a column.
+    pub fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column: None,
+            message: message.into(),
+        }
+    }
+
+    /// Places the error at `column` of its line.
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.column {
+            Some(column) => write!(f, "Line {}, column {}: {}", self.line, column, self.message),
+            None => write!(f, "Line {}: {}", self.line, self.message),
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
 
 /// A convenience function to compile VibeLang source code directly into Rust code.
 ///
@@ -1,3 +1,5 @@
+use crate::compiler::SourceError;
+use crate::compiler::lexer::{Token, TokenKind, normalize_line_endings, tokenize};
 use crate::utils::ast::{AstNode, AstNodeType};
 use anyhow::{Result, anyhow};
 use pest::Parser;
@@ -8,48 +10,204 @@ use pest::iterators::Pair;
 pub struct VibeParser;
 
 pub fn parse_source(source: &str) -> Result<AstNode> {
+    let source = normalize_line_endings(source);
+    let source = source.as_ref();
     // Now that the macro can find the file, `VibeParser::parse` will exist.
-    let pairs = VibeParser::parse(Rule::Program, source)?
+    let pairs = VibeParser::parse(Rule::Program, source)
+        .map_err(|error| match (malformed_meaning(source, &error), unclosed_brace(source)) {
+            (Some((meaning, problem)), _) => SourceError::new(
+                meaning.line,
+                format!(
+                    "Malformed meaning type: {}. Write it as `Meaning<Type>(\"description\")`, e.g. `Meaning<Int>(\"the current year\")`",
+                    problem
+                ),
+            )
+            .with_column(meaning.column)
+            .into(),
+            (None, Some((line, column))) => SourceError::new(
+                line,
+                "This `{` is never closed before the end of input",
+            )
+            .with_column(column)
+            .into(),
+            (None, None) => match stray_token(source, &error) {
+                Some(token) => SourceError::new(
+                    token.line,
+                    format!(
+                        "Unexpected `{}`: expected a declaration such as `fn`, `type` or `class`",
+                        token.text
+                    ),
+                )
+                .with_column(token.column)
+                .into(),
+                None => anyhow::Error::from(error),
+            },
+        })?
         .next()
         .ok_or_else(|| anyhow!("Failed to parse program: no pairs found"))?;
 
     let mut program_node = AstNode::new(AstNodeType::Program);
     // The `Rule` enum is now successfully generated by the derive macro.
     for pair in pairs.into_inner() {
-        if let Rule::Declaration = pair.as_rule() {
-            if let Some(declaration) = pair.into_inner().next() {
-                let decl_node = build_ast_from_pair(declaration)?;
-                program_node.add_child(decl_node);
+        match pair.as_rule() {
+            Rule::Declaration => {
+                if let Some(declaration) = pair.into_inner().next() {
+                    let mut decl_node = build_ast_from_pair(declaration)?;
+                    attach_doc_comments(&mut decl_node, source);
+                    program_node.add_child(decl_node);
+                }
+            }
+            // `@package("name")`, `@binary("name")` and `@placeholders("${name}")` become the
+            // "package", "binary" and "placeholders" properties of the program.
+            Rule::ProjectAnnotation => {
+                let (line, column) = pair.line_col();
+                let mut inner = pair.into_inner();
+                let kind = inner.next().unwrap().as_str();
+                let text = inner.next().unwrap().as_str();
+                let value = &text[1..text.len() - 1];
+                if kind != "placeholders" && !is_cargo_name(value) {
+                    return Err(SourceError::new(
+                        line,
+                        format!(
+                            "`@{}(\"{}\")` is not a name Cargo accepts: use ASCII letters, digits, `-` and `_`, start with a letter or `_`, and avoid Rust keywords",
+                            kind, value
+                        ),
+                    )
+                    .with_column(column)
+                    .into());
+                }
+                program_node.set_string(kind, value);
             }
+            _ => {}
         }
     }
     Ok(program_node)
 }
 
+/// Whether Cargo accepts `name` as the name of a package or binary.
+fn is_cargo_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        && syn::parse_str::<syn::Ident>(&name.replace('-', "_")).is_ok()
+}
+
+/// The name written by an `Identifier`, without the `r#` that lets reserved words such as
+/// `prompt` be used as names.
+fn identifier<'i>(pair: Pair<'i, Rule>) -> &'i str {
+    let text = pair.as_str();
+    text.strip_prefix("r#").unwrap_or(text)
+}
+
+/// Sets the "doc" property of a declaration, and of the members of a class, to the text of
+/// the `///` comment lines directly above it, one line per comment line.
+fn attach_doc_comments(node: &mut AstNode, source: &str) {
+    if let Some(doc) = doc_comment_above(source, node.line) {
+        node.set_string("doc", &doc);
+    }
+    if node.node_type == AstNodeType::ClassDecl {
+        for member in &mut node.children {
+            attach_doc_comments(member, source);
+        }
+    }
+}
+
+/// The `///` comment lines that end just before the 1-based `line`, without their slashes.
+/// As in Rust, `////` starts an ordinary comment.
+fn doc_comment_above(source: &str, line: usize) -> Option<String> {
+    let lines: Vec<&str> = source.lines().take(line.saturating_sub(1)).collect();
+    let mut doc: Vec<&str> = lines
+        .iter()
+        .rev()
+        .map(|text| text.trim())
+        .take_while(|text| text.starts_with("///") && !text.starts_with("////"))
+        .map(|text| {
+            let text = &text[3..];
+            text.strip_prefix(' ').unwrap_or(text)
+        })
+        .collect();
+    if doc.is_empty() {
+        return None;
+    }
+    doc.reverse();
+    Some(doc.join("\n"))
+}
+
+/// Builds the AST node for a pair and records its source position.
 fn build_ast_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
+    let (line, column) = pair.line_col();
+    let mut node = build_node_from_pair(pair)?;
+    node.line = line;
+    node.column = column;
+    Ok(node)
+}
+
+fn build_node_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
     match pair.as_rule() {
         Rule::FunctionDecl => {
-            let mut inner = pair.into_inner();
-            let name = inner.next().unwrap().as_str();
+            let mut inner = pair.into_inner().peekable();
+            let length = inner
+                .next_if(|item| item.as_rule() == Rule::LengthAnnotation)
+                .map(|item| item.into_inner().as_str());
+            let mut call_settings = Vec::new();
+            let mut with_confidence = false;
+            while let Some(annotation) = inner.next_if(|item| {
+                matches!(
+                    item.as_rule(),
+                    Rule::CallAnnotation | Rule::ConfidenceAnnotation
+                )
+            }) {
+                if annotation.as_rule() == Rule::ConfidenceAnnotation {
+                    with_confidence = true;
+                    continue;
+                }
+                let mut annotation = annotation.into_inner();
+                let kind = annotation.next().unwrap().as_str();
+                let value: i64 = annotation.next().unwrap().as_str().parse()?;
+                call_settings.push((kind, value));
+            }
+            let is_async = inner
+                .next_if(|item| item.as_rule() == Rule::AsyncModifier)
+                .is_some();
+            let name = identifier(inner.next().unwrap());
             let mut func = AstNode::new(AstNodeType::FunctionDecl);
             func.set_string("name", name);
+            func.set_bool("async", is_async);
+            if let Some(length) = length {
+                func.set_string("length", length);
+            }
+            // `@timeout(10)` and `@retries(3)` become the "timeout" and "retries" properties.
+            for (kind, value) in call_settings {
+                func.set_int(kind, value);
+            }
+            if with_confidence {
+                func.set_bool("with_confidence", true);
+            }
 
             for item in inner {
                 match item.as_rule() {
                     Rule::ParamList => {
                         let mut params_node = AstNode::new(AstNodeType::ParamList);
                         for param_pair in item.into_inner() {
+                            let (line, column) = param_pair.line_col();
                             let mut param_inner = param_pair.into_inner();
-                            let param_name = param_inner.next().unwrap().as_str();
+                            let param_name = identifier(param_inner.next().unwrap());
                             let param_type = build_ast_from_pair(param_inner.next().unwrap())?;
                             let mut param_node = AstNode::new(AstNodeType::Parameter);
+                            param_node.line = line;
+                            param_node.column = column;
                             param_node.set_string("name", param_name);
                             param_node.add_child(param_type);
+                            // `where` comparisons follow the type as `Comparison` children.
+                            for comparison in param_inner {
+                                param_node.add_child(build_ast_from_pair(comparison)?);
+                            }
                             params_node.add_child(param_node);
                         }
                         func.add_child(params_node);
                     }
-                    Rule::Type => {
+                    Rule::Type | Rule::UnionType => {
                         let type_node = build_ast_from_pair(item)?;
                         func.add_child(type_node);
                     }
@@ -65,25 +223,115 @@ fn build_ast_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
         Rule::Block => {
             let mut block_node = AstNode::new(AstNodeType::Block);
             for stmt_pair in pair.into_inner() {
-                if let Rule::Statement = stmt_pair.as_rule() {
-                    if let Some(statement) = stmt_pair.into_inner().next() {
-                        let stmt_node = build_ast_from_pair(statement)?;
-                        block_node.add_child(stmt_node);
-                    }
+                if let Rule::Statement = stmt_pair.as_rule()
+                    && let Some(statement) = stmt_pair.into_inner().next()
+                {
+                    let stmt_node = build_ast_from_pair(statement)?;
+                    block_node.add_child(stmt_node);
                 }
             }
             Ok(block_node)
         }
         Rule::PromptStmt => {
-            let template = pair.into_inner().next().unwrap().as_str();
+            let literal = pair.into_inner().next().unwrap();
+            let text = literal.as_str();
             let mut prompt_node = AstNode::new(AstNodeType::PromptBlock);
-            // Remove quotes from the string literal
-            prompt_node.set_string("template", &template[1..template.len() - 1]);
+            if literal.as_rule() == Rule::TripleStringLiteral {
+                let body = strip_notes(&text[3..text.len() - 3]);
+                prompt_node.set_string("template", &dedent(&body));
+            } else {
+                // Remove quotes from the string literal
+                prompt_node.set_string("template", &text[1..text.len() - 1]);
+            }
             Ok(prompt_node)
         }
+        Rule::TempStmt => {
+            let literal = pair.into_inner().next().unwrap();
+            let mut temp_node = AstNode::new(AstNodeType::TempStmt);
+            temp_node.set_float("value", literal.as_str().parse()?);
+            Ok(temp_node)
+        }
+        Rule::VarDecl => {
+            let mut inner = pair.into_inner();
+            let name = identifier(inner.next().unwrap());
+            let mut var_node = AstNode::new(AstNodeType::VarDecl);
+            var_node.set_string("name", name);
+            for item in inner {
+                var_node.add_child(build_ast_from_pair(item)?);
+            }
+            Ok(var_node)
+        }
+        Rule::ReturnStmt => {
+            let mut return_node = AstNode::new(AstNodeType::ReturnStmt);
+            if let Some(expr) = pair.into_inner().next() {
+                return_node.add_child(build_ast_from_pair(expr)?);
+            }
+            Ok(return_node)
+        }
+        Rule::ExprStmt => {
+            let mut expr_stmt_node = AstNode::new(AstNodeType::ExprStmt);
+            let expr = build_ast_from_pair(pair.into_inner().next().unwrap())?;
+            expr_stmt_node.add_child(expr);
+            Ok(expr_stmt_node)
+        }
+        Rule::Expression | Rule::Literal => {
+            // Recurse into the concrete expression or literal rule
+            build_ast_from_pair(pair.into_inner().next().unwrap())
+        }
+        Rule::CallExpr => {
+            let mut inner = pair.into_inner();
+            let name = identifier(inner.next().unwrap());
+            let mut call_node = AstNode::new(AstNodeType::CallExpr);
+            call_node.set_string("name", name);
+            for arg in inner {
+                call_node.add_child(build_ast_from_pair(arg)?);
+            }
+            Ok(call_node)
+        }
+        Rule::Comparison => {
+            let (line, column) = pair.line_col();
+            let mut inner = pair.into_inner();
+            let left = build_ast_from_pair(inner.next().unwrap())?;
+            let op = inner.next().unwrap().as_str();
+            let right = build_ast_from_pair(inner.next().unwrap())?;
+            let mut comparison_node = AstNode::new(AstNodeType::Comparison);
+            comparison_node.line = line;
+            comparison_node.column = column;
+            comparison_node.set_string("op", op);
+            comparison_node.add_child(left);
+            comparison_node.add_child(right);
+            Ok(comparison_node)
+        }
+        Rule::Identifier => {
+            let mut identifier_node = AstNode::new(AstNodeType::Identifier);
+            identifier_node.set_string("name", identifier(pair));
+            Ok(identifier_node)
+        }
+        Rule::StringLiteral => {
+            let literal = pair.as_str();
+            let mut string_node = AstNode::new(AstNodeType::StringLiteral);
+            // Remove quotes from the string literal
+            string_node.set_string("value", &literal[1..literal.len() - 1]);
+            Ok(string_node)
+        }
+        Rule::IntLiteral => {
+            let mut int_node = AstNode::new(AstNodeType::IntLiteral);
+            int_node.set_int("value", pair.as_str().parse()?);
+            Ok(int_node)
+        }
+        Rule::FloatLiteral => {
+            let mut float_node = AstNode::new(AstNodeType::FloatLiteral);
+            float_node.set_float("value", pair.as_str().parse()?);
+            Ok(float_node)
+        }
+        Rule::BoolLiteral => {
+            let mut bool_node = AstNode::new(AstNodeType::BoolLiteral);
+            bool_node.set_bool("value", pair.as_str() == "true");
+            Ok(bool_node)
+        }
         Rule::TypeDecl => {
             let mut inner = pair.into_inner();
-            let name = inner.next().unwrap().as_str();
+            let name = identifier(inner.next().unwrap());
             let type_def = build_ast_from_pair(inner.next().unwrap())?;
 
             let mut type_decl_node = AstNode::new(AstNodeType::TypeDecl);
@@ -91,11 +339,49 @@ fn build_ast_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
             type_decl_node.add_child(type_def);
             Ok(type_decl_node)
         }
+        Rule::ImportDecl => {
+            let text = pair.into_inner().next().unwrap().as_str();
+            let mut import_node = AstNode::new(AstNodeType::Import);
+            import_node.set_string("path", &text[1..text.len() - 1]);
+            Ok(import_node)
+        }
+        Rule::ConstDecl => {
+            let mut inner = pair.into_inner();
+            let name = identifier(inner.next().unwrap());
+            let mut const_node = AstNode::new(AstNodeType::ConstDecl);
+            const_node.set_string("name", name);
+            const_node.add_child(build_ast_from_pair(inner.next().unwrap())?);
+            Ok(const_node)
+        }
+        Rule::ClassDecl => {
+            let mut inner = pair.into_inner();
+            let name = identifier(inner.next().unwrap());
+            let mut class_node = AstNode::new(AstNodeType::ClassDecl);
+            class_node.set_string("name", name);
+            for member in inner {
+                class_node.add_child(build_ast_from_pair(member)?);
+            }
+            Ok(class_node)
+        }
+        Rule::MemberVar => {
+            // Shaped like a `let` statement: the type, then the default value if any.
+            let mut inner = pair
+                .into_inner()
+                .filter(|item| item.as_rule() != Rule::MemberLet);
+            let name = identifier(inner.next().unwrap());
+            let mut member_node = AstNode::new(AstNodeType::MemberVar);
+            member_node.set_string("name", name);
+            for item in inner {
+                member_node.add_child(build_ast_from_pair(item)?);
+            }
+            Ok(member_node)
+        }
         Rule::Type => {
             // Recurse into the actual type rule (MeaningType or BasicType)
             build_ast_from_pair(pair.into_inner().next().unwrap())
         }
         Rule::MeaningType => {
+            let (line, column) = pair.line_col();
             let mut inner = pair.into_inner();
             let base_type = build_ast_from_pair(inner.next().unwrap())?;
             let meaning_str = inner.next().unwrap().as_str();
@@ -103,11 +389,66 @@ fn build_ast_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
             let mut meaning_node = AstNode::new(AstNodeType::MeaningType);
             // Remove quotes from the string literal
             meaning_node.set_string("meaning", &meaning_str[1..meaning_str.len() - 1]);
+            for constraint in inner {
+                let constraint = constraint.into_inner().next().unwrap();
+                let rule = constraint.as_rule();
+                if rule == Rule::UnitConstraint {
+                    let unit = constraint.into_inner().next().unwrap().as_str();
+                    meaning_node.set_string("unit", &unit[1..unit.len() - 1]);
+                    continue;
+                }
+                let mut bounds = constraint.into_inner();
+                let mut next_bound =
+                    || -> Result<f64> { Ok(bounds.next().unwrap().as_str().parse()?) };
+                match rule {
+                    Rule::RangeConstraint => {
+                        meaning_node.set_float("min", next_bound()?);
+                        meaning_node.set_float("max", next_bound()?);
+                    }
+                    Rule::MinConstraint => meaning_node.set_float("min", next_bound()?),
+                    Rule::MaxConstraint => meaning_node.set_float("max", next_bound()?),
+                    _ => return Err(anyhow!("Unhandled meaning constraint: {:?}", rule)),
+                }
+            }
+            if let (Some(min), Some(max)) =
+                (meaning_node.get_float("min"), meaning_node.get_float("max"))
+                && min > max
+            {
+                return Err(SourceError::new(
+                    line,
+                    format!(
+                        "Empty range in meaning type: the minimum {} is greater than the maximum {}",
+                        min, max
+                    ),
+                )
+                .with_column(column)
+                .into());
+            }
             meaning_node.add_child(base_type);
             Ok(meaning_node)
         }
+        Rule::UnionType => {
+            let mut union_node = AstNode::new(AstNodeType::UnionType);
+            for variant in pair.into_inner() {
+                union_node.add_child(build_ast_from_pair(variant)?);
+            }
+            Ok(union_node)
+        }
+        Rule::TupleType => {
+            let mut tuple_node = AstNode::new(AstNodeType::TupleType);
+            for element in pair.into_inner() {
+                tuple_node.add_child(build_ast_from_pair(element)?);
+            }
+            Ok(tuple_node)
+        }
+        Rule::UnitType => {
+            // `()` is kept as a basic type; codegen maps it to Rust's unit type.
+            let mut unit_type_node = AstNode::new(AstNodeType::BasicType);
+            unit_type_node.set_string("type", "()");
+            Ok(unit_type_node)
+        }
         Rule::BasicType => {
-            let type_name = pair.as_str();
+            let type_name = identifier(pair);
             let mut basic_type_node = AstNode::new(AstNodeType::BasicType);
             basic_type_node.set_string("type", type_name);
             Ok(basic_type_node)
@@ -116,6 +457,178 @@ fn build_ast_from_pair(pair: Pair<Rule>) -> Result<AstNode> {
     }
 }
 
+/// Parses the source like `parse_source` and also returns its tokens, as from `tokenize`.
+///
+/// Meant for tooling that needs both the structure and the lexical detail, such as editors
+/// that highlight syntax and show an outline.
+pub fn parse_source_with_tokens(source: &str) -> Result<(AstNode, Vec<Token>)> {
+    let ast = parse_source(source)?;
+    let tokens = tokenize(source)?;
+    Ok((ast, tokens))
+}
+
+/// The token that stopped the parse, when it stands where a declaration should start, such as
+/// a `}` left over after the last function.
+fn stray_token(source: &str, error: &pest::error::Error<Rule>) -> Option<Token> {
+    let pest::error::ErrorVariant::ParsingError { positives, .. } = &error.variant else {
+        return None;
+    };
+    if !positives.contains(&Rule::EOI) {
+        return None;
+    }
+    let (line, column) = match error.line_col {
+        pest::error::LineColLocation::Pos(position) => position,
+        pest::error::LineColLocation::Span(start, _) => start,
+    };
+    tokenize(source)
+        .ok()?
+        .into_iter()
+        .find(|token| token.line == line && token.column == column)
+}
+
+/// The first `Meaning` before the position of the parse `error` whose `<Type>` is malformed,
+/// such as `Meaning<Int("x")` missing its `>`, with what is wrong with it.
+fn malformed_meaning(
+    source: &str,
+    error: &pest::error::Error<Rule>,
+) -> Option<(Token, &'static str)> {
+    let error_position = match error.line_col {
+        pest::error::LineColLocation::Pos(position) => position,
+        pest::error::LineColLocation::Span(start, _) => start,
+    };
+    let tokens: Vec<Token> = tokenize(source)
+        .ok()?
+        .into_iter()
+        .filter(|token| token.kind != TokenKind::Comment)
+        .collect();
+    for (index, token) in tokens.iter().enumerate() {
+        if token.text != "Meaning" || (token.line, token.column) > error_position {
+            continue;
+        }
+        let problem = match tokens.get(index + 1) {
+            Some(next) if next.text == "<" => {
+                if angle_closed(&tokens[index + 2..]) {
+                    continue;
+                }
+                "the `<` after `Meaning` is not closed with `>` before the description"
+            }
+            _ => "`Meaning` must be followed by `<`, the type and `>`",
+        };
+        return Some((token.clone(), problem));
+    }
+    None
+}
+
+/// Whether the tokens after a `Meaning<` close it with `>`, allowing the parentheses of
+/// inner meaning and tuple types but not the description of this one.
+fn angle_closed(tokens: &[Token]) -> bool {
+    let (mut angles, mut parens) = (1, 0);
+    let mut previous = "<";
+    for token in tokens {
+        match token.text.as_str() {
+            "<" => angles += 1,
+            ">" => {
+                angles -= 1;
+                if angles == 0 {
+                    return true;
+                }
+            }
+            "(" if parens > 0 || matches!(previous, "<" | ">") => parens += 1,
+            ")" if parens > 0 => parens -= 1,
+            "(" | ")" | ";" | "{" | "}" => return false,
+            _ => {}
+        }
+        previous = token.text.as_str();
+    }
+    false
+}
+
+/// Finds the innermost `{` that is still open at the end of the source.
+///
+/// Braces inside string literals and comments are ignored. Returns the 1-based line and column
+/// of the opening brace.
+fn unclosed_brace(source: &str) -> Option<(usize, usize)> {
+    let mut open_braces = Vec::new();
+    let mut chars = source.chars().peekable();
+    let (mut line, mut column) = (1, 0);
+
+    while let Some(c) = chars.next() {
+        column += 1;
+        match c {
+            '\n' => {
+                line += 1;
+                column = 0;
+            }
+            '{' => open_braces.push((line, column)),
+            '}' => {
+                open_braces.pop();
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                // Skip the comment, leaving the newline to be counted above.
+                while chars.next_if(|&next| next != '\n').is_some() {}
+            }
+            '"' => {
+                let mut rest = chars.clone();
+                let delimiter = if rest.next() == Some('"') && rest.next() == Some('"') {
+                    chars.next();
+                    chars.next();
+                    column += 2;
+                    "\"\"\""
+                } else {
+                    "\""
+                };
+                let mut tail = String::new();
+                for next in chars.by_ref() {
+                    column += 1;
+                    if next == '\n' {
+                        line += 1;
+                        column = 0;
+                    }
+                    tail.push(next);
+                    if tail.ends_with(delimiter) {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    open_braces.pop()
+}
+
+/// Drops the lines of a triple-quoted prompt that start with `#!`, which are notes for the
+/// author and are not sent to the model.
+fn strip_notes(text: &str) -> String {
+    text.split_inclusive('\n')
+        .filter(|line| !line.trim_start().starts_with("#!"))
+        .collect()
+}
+
+/// Strips the indentation shared by all non-blank lines of a triple-quoted string.
+///
+/// A line break right after the opening quotes and trailing whitespace before the closing
+/// quotes are dropped too, so the text can start and end on its own lines.
+fn dedent(text: &str) -> String {
+    let text = text
+        .strip_prefix("\r\n")
+        .or_else(|| text.strip_prefix('\n'))
+        .unwrap_or(text);
+    let text = text.trim_end();
+
+    let indent = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    text.lines()
+        .map(|line| line.get(indent..).unwrap_or("").trim_end())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -143,6 +656,146 @@ mod tests {
         assert_eq!(prompt_node.get_string("template").unwrap(), "Say hello");
     }
 
+    #[test]
+    fn test_parse_temperature_statement() {
+        let source = "fn brainstorm() -> String { temperature 0.2; prompt \"Name a color\"; }";
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let block_node = ast.children[0]
+            .children
+            .iter()
+            .find(|n| n.node_type == AstNodeType::Block)
+            .unwrap();
+        let temp_node = &block_node.children[0];
+        assert_eq!(temp_node.node_type, AstNodeType::TempStmt);
+        assert_eq!(temp_node.get_float("value"), Some(0.2));
+        assert_eq!(block_node.children[1].node_type, AstNodeType::PromptBlock);
+    }
+
+    #[test]
+    fn test_escaped_prompt_keyword_is_an_identifier() {
+        let source = "fn rewrite(r#prompt: String) -> String { let r#temperature = 1; prompt \"Improve: {prompt}\"; }";
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let params = &ast.children[0].children[0];
+        assert_eq!(params.children[0].get_string("name").unwrap(), "prompt");
+        let block = ast.children[0].children.last().unwrap();
+        assert_eq!(block.children[0].node_type, AstNodeType::VarDecl);
+        assert_eq!(block.children[0].get_string("name").unwrap(), "temperature");
+
+        let code = crate::compiler::codegen::CodeGenerator::new()
+            .generate(&ast, true)
+            .unwrap();
+        assert!(code.contains("pub fn rewrite(llm: &LlmClient, prompt: String) -> String"));
+        crate::testing::check_rust_syntax(&code).unwrap();
+
+        // Escaping does not make Rust keywords usable as Rust names.
+        let rust_keyword = parse_source("fn f(r#type: String) { prompt \"{type}\"; }").unwrap();
+        let error = crate::compiler::codegen::CodeGenerator::new()
+            .generate(&rust_keyword, true)
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("reserved word in Rust"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_parse_class_member_with_default() {
+        let source = "class Forecast { let city: String = \"Paris\"; letter: Int; }";
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let class_node = &ast.children[0];
+        assert_eq!(class_node.node_type, AstNodeType::ClassDecl);
+        assert_eq!(class_node.get_string("name").unwrap(), "Forecast");
+
+        let city = &class_node.children[0];
+        assert_eq!(city.node_type, AstNodeType::MemberVar);
+        assert_eq!(city.get_string("name").unwrap(), "city");
+        assert_eq!(city.children[0].node_type, AstNodeType::BasicType);
+        assert_eq!(city.children[1].get_string("value").unwrap(), "Paris");
+
+        // A field whose name starts with `let` is not read as `let ter`.
+        let letter = &class_node.children[1];
+        assert_eq!(letter.get_string("name").unwrap(), "letter");
+        assert_eq!(letter.children.len(), 1);
+    }
+
+    // Node types, positions and properties of the tree, in a stable order.
+    fn outline(node: &AstNode) -> Vec<String> {
+        let mut properties: Vec<String> = node
+            .properties
+            .iter()
+            .map(|(name, value)| format!("{}={:?}", name, value))
+            .collect();
+        properties.sort();
+        let mut lines = vec![format!(
+            "{:?} {}:{} {}",
+            node.node_type,
+            node.line,
+            node.column,
+            properties.join(" ")
+        )];
+        for child in &node.children {
+            lines.extend(outline(child));
+        }
+        lines
+    }
+
+    #[test]
+    fn test_parse_source_with_tokens_matches_separate_calls() {
+        let source = r#"
+            // Weather lookup
+            type Weather = Meaning<String>("weather description");
+            fn get_weather(city: String) -> Weather {
+                prompt "What is the weather like in {city}?";
+            }
+        "#;
+
+        let (ast, tokens) = parse_source_with_tokens(source).unwrap();
+
+        assert_eq!(tokens, tokenize(source).unwrap());
+        assert_eq!(outline(&ast), outline(&parse_source(source).unwrap()));
+        assert!(parse_source_with_tokens("fn broken( {").is_err());
+    }
+
+    #[test]
+    fn test_parse_meaning_constraints() {
+        let source = r#"
+            type Age = Meaning<Int>("age in years", range: 0..150);
+            type Ratio = Meaning<Float>("ratio", min: -0.5, max: 1.5);
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let age = &ast.children[0].children[0];
+        assert_eq!(age.get_string("meaning").unwrap(), "age in years");
+        assert_eq!(age.get_float("min"), Some(0.0));
+        assert_eq!(age.get_float("max"), Some(150.0));
+        let ratio = &ast.children[1].children[0];
+        assert_eq!(ratio.get_float("min"), Some(-0.5));
+        assert_eq!(ratio.get_float("max"), Some(1.5));
+
+        let error = parse_source(r#"type Age = Meaning<Int>("age", min: 150, max: 0);"#)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("Line 1, column 12: Empty range in meaning type"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_parse_meaning_unit() {
+        let source = r#"type Temperature = Meaning<Float>("outdoor temperature", unit: "celsius", min: -90);"#;
+        let ast = parse_source(source).unwrap();
+
+        let meaning = &ast.children[0].children[0];
+        assert_eq!(meaning.get_string("unit").unwrap(), "celsius");
+        assert_eq!(meaning.get_float("min"), Some(-90.0));
+    }
+
     #[test]
     fn test_parse_function_with_params_and_return_type() {
         let source = r#"
@@ -176,6 +829,28 @@ mod tests {
         assert_eq!(return_type_node.get_string("type").unwrap(), "Int");
     }
 
+    #[test]
+    fn test_parse_tuple_return_type() {
+        let source = r#"
+            fn city_and_population(country: String) -> (String, Int) {
+                prompt "Name the capital of {country} and its population.";
+            }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let tuple_node = ast.children[0]
+            .children
+            .iter()
+            .find(|n| n.node_type == AstNodeType::TupleType)
+            .unwrap();
+        let elements: Vec<&str> = tuple_node
+            .children
+            .iter()
+            .map(|element| element.get_string("type").unwrap().as_str())
+            .collect();
+        assert_eq!(elements, ["String", "Int"]);
+    }
+
     #[test]
     fn test_parse_type_alias_with_meaning() {
         let source = r#"type Population = Meaning<Int>("population count in millions");"#;
@@ -214,6 +889,289 @@ mod tests {
         assert_eq!(ast.children[2].node_type, AstNodeType::FunctionDecl);
     }
 
+    #[test]
+    fn test_parse_async_function_modifier() {
+        let source = r#"
+            async fn get_weather(city: String) -> String {
+                prompt "What is the weather in {city}?";
+            }
+            fn get_year() -> Int { prompt "What year is it?"; }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let async_func = &ast.children[0];
+        assert_eq!(async_func.get_string("name").unwrap(), "get_weather");
+        assert_eq!(async_func.get_bool("async"), Some(true));
+
+        let sync_func = &ast.children[1];
+        assert_eq!(sync_func.get_bool("async"), Some(false));
+    }
+
+    #[test]
+    fn test_parse_timeout_and_retries_annotations() {
+        let source = r#"
+            @concise @timeout(10) @retries(3) async fn summarize(text: String) -> String {
+                prompt "Summarize {text}.";
+            }
+            fn get_year() -> Int { prompt "What year is it?"; }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let summarize = &ast.children[0];
+        assert_eq!(summarize.get_string("length").unwrap(), "concise");
+        assert_eq!(summarize.get_int("timeout"), Some(10));
+        assert_eq!(summarize.get_int("retries"), Some(3));
+        assert_eq!(summarize.get_bool("async"), Some(true));
+
+        let get_year = &ast.children[1];
+        assert_eq!(get_year.get_int("timeout"), None);
+        assert_eq!(get_year.get_int("retries"), None);
+    }
+
+    #[test]
+    fn test_parse_where_guard_on_parameter() {
+        let source = r#"
+            fn advise(age: Int where age >= 0 && age < 150, name: String) -> String {
+                prompt "Advise {name}, aged {age}.";
+            }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+
+        let params = &ast.children[0].children[0];
+        let age = &params.children[0];
+        assert_eq!(age.children.len(), 3);
+        assert_eq!(age.children[0].get_string("type").unwrap(), "Int");
+
+        let lower = &age.children[1];
+        assert_eq!(lower.node_type, AstNodeType::Comparison);
+        assert_eq!(lower.get_string("op").unwrap(), ">=");
+        assert_eq!(lower.children[0].get_string("name").unwrap(), "age");
+        assert_eq!(lower.children[1].get_int("value"), Some(0));
+        assert_eq!(age.children[2].get_string("op").unwrap(), "<");
+
+        let name = &params.children[1];
+        assert_eq!(name.children.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_call_expression_with_literals() {
+        let source = r#"
+            fn describe() -> String {
+                greet("Ada", 36, 1.5, true);
+                prompt "Describe it";
+            }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+        let block_node = ast.children[0]
+            .children
+            .iter()
+            .find(|n| n.node_type == AstNodeType::Block)
+            .unwrap();
+
+        let expr_stmt = &block_node.children[0];
+        assert_eq!(expr_stmt.node_type, AstNodeType::ExprStmt);
+        let call_node = &expr_stmt.children[0];
+        assert_eq!(call_node.node_type, AstNodeType::CallExpr);
+        assert_eq!(call_node.get_string("name").unwrap(), "greet");
+        assert_eq!(call_node.line, 3);
+
+        let arg_types: Vec<AstNodeType> = call_node.children.iter().map(|c| c.node_type).collect();
+        assert_eq!(
+            arg_types,
+            vec![
+                AstNodeType::StringLiteral,
+                AstNodeType::IntLiteral,
+                AstNodeType::FloatLiteral,
+                AstNodeType::BoolLiteral
+            ]
+        );
+        assert_eq!(call_node.children[1].get_int("value"), Some(36));
+        assert_eq!(call_node.children[2].get_float("value"), Some(1.5));
+    }
+
+    #[test]
+    fn test_parse_float_literals_with_optional_digits() {
+        let source = r#"
+            fn scale() -> Float {
+                resize(.5, 5., 5.5, -.25, 7);
+                prompt "Scale it";
+            }
+        "#;
+        let ast = parse_source(source).expect("Parsing failed");
+        let block_node = ast.children[0]
+            .children
+            .iter()
+            .find(|n| n.node_type == AstNodeType::Block)
+            .unwrap();
+        let args = &block_node.children[0].children[0].children;
+
+        assert_eq!(args[0].node_type, AstNodeType::FloatLiteral);
+        assert_eq!(args[0].get_float("value"), Some(0.5));
+        assert_eq!(args[1].node_type, AstNodeType::FloatLiteral);
+        assert_eq!(args[1].get_float("value"), Some(5.0));
+        assert_eq!(args[2].node_type, AstNodeType::FloatLiteral);
+        assert_eq!(args[2].get_float("value"), Some(5.5));
+        assert_eq!(args[3].get_float("value"), Some(-0.25));
+        assert_eq!(args[4].node_type, AstNodeType::IntLiteral);
+    }
+
+    #[test]
+    fn test_parser_rejects_lone_dot_as_number() {
+        let source = r#"fn f() -> Float { resize(.); prompt "x"; }"#;
+        assert!(parse_source(source).is_err());
+    }
+
+    #[test]
+    fn test_missing_closing_brace_reports_opening_line() {
+        let source =
+            "type Year = Int;\n\nfn get_year() -> Year {\n    prompt \"What year is it? {}\";\n";
+        let error = parse_source(source).unwrap_err().to_string();
+        assert!(
+            error.starts_with("Line 3, column 23: "),
+            "Error should point at the opening brace: {}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_trailing_tokens_report_the_first_stray_token() {
+        let source = "type Year = Int;\n\nfn get_year() -> Year {\n    prompt \"What year is it?\";\n}\n} ) ;\n";
+        let error = parse_source(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "Line 6, column 1: Unexpected `}`: expected a declaration such as `fn`, `type` or `class`"
+        );
+    }
+
+    #[test]
+    fn test_malformed_meaning_type_reports_where_it_starts() {
+        let source = "type City = Meaning<String>(\"a city\");\ntype Year = Meaning<Int(\"the current year\");\n";
+        let error = parse_source(source).unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "Line 2, column 13: Malformed meaning type: the `<` after `Meaning` is not closed with `>` before the description. Write it as `Meaning<Type>(\"description\")`, e.g. `Meaning<Int>(\"the current year\")`"
+        );
+
+        let nested =
+            "fn f() -> Meaning<Meaning<Int>(\"a year\")>(\"the year\" { prompt \"Year?\"; }";
+        let error = parse_source(nested).unwrap_err().to_string();
+        assert!(!error.starts_with("Malformed meaning type"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_comments_between_declarations() {
+        let source = r#"
+            // Leading comment
+
+            type Year = Meaning<Int>("a calendar year");
+            // Between two type declarations
+            type City = Meaning<String>("a city name");
+
+            // Several comment lines
+            // before a function
+
+            fn get_year() -> Year {
+                // Inside a block
+                prompt "What year is it?"; // After a statement
+            }
+            // Between functions
+            fn get_city(country: String) -> City { prompt "Capital of {country}?"; }
+            // Trailing comment without a newline"#;
+
+        let ast = parse_source(source).unwrap();
+
+        let kinds: Vec<AstNodeType> = ast.children.iter().map(|c| c.node_type).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                AstNodeType::TypeDecl,
+                AstNodeType::TypeDecl,
+                AstNodeType::FunctionDecl,
+                AstNodeType::FunctionDecl,
+            ]
+        );
+        let body = &ast.children[2].children[1];
+        assert_eq!(body.node_type, AstNodeType::Block);
+        assert_eq!(body.children.len(), 1);
+    }
+
+    #[test]
+    fn test_doc_comment_attaches_to_following_declaration() {
+        let source = r#"
+            // An ordinary comment is not documentation.
+
+            /// Asks for the current year.
+            ///
+            ///   Indentation after the first space is kept.
+            fn get_year() -> Int { prompt "Year?"; }
+
+            fn undocumented() -> Int { prompt "Number?"; }
+
+            /// A point on a map.
+            class Point {
+                /// Degrees north.
+                lat: Float;
+                lon: Float;
+            }
+        "#;
+        let ast = parse_source(source).unwrap();
+
+        assert_eq!(
+            ast.children[0].get_string("doc").unwrap(),
+            "Asks for the current year.\n\n  Indentation after the first space is kept."
+        );
+        assert!(ast.children[1].get_string("doc").is_none());
+        let class = &ast.children[2];
+        assert_eq!(class.get_string("doc").unwrap(), "A point on a map.");
+        assert_eq!(
+            class.children[0].get_string("doc").unwrap(),
+            "Degrees north."
+        );
+        assert!(class.children[1].get_string("doc").is_none());
+    }
+
+    #[test]
+    fn test_parse_crlf_source_like_lf_source() {
+        let lf = "/// Asks for an age.\nfn age(p: String) -> Int {\n    prompt \"\"\"\n        How old is {p}?\n        Answer in years.\n        \"\"\";\n}\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let expected = parse_source(lf).unwrap();
+        let ast = parse_source(&crlf).unwrap();
+
+        let func = &ast.children[0];
+        assert_eq!(func.line, expected.children[0].line);
+        assert_eq!(func.get_string("doc").unwrap(), "Asks for an age.");
+        let prompt = &func.children[2].children[0];
+        assert_eq!(
+            prompt.get_string("template").unwrap(),
+            expected.children[0].children[2].children[0]
+                .get_string("template")
+                .unwrap()
+        );
+        assert_eq!(
+            prompt.get_string("template").unwrap(),
+            "How old is {p}?\nAnswer in years."
+        );
+
+        let error = parse_source("fn f() {\r\n  prompt \"Hi\";\r\n").unwrap_err();
+        assert!(
+            error.to_string().starts_with("Line 1, column 8: "),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_parse_comment_only_program() {
+        let source = "// Nothing here yet\n\n// Still nothing\n";
+
+        let ast = parse_source(source).unwrap();
+
+        assert!(ast.children.is_empty());
+    }
+
     #[test]
     fn test_parser_rejects_invalid_syntax() {
         let source = "fn my_func( -> ) { }"; // Malformed function signature
@@ -1,23 +1,142 @@
+use crate::compiler::SourceError;
+use crate::compiler::parser::parse_source;
 use crate::runtime::llm_provider::LlmProvider;
-use anyhow::Result;
+use anyhow::{Context, Result, anyhow};
+use once_cell::sync::Lazy;
 use regex::Regex;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml_edit::{Array, DocumentMut, Item, Table, value};
+
+// Compiled once on first use and shared by every builder.
+static VERSION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?m)^version\s*=\s*"([^"]+)""#).expect("Invalid Cargo.toml version regex")
+});
+static SEMANTIC_ANNOTATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"Meaning<.+?>\(\s*"(.+?)""#).expect("Invalid semantic annotation regex")
+});
+
+/// Starts a region of a generated source file that `ProjectBuilder::build` keeps when it
+/// writes the file again. The region runs from this marker to the end of the first
+/// `USER_REGION_END` marker after it.
+///
+/// The generated `main`, and the tests of a generated library, are in such a region.
+pub const USER_REGION_BEGIN: &str = "// VIBE:BEGIN-USER";
+/// Ends a region started with `USER_REGION_BEGIN`.
+pub const USER_REGION_END: &str = "// VIBE:END-USER";
+
+/// The `crate-type` of a generated library crate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CrateType {
+    /// A Rust library, used by other Rust crates.
+    #[default]
+    Rlib,
+    /// A C-compatible dynamic library, for FFI.
+    Cdylib,
+    /// A C-compatible static library, for FFI.
+    Staticlib,
+    /// A Rust dynamic library.
+    Dylib,
+}
+
+impl CrateType {
+    /// The value written to `crate-type` in Cargo.toml.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CrateType::Rlib => "rlib",
+            CrateType::Cdylib => "cdylib",
+            CrateType::Staticlib => "staticlib",
+            CrateType::Dylib => "dylib",
+        }
+    }
+}
+
+impl std::str::FromStr for CrateType {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        match name {
+            "rlib" => Ok(CrateType::Rlib),
+            "cdylib" => Ok(CrateType::Cdylib),
+            "staticlib" => Ok(CrateType::Staticlib),
+            "dylib" => Ok(CrateType::Dylib),
+            _ => Err(anyhow!(
+                "Unsupported crate type `{}`: expected one of rlib, cdylib, staticlib, dylib",
+                name
+            )),
+        }
+    }
+}
 
 /// Handles the scaffolding of the generated Rust project.
 /// It is generic over any type T that implements the LlmProvider trait.
 pub struct ProjectBuilder<'a, T: LlmProvider> {
     llm_client: &'a T,
+    workspace_root: Option<PathBuf>,
+    crate_type: CrateType,
+    post_generate_hook: Option<String>,
+    suggest_names: bool,
+    async_runtime: bool,
 }
 
 impl<'a, T: LlmProvider> ProjectBuilder<'a, T> {
     /// Creates a new ProjectBuilder with a reference to an LLM provider.
     pub fn new(llm_client: &'a T) -> Self {
-        Self { llm_client }
+        Self {
+            llm_client,
+            workspace_root: None,
+            crate_type: CrateType::default(),
+            post_generate_hook: None,
+            suggest_names: false,
+            async_runtime: false,
+        }
+    }
+
+    /// Adds every built project to the Cargo workspace at `workspace_root`.
+    ///
+    /// The workspace `Cargo.toml` is created if it does not exist. Projects must be built
+    /// inside the workspace directory; they then share its `Cargo.lock` and target directory.
+    pub fn with_workspace(mut self, workspace_root: &Path) -> Self {
+        self.workspace_root = Some(workspace_root.to_path_buf());
+        self
+    }
+
+    /// Sets the `crate-type` used when building a library crate.
+    pub fn with_crate_type(mut self, crate_type: CrateType) -> Self {
+        self.crate_type = crate_type;
+        self
+    }
+
+    /// Runs `command` through the shell in the project directory after each build, e.g.
+    /// `cargo fmt`. A failing command fails the build, with its output in the error.
+    pub fn with_post_generate_hook(mut self, command: &str) -> Self {
+        self.post_generate_hook = Some(command.to_string());
+        self
+    }
+
+    /// Asks the LLM to name each project after the meanings declared in its source.
+    ///
+    /// Off by default, since it sends a request to the LLM on every build. Projects are
+    /// otherwise named from `@package` and `@binary`, or after a hash of the source.
+    pub fn with_suggested_names(mut self, suggest_names: bool) -> Self {
+        self.suggest_names = suggest_names;
+        self
+    }
+
+    /// Adds tokio to the dependencies, for code with async functions, such as code for which
+    /// `CodeGenerator::has_async_functions` is true. Library crates always depend on it.
+    pub fn with_async_runtime(mut self, async_runtime: bool) -> Self {
+        self.async_runtime = async_runtime;
+        self
     }
 
     /// Builds the project structure in the output directory.
     ///
+    /// When the source file is generated again, the user region marked with
+    /// `USER_REGION_BEGIN` and `USER_REGION_END` in the previous file is kept. It replaces
+    /// the region in the new code if that has one, and is appended to the code otherwise.
+    ///
     /// # Arguments
     /// * `output_dir` - Path where the generated project directory will be created.
     /// * `vibelang_source` - The original VibeLang source code.
@@ -35,21 +154,126 @@ impl<'a, T: LlmProvider> ProjectBuilder<'a, T> {
 
         let (package_name, bin_name) = self.generate_project_names(vibelang_source)?;
         let vibelang_version = self.get_vibelang_version()?;
-        let cargo_content =
-            self.create_cargo_toml_content(&package_name, &bin_name, as_lib, &vibelang_version)?;
+        let cargo_content = self.create_cargo_toml_content(
+            &package_name,
+            &bin_name,
+            as_lib,
+            self.async_runtime,
+            &vibelang_version,
+        )?;
 
         fs::write(output_dir.join("Cargo.toml"), cargo_content)?;
 
         // Generate either lib.rs or main.rs based on as_lib parameter
-        if as_lib {
-            fs::write(src_dir.join("lib.rs"), generated_rust_code)?;
-        } else {
-            fs::write(src_dir.join("main.rs"), generated_rust_code)?;
+        let source_file = src_dir.join(if as_lib { "lib.rs" } else { "main.rs" });
+        let code = match fs::read_to_string(&source_file) {
+            Ok(previous) => keep_user_region(&previous, generated_rust_code),
+            Err(_) => generated_rust_code.to_string(),
+        };
+        fs::write(source_file, code)?;
+
+        if let Some(workspace_root) = &self.workspace_root {
+            self.add_workspace_member(workspace_root, output_dir)?;
+        }
+
+        if let Some(command) = &self.post_generate_hook {
+            run_hook(command, output_dir)?;
         }
 
         Ok(())
     }
 
+    /// Writes generated library code to `module_path` inside an existing crate, without
+    /// creating or changing any `Cargo.toml`.
+    ///
+    /// The user region of a previous file is kept, as `build` keeps it. Returns the
+    /// dependencies the code needs, as lines to add to the crate's `[dependencies]`.
+    ///
+    /// # Arguments
+    /// * `module_path` - Path of the `.rs` file to write, e.g. `src/vibe.rs`.
+    /// * `generated_rust_code` - The generated Rust code.
+    pub fn write_module(
+        &self,
+        module_path: &Path,
+        generated_rust_code: &str,
+    ) -> Result<Vec<String>> {
+        if let Some(parent) = module_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let code = match fs::read_to_string(module_path) {
+            Ok(previous) => keep_user_region(&previous, generated_rust_code),
+            Err(_) => generated_rust_code.to_string(),
+        };
+        fs::write(module_path, code)?;
+
+        let vibelang_version = self.get_vibelang_version()?;
+        Ok(dependencies(&vibelang_version, self.async_runtime))
+    }
+
+    /// Lists `member_dir` in the `members` of the workspace at `workspace_root`.
+    ///
+    /// Creates the workspace manifest if needed and leaves it untouched when the member is
+    /// already listed, so rebuilding a project does not add it twice.
+    fn add_workspace_member(&self, workspace_root: &Path, member_dir: &Path) -> Result<()> {
+        let root = workspace_root.canonicalize().with_context(|| {
+            format!(
+                "Workspace directory {} does not exist",
+                workspace_root.display()
+            )
+        })?;
+        let member = member_dir.canonicalize()?;
+        let relative = member.strip_prefix(&root).map_err(|_| {
+            anyhow!(
+                "Cannot add {} to the workspace at {}: the project must be inside the workspace directory",
+                member.display(),
+                root.display()
+            )
+        })?;
+        // Cargo expects forward slashes in member paths on every platform.
+        let member_path = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let manifest_path = root.join("Cargo.toml");
+        let mut manifest = if manifest_path.exists() {
+            fs::read_to_string(&manifest_path)?
+                .parse::<DocumentMut>()
+                .with_context(|| format!("Failed to parse {}", manifest_path.display()))?
+        } else {
+            DocumentMut::new()
+        };
+
+        let workspace = manifest
+            .entry("workspace")
+            .or_insert_with(|| Item::Table(Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("`workspace` in {} is not a table", manifest_path.display()))?;
+        if !workspace.contains_key("resolver") {
+            workspace.insert("resolver", value("2"));
+        }
+        let members = workspace
+            .entry("members")
+            .or_insert_with(|| value(Array::new()))
+            .as_array_mut()
+            .ok_or_else(|| {
+                anyhow!(
+                    "`workspace.members` in {} is not an array",
+                    manifest_path.display()
+                )
+            })?;
+
+        if !members
+            .iter()
+            .any(|m| m.as_str() == Some(member_path.as_str()))
+        {
+            members.push(member_path);
+            fs::write(&manifest_path, manifest.to_string())?;
+        }
+        Ok(())
+    }
+
     /// Reads the current vibelang version from the library's Cargo.toml file.
     fn get_vibelang_version(&self) -> Result<String> {
         // Try to find the Cargo.toml file in the current workspace
@@ -74,8 +298,7 @@ impl<'a, T: LlmProvider> ProjectBuilder<'a, T> {
 
     /// Extracts the version from Cargo.toml content.
     fn extract_version_from_cargo_toml(&self, content: &str) -> Option<String> {
-        let version_regex = Regex::new(r#"(?m)^version\s*=\s*"([^"]+)""#).ok()?;
-        version_regex
+        VERSION_RE
             .captures(content)?
             .get(1)
             .map(|m| m.as_str().to_string())
@@ -87,12 +310,14 @@ impl<'a, T: LlmProvider> ProjectBuilder<'a, T> {
     /// * `package_name` - The name of the package.
     /// * `bin_name` - The name of the binary (only used for binary crates).
     /// * `as_lib` - If true, generates library configuration; if false, generates binary configuration.
+    /// * `async_runtime` - If true, the binary depends on tokio to run async functions.
     /// * `vibelang_version` - The version of vibelang to use as dependency.
     fn create_cargo_toml_content(
         &self,
         package_name: &str,
         bin_name: &str,
         as_lib: bool,
+        async_runtime: bool,
         vibelang_version: &str,
     ) -> Result<String> {
         if as_lib {
@@ -103,19 +328,16 @@ version = "0.1.0"
 edition = "2024"
 
 [dependencies]
-vibelang = "{}"
-anyhow = "1.0"
-reqwest = {{ version = "0.12", features = ["json", "blocking"] }}
-serde_json = "1.0"
-tokio = {{ version = "1.0", features = ["full"] }}
+{}
 
 [lib]
 name = "{}"
-crate-type = ["rlib"]
+crate-type = ["{}"]
 "#,
                 package_name,
-                vibelang_version,
-                package_name.replace("-", "_")
+                dependencies(vibelang_version, true).join("\n"),
+                package_name.replace("-", "_"),
+                self.crate_type.as_str()
             ))
         } else {
             Ok(format!(
@@ -125,50 +347,170 @@ version = "0.1.0"
 edition = "2024"
 
 [dependencies]
-vibelang = "{}"
-anyhow = "1.0"
-reqwest = {{ version = "0.12", features = ["json", "blocking"] }}
-serde_json = "1.0"
+{}
 
 [[bin]]
 name = "{}"
 path = "src/main.rs"
 "#,
-                package_name, vibelang_version, bin_name
+                package_name,
+                dependencies(vibelang_version, async_runtime).join("\n"),
+                bin_name
             ))
         }
     }
 
     fn extract_semantic_annotations(&self, source: &str) -> Vec<String> {
-        let re = Regex::new(r#"Meaning<.+?>"(.+?)""#).unwrap();
-        re.captures_iter(source)
+        SEMANTIC_ANNOTATION_RE
+            .captures_iter(source)
             .map(|cap| cap[1].to_string())
             .collect()
     }
 
+    /// Names the package and binary, preferring `@package("...")` and `@binary("...")` in
+    /// the source over names suggested by the LLM, when it is asked for them.
     fn generate_project_names(&self, source: &str) -> Result<(String, String)> {
+        // Sources that don't parse have no annotations; codegen reports their errors. Located
+        // errors, such as a name Cargo would not accept, are returned as they are.
+        let program = match parse_source(source) {
+            Ok(program) => Some(program),
+            Err(e) if e.is::<SourceError>() => return Err(e),
+            Err(_) => None,
+        };
+        let package = program
+            .as_ref()
+            .and_then(|p| p.get_string("package").cloned());
+        let binary = program
+            .as_ref()
+            .and_then(|p| p.get_string("binary").cloned());
+
+        if let (Some(package), Some(binary)) = (&package, &binary) {
+            return Ok((package.clone(), binary.clone()));
+        }
+        let (suggested_package, suggested_binary) = self.suggest_project_names(source)?;
+        Ok((
+            package.unwrap_or(suggested_package),
+            binary.unwrap_or(suggested_binary),
+        ))
+    }
+
+    fn suggest_project_names(&self, source: &str) -> Result<(String, String)> {
         let annotations = self.extract_semantic_annotations(source);
-        if annotations.is_empty() {
-            return Ok(("vibe-project".to_string(), "vibeapp".to_string()));
+        if !self.suggest_names || annotations.is_empty() {
+            return Ok(fallback_project_names(source));
         }
 
-        // This can generate context-aware project names
-        // let context = annotations.join(", ");
-        // let prompt = format!(
-        //     "Based on these concepts: {}, suggest a snake_case Rust package name and a binary name. Respond ONLY with JSON in the format {{\"packagename\": \"name\", \"binname\": \"name\"}}",
-        //     context
-        // );
+        // Ask the LLM for context-aware project names, falling back to defaults.
+        let context = annotations.join(", ");
+        let prompt = format!(
+            "Based on these concepts: {}, suggest a snake_case Rust package name and a binary name. Respond ONLY with JSON in the format {{\"packagename\": \"name\", \"binname\": \"name\"}}",
+            context
+        );
+
+        if let Ok(response) = self.llm_client.generate(&prompt)
+            && let Ok(json) = serde_json::from_str::<serde_json::Value>(&response)
+        {
+            let (fallback_package, fallback_binary) = fallback_project_names(source);
+            let package = json["packagename"]
+                .as_str()
+                .map(|name| name.replace("_", "-"))
+                .unwrap_or(fallback_package);
+            let binary = json["binname"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or(fallback_binary);
+            return Ok((package, binary));
+        }
+
+        Ok(fallback_project_names(source))
+    }
+}
+
+/// Names for a project the LLM did not name, such as `vibe-project-1a2b3c4d` and
+/// `vibeapp-1a2b3c4d`. The suffix is a hash of the source, so projects generated from
+/// different sources into sibling directories do not collide, and regenerating one keeps
+/// its names.
+fn fallback_project_names(source: &str) -> (String, String) {
+    // FNV-1a, rather than `DefaultHasher`, whose output may change between Rust releases.
+    let hash = source.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    (
+        format!("vibe-project-{:08x}", hash),
+        format!("vibeapp-{:08x}", hash),
+    )
+}
+
+/// The byte range of the user region in `code`, from the start of its `USER_REGION_BEGIN`
+/// marker to the end of the first `USER_REGION_END` marker after it.
+fn user_region(code: &str) -> Option<(usize, usize)> {
+    let start = code.find(USER_REGION_BEGIN)?;
+    let end = start + code[start..].find(USER_REGION_END)? + USER_REGION_END.len();
+    Some((start, end))
+}
+
+/// Carries the user region of `previous` over into `generated`.
+fn keep_user_region(previous: &str, generated: &str) -> String {
+    let Some((start, end)) = user_region(previous) else {
+        return generated.to_string();
+    };
+    let region = &previous[start..end];
+    match user_region(generated) {
+        Some((new_start, new_end)) => {
+            format!(
+                "{}{}{}",
+                &generated[..new_start],
+                region,
+                &generated[new_end..]
+            )
+        }
+        None => {
+            let separator = if generated.ends_with('\n') { "" } else { "\n" };
+            format!("{}{}\n{}\n", generated, separator, region)
+        }
+    }
+}
 
-        // if let Ok(response) = self.llm_client.generate(&prompt) {
-        //     if let Ok(json) = serde_json::from_str::<serde_json::Value>(&response) {
-        //         let package = json["packagename"].as_str().unwrap_or("vibe-project").replace("_", "-");
-        //         let binary = json["binname"].as_str().unwrap_or("vibeapp").to_string();
-        //         return Ok((package, binary));
-        //     }
-        // }
+/// Runs a post-generation hook in `dir`, failing with its output if it does not succeed.
+fn run_hook(command: &str, dir: &Path) -> Result<()> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let output = shell
+        .arg(command)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("Failed to start post-generation hook `{}`", command))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Post-generation hook `{}` failed with {}:\n{}{}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(())
+}
 
-        Ok(("vibe-project".to_string(), "vibeapp".to_string()))
+/// The `[dependencies]` lines of a generated crate; tokio only when it runs async code.
+fn dependencies(vibelang_version: &str, tokio: bool) -> Vec<String> {
+    let mut lines = vec![
+        format!("vibelang = \"{}\"", vibelang_version),
+        "anyhow = \"1.0\"".to_string(),
+        "reqwest = { version = \"0.12\", features = [\"json\", \"blocking\"] }".to_string(),
+        "serde_json = \"1.0\"".to_string(),
+    ];
+    if tokio {
+        lines.push("tokio = { version = \"1.0\", features = [\"full\"] }".to_string());
     }
+    lines
 }
 
 #[cfg(test)]
@@ -176,6 +518,141 @@ mod tests {
     use super::*;
     use crate::runtime::llm_provider::MockLlmProvider;
 
+    #[test]
+    fn test_workspace_members_are_added_once() {
+        let mock_client = MockLlmProvider::new();
+        let workspace = tempfile::tempdir().unwrap();
+        let builder = ProjectBuilder::new(&mock_client).with_workspace(workspace.path());
+        let source = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+
+        for name in ["first", "second", "first"] {
+            builder
+                .build(&workspace.path().join(name), source, "fn main() {}", false)
+                .unwrap();
+        }
+
+        let manifest = fs::read_to_string(workspace.path().join("Cargo.toml")).unwrap();
+        let document = manifest.parse::<DocumentMut>().unwrap();
+        let members: Vec<&str> = document["workspace"]["members"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|m| m.as_str())
+            .collect();
+        assert_eq!(members, vec!["first", "second"]);
+    }
+
+    #[test]
+    fn test_workspace_member_must_be_inside_workspace() {
+        let mock_client = MockLlmProvider::new();
+        let workspace = tempfile::tempdir().unwrap();
+        let elsewhere = tempfile::tempdir().unwrap();
+        let builder = ProjectBuilder::new(&mock_client).with_workspace(workspace.path());
+
+        let result = builder.build(elsewhere.path(), "", "fn main() {}", false);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_post_generate_hook_runs_in_output_directory() {
+        let mock_client = MockLlmProvider::new();
+        let output = tempfile::tempdir().unwrap();
+        let source = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+
+        ProjectBuilder::new(&mock_client)
+            .with_post_generate_hook("touch hooked")
+            .build(output.path(), source, "fn main() {}", false)
+            .unwrap();
+        assert!(output.path().join("hooked").exists());
+
+        let error = ProjectBuilder::new(&mock_client)
+            .with_post_generate_hook("echo formatting; exit 3")
+            .build(output.path(), source, "fn main() {}", false)
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("`echo formatting; exit 3` failed"),
+            "{}",
+            error
+        );
+        assert!(error.contains("formatting"), "{}", error);
+    }
+
+    #[test]
+    fn test_tokio_dependency_follows_the_async_runtime_option() {
+        let mock_client = MockLlmProvider::new();
+        let output = tempfile::tempdir().unwrap();
+        let source = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+        let manifest = || fs::read_to_string(output.path().join("Cargo.toml")).unwrap();
+
+        // Decided by the option, not by what the code happens to contain.
+        ProjectBuilder::new(&mock_client)
+            .build(
+                output.path(),
+                source,
+                "#[tokio::main]\nasync fn main() {}",
+                false,
+            )
+            .unwrap();
+        assert!(!manifest().contains("tokio"));
+
+        ProjectBuilder::new(&mock_client)
+            .with_async_runtime(true)
+            .build(output.path(), source, "fn main() {}", false)
+            .unwrap();
+        assert!(manifest().contains("tokio"));
+    }
+
+    #[test]
+    fn test_user_region_survives_regeneration() {
+        let mock_client = MockLlmProvider::new();
+        let output = tempfile::tempdir().unwrap();
+        let builder = ProjectBuilder::new(&mock_client);
+        let source = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+        builder
+            .build(output.path(), source, "fn generated_v1() {}\n", false)
+            .unwrap();
+
+        let main_rs = output.path().join("src/main.rs");
+        let edited = format!(
+            "{}\n{}\nfn custom() {{}}\n{}\n",
+            fs::read_to_string(&main_rs).unwrap(),
+            USER_REGION_BEGIN,
+            USER_REGION_END
+        );
+        fs::write(&main_rs, edited).unwrap();
+        builder
+            .build(output.path(), source, "fn generated_v2() {}\n", false)
+            .unwrap();
+
+        let regenerated = fs::read_to_string(&main_rs).unwrap();
+        assert!(
+            regenerated.starts_with("fn generated_v2() {}\n"),
+            "{}",
+            regenerated
+        );
+        assert!(!regenerated.contains("generated_v1"), "{}", regenerated);
+        assert!(regenerated.contains(&format!(
+            "{}\nfn custom() {{}}\n{}",
+            USER_REGION_BEGIN, USER_REGION_END
+        )));
+
+        // A region in the new code is replaced by the kept one, in place.
+        let generated = format!(
+            "fn a() {{}}\n{}\n{}\nfn b() {{}}\n",
+            USER_REGION_BEGIN, USER_REGION_END
+        );
+        let kept = keep_user_region(&regenerated, &generated);
+        assert_eq!(
+            kept,
+            format!(
+                "fn a() {{}}\n{}\nfn custom() {{}}\n{}\nfn b() {{}}\n",
+                USER_REGION_BEGIN, USER_REGION_END
+            )
+        );
+    }
+
     #[test]
     fn test_version_extraction() {
         let mock_client = MockLlmProvider::new();
@@ -194,18 +671,56 @@ serde = "1.0"
         assert_eq!(version, Some("0.2.5".to_string()));
     }
 
+    #[test]
+    fn test_regexes_are_compiled_once_and_reused() {
+        let mock_client = MockLlmProvider::new();
+        let builder = ProjectBuilder::new(&mock_client);
+        let source = r#"
+            type Capital = Meaning<String>("the capital city");
+            type Year = Meaning<Int>( "a calendar year" );
+        "#;
+
+        let first = builder.extract_semantic_annotations(source);
+        let regex_after_first_call: *const Regex = &*SEMANTIC_ANNOTATION_RE;
+        let second = builder.extract_semantic_annotations(source);
+
+        assert_eq!(first, vec!["the capital city", "a calendar year"]);
+        assert_eq!(first, second);
+        assert!(std::ptr::eq(
+            regex_after_first_call,
+            &*SEMANTIC_ANNOTATION_RE
+        ));
+        assert_eq!(
+            builder.extract_version_from_cargo_toml("[package]\nversion = \"1.2.3\"\n"),
+            Some("1.2.3".to_string())
+        );
+        assert_eq!(builder.extract_version_from_cargo_toml("[package]\n"), None);
+    }
+
     #[test]
     fn test_cargo_toml_generation_binary_with_version() {
         let mock_client = MockLlmProvider::new();
         let builder = ProjectBuilder::new(&mock_client);
         let content = builder
-            .create_cargo_toml_content("mycoolpackage", "myapp", false, "0.2.5")
+            .create_cargo_toml_content("mycoolpackage", "myapp", false, false, "0.2.5")
             .unwrap();
         assert!(content.contains(r#"name = "mycoolpackage""#));
         assert!(content.contains(r#"name = "myapp""#));
         assert!(content.contains("[[bin]]"));
         assert!(content.contains(r#"vibelang = "0.2.5""#));
         assert!(!content.contains("[lib]"));
+        assert!(!content.contains("tokio"));
+    }
+
+    #[test]
+    fn test_cargo_toml_generation_binary_with_async_runtime() {
+        let mock_client = MockLlmProvider::new();
+        let builder = ProjectBuilder::new(&mock_client);
+        let content = builder
+            .create_cargo_toml_content("mycoolpackage", "myapp", false, true, "0.2.5")
+            .unwrap();
+        assert!(content.contains("tokio"));
+        assert!(content.contains("[[bin]]"));
     }
 
     #[test]
@@ -213,13 +728,33 @@ serde = "1.0"
         let mock_client = MockLlmProvider::new();
         let builder = ProjectBuilder::new(&mock_client);
         let content = builder
-            .create_cargo_toml_content("mycoolpackage", "myapp", true, "0.2.5")
+            .create_cargo_toml_content("mycoolpackage", "myapp", true, false, "0.2.5")
             .unwrap();
         assert!(content.contains(r#"name = "mycoolpackage""#));
         assert!(content.contains("[lib]"));
         assert!(content.contains("tokio"));
         assert!(content.contains(r#"vibelang = "0.2.5""#));
         assert!(!content.contains("[[bin]]"));
+        assert!(content.contains(r#"crate-type = ["rlib"]"#));
+    }
+
+    #[test]
+    fn test_cargo_toml_generation_library_with_cdylib() {
+        let mock_client = MockLlmProvider::new();
+        let default_content = ProjectBuilder::new(&mock_client)
+            .create_cargo_toml_content("mycoolpackage", "myapp", true, false, "0.2.5")
+            .unwrap();
+        let content = ProjectBuilder::new(&mock_client)
+            .with_crate_type("cdylib".parse().unwrap())
+            .create_cargo_toml_content("mycoolpackage", "myapp", true, false, "0.2.5")
+            .unwrap();
+
+        assert!(content.contains(r#"crate-type = ["cdylib"]"#));
+        assert_eq!(
+            content.replace(r#"["cdylib"]"#, r#"["rlib"]"#),
+            default_content
+        );
+        assert!("wasm".parse::<CrateType>().is_err());
     }
 
     #[test]
@@ -229,7 +764,7 @@ serde = "1.0"
             Ok(r#"{"packagename": "geography_tools", "binname": "capitalfinder"}"#.to_string())
         });
 
-        let builder = ProjectBuilder::new(&mock_client);
+        let builder = ProjectBuilder::new(&mock_client).with_suggested_names(true);
         let source = r#"type Capital = Meaning<String>("the capital city of a country")"#;
         let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
 
@@ -245,12 +780,23 @@ serde = "1.0"
             .times(1)
             .returning(|_| Ok("I am not valid JSON.".to_string()));
 
+        let builder = ProjectBuilder::new(&mock_client).with_suggested_names(true);
+        let source = r#"type Capital = Meaning<String>("a capital city")"#;
+        let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
+
+        assert_eq!((package_name, bin_name), fallback_project_names(source));
+    }
+
+    #[test]
+    fn test_names_are_not_suggested_unless_asked_for() {
+        // No expectations: the LLM must not be asked by default.
+        let mock_client = MockLlmProvider::new();
+
         let builder = ProjectBuilder::new(&mock_client);
         let source = r#"type Capital = Meaning<String>("a capital city")"#;
         let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
 
-        assert_eq!(package_name, "vibe-project");
-        assert_eq!(bin_name, "vibeapp");
+        assert_eq!((package_name, bin_name), fallback_project_names(source));
     }
 
     #[test]
@@ -261,7 +807,76 @@ serde = "1.0"
         let source = r#"fn get_year() -> Int { prompt "What year is it?" }"#;
         let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
 
-        assert_eq!(package_name, "vibe-project");
-        assert_eq!(bin_name, "vibeapp");
+        assert!(
+            package_name.starts_with("vibe-project-"),
+            "{}",
+            package_name
+        );
+        assert!(bin_name.starts_with("vibeapp-"), "{}", bin_name);
+    }
+
+    #[test]
+    fn test_fallback_names_are_distinct_per_source_and_stable() {
+        let year = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+        let month = r#"fn get_month() -> Int { prompt "What month is it?"; }"#;
+
+        assert_eq!(fallback_project_names(year), fallback_project_names(year));
+        assert_ne!(
+            fallback_project_names(year).0,
+            fallback_project_names(month).0
+        );
+        assert_ne!(
+            fallback_project_names(year).1,
+            fallback_project_names(month).1
+        );
+    }
+
+    #[test]
+    fn test_name_annotations_take_precedence() {
+        // No expectations: the LLM must not be asked when both names are given.
+        let mock_client = MockLlmProvider::new();
+
+        let builder = ProjectBuilder::new(&mock_client);
+        let source = r#"
+            @package("weather-tools")
+            @binary("forecast")
+            fn get_year() -> Int { prompt "What year is it?"; }
+        "#;
+        let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
+
+        assert_eq!(package_name, "weather-tools");
+        assert_eq!(bin_name, "forecast");
+    }
+
+    #[test]
+    fn test_name_annotations_cargo_rejects_are_reported() {
+        let mock_client = MockLlmProvider::new();
+
+        let builder = ProjectBuilder::new(&mock_client);
+        let source = "fn get_year() -> Int { prompt \"What year is it?\"; }\n@package(\"my app!\")";
+        let error = builder.generate_project_names(source).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Line 2, column 1: `@package(\"my app!\")` is not a name Cargo accepts: use ASCII letters, digits, `-` and `_`, start with a letter or `_`, and avoid Rust keywords"
+        );
+        assert!(
+            builder
+                .generate_project_names("@binary(\"2fast\")")
+                .is_err()
+        );
+        assert!(builder.generate_project_names("@binary(\"fn\")").is_err());
+    }
+
+    #[test]
+    fn test_binary_annotation_keeps_fallback_package_name() {
+        let mock_client = MockLlmProvider::new();
+
+        let builder = ProjectBuilder::new(&mock_client);
+        let source = r#"@binary("yearbot") fn get_year() -> Int { prompt "What year is it?"; }"#;
+        let (package_name, bin_name) = builder.generate_project_names(source).unwrap();
+
+        assert_eq!(package_name, fallback_project_names(source).0);
+        assert_eq!(bin_name, "yearbot");
     }
 }
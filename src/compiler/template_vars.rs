@@ -0,0 +1,126 @@
+//! Finds the context variables a Tera template reads, so custom templates can be checked
+//! against the variables `CodeGenerator` provides before rendering.
+
+use std::collections::BTreeSet;
+use tera::ast::{Expr, ExprVal, FunctionCall, Node};
+
+/// Returns the root names of the context variables read by `nodes`, such as `functions` for
+/// `{{ functions | length }}` or `header` for `{{ header.version }}`.
+///
+/// Names bound by `for` loops and `set` are not reported, nor are variables guarded by the
+/// `default` filter or by an `is defined` test.
+pub(crate) fn referenced_variables(nodes: &[Node]) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let mut bound = Vec::new();
+    visit_nodes(nodes, &mut bound, &mut found);
+    found
+}
+
+fn visit_nodes(nodes: &[Node], bound: &mut Vec<String>, found: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::VariableBlock(_, expr) => visit_expr(expr, bound, found),
+            Node::Set(_, set) => {
+                visit_expr(&set.value, bound, found);
+                bound.push(set.key.clone());
+            }
+            Node::FilterSection(_, section, _) => {
+                visit_call(&section.filter, bound, found);
+                visit_nodes(&section.body, bound, found);
+            }
+            Node::Block(_, block, _) => visit_nodes(&block.body, bound, found),
+            Node::Forloop(_, forloop, _) => {
+                visit_expr(&forloop.container, bound, found);
+                let scope = bound.len();
+                bound.extend(forloop.key.iter().cloned());
+                bound.push(forloop.value.clone());
+                bound.push("loop".to_string());
+                visit_nodes(&forloop.body, bound, found);
+                bound.truncate(scope);
+                if let Some(empty_body) = &forloop.empty_body {
+                    visit_nodes(empty_body, bound, found);
+                }
+            }
+            Node::If(if_node, _) => {
+                for (_, condition, body) in &if_node.conditions {
+                    visit_expr(condition, bound, found);
+                    visit_nodes(body, bound, found);
+                }
+                if let Some((_, body)) = &if_node.otherwise {
+                    visit_nodes(body, bound, found);
+                }
+            }
+            Node::MacroDefinition(_, definition, _) => {
+                // Macros only see their own arguments.
+                let mut macro_bound: Vec<String> = definition.args.keys().cloned().collect();
+                visit_nodes(&definition.body, &mut macro_bound, found);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn visit_expr(expr: &Expr, bound: &[String], found: &mut BTreeSet<String>) {
+    if expr.has_default_filter() {
+        return;
+    }
+    for filter in &expr.filters {
+        visit_call(filter, bound, found);
+    }
+
+    match &expr.val {
+        ExprVal::Ident(name) => record(name, bound, found),
+        ExprVal::Math(math) => {
+            visit_expr(&math.lhs, bound, found);
+            visit_expr(&math.rhs, bound, found);
+        }
+        ExprVal::Logic(logic) => {
+            visit_expr(&logic.lhs, bound, found);
+            visit_expr(&logic.rhs, bound, found);
+        }
+        ExprVal::Test(test) => {
+            if test.name != "defined" && test.name != "undefined" {
+                record(&test.ident, bound, found);
+            }
+            for arg in &test.args {
+                visit_expr(arg, bound, found);
+            }
+        }
+        ExprVal::MacroCall(call) => {
+            for arg in call.args.values() {
+                visit_expr(arg, bound, found);
+            }
+        }
+        ExprVal::FunctionCall(call) => visit_call(call, bound, found),
+        ExprVal::Array(items) => {
+            for item in items {
+                visit_expr(item, bound, found);
+            }
+        }
+        ExprVal::StringConcat(concat) => {
+            for value in &concat.values {
+                if let ExprVal::Ident(name) = value {
+                    record(name, bound, found);
+                }
+            }
+        }
+        ExprVal::In(in_expr) => {
+            visit_expr(&in_expr.lhs, bound, found);
+            visit_expr(&in_expr.rhs, bound, found);
+        }
+        ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+    }
+}
+
+fn visit_call(call: &FunctionCall, bound: &[String], found: &mut BTreeSet<String>) {
+    for arg in call.args.values() {
+        visit_expr(arg, bound, found);
+    }
+}
+
+fn record(name: &str, bound: &[String], found: &mut BTreeSet<String>) {
+    let root = name.split(['.', '[']).next().unwrap_or(name);
+    if !bound.iter().any(|b| b == root) {
+        found.insert(root.to_string());
+    }
+}
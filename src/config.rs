@@ -1,9 +1,137 @@
+#[cfg(feature = "runtime")]
+use anyhow::{Result, anyhow};
+#[cfg(feature = "runtime")]
+use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderName, HeaderValue};
 use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Clone)] // Added Clone for convenience
+/// The LLM backend the client talks to, which decides the request and response formats.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    /// Ollama's native `/api/generate` endpoint.
+    #[default]
+    Ollama,
+    /// An OpenAI-compatible `/v1/chat/completions` endpoint.
+    #[cfg(feature = "openai")]
+    OpenAi,
+    /// No backend: `EchoProvider` answers locally with deterministic canned replies.
+    Echo,
+}
+
+impl Provider {
+    /// Looks up a provider by its case-insensitive name: `ollama`, `openai` or `echo`.
+    /// `openai` is only known with the `openai` feature.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "ollama" => Some(Self::Ollama),
+            #[cfg(feature = "openai")]
+            "openai" => Some(Self::OpenAi),
+            "echo" => Some(Self::Echo),
+            _ => None,
+        }
+    }
+}
+
+/// A provider of the fallback chain in `Config::providers`, with the endpoint, model and API
+/// key its requests use. Settings left unset are taken from the `Config` itself.
+#[derive(Deserialize, Clone, PartialEq, Eq)]
+pub struct ProviderSettings {
+    pub provider: Provider,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub api_key: Option<String>,
+}
+
+impl From<Provider> for ProviderSettings {
+    /// Settings that use the `Config`'s endpoint, model and key.
+    fn from(provider: Provider) -> Self {
+        Self {
+            provider,
+            base_url: None,
+            model: None,
+            api_key: None,
+        }
+    }
+}
+
+impl fmt::Debug for ProviderSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProviderSettings")
+            .field("provider", &self.provider)
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// A provider with the settings a request to it is sent with, as resolved by
+/// `Config::provider_chain`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Backend {
+    pub provider: Provider,
+    pub base_url: String,
+    pub model: String,
+    pub api_key: Option<String>,
+}
+
+impl fmt::Debug for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Backend")
+            .field("provider", &self.provider)
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+/// The environment variable naming the file `LlmClient` appends its prompt trace to.
+pub const TRACE_PROMPTS_ENV: &str = "VIBELANG_TRACE_PROMPTS";
+
+/// `Debug` and `Display` both redact the API key and sensitive-looking header values, so
+/// a config can be logged safely.
+#[derive(Deserialize, Clone)] // Added Clone for convenience
 pub struct Config {
     pub ollama_base_url: String,
     pub ollama_model: String,
+    #[serde(default)]
+    pub provider: Provider,
+    /// Providers tried in order until one answers, e.g. a local Ollama and then a hosted
+    /// OpenAI-compatible backend, each with its own endpoint, model and key if needed.
+    /// When empty, only `provider` is used.
+    #[serde(default)]
+    pub providers: Vec<ProviderSettings>,
+    /// Sent as a bearer token when set, for backends that require one.
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Added to every request, for gateways that need e.g. a routing key.
+    #[serde(default)]
+    pub extra_headers: HashMap<String, String>,
+    /// When set, every prompt and response is appended to this file as a JSON line.
+    #[serde(default)]
+    pub trace_path: Option<PathBuf>,
+    /// Put before every prompt, for models that need particular phrasing to answer tersely.
+    #[serde(default)]
+    pub prompt_prefix: String,
+    /// Put after every prompt.
+    #[serde(default)]
+    pub prompt_suffix: String,
+    /// Sequences at which the model stops generating. None are sent when empty.
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// Seconds after which a request to the backend is abandoned. No limit when unset.
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+    /// How often a failed request is sent again before the call fails.
+    #[serde(default)]
+    pub retries: usize,
 }
 
 impl Config {
@@ -14,8 +142,131 @@ impl Config {
             ollama_base_url: std::env::var("OLLAMA_BASE_URL")
                 .unwrap_or_else(|_| "http://localhost:11434".to_string()),
             ollama_model: std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| "llama3.1".to_string()),
+            provider: std::env::var("VIBELANG_PROVIDER")
+                .ok()
+                .and_then(|name| Provider::from_name(&name))
+                .unwrap_or_default(),
+            providers: std::env::var("VIBELANG_PROVIDERS")
+                .map(|names| {
+                    names
+                        .split(',')
+                        .filter_map(|name| provider_settings_from_env(name.trim()))
+                        .collect()
+                })
+                .unwrap_or_default(),
+            api_key: std::env::var("VIBELANG_API_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            extra_headers: std::env::var("VIBELANG_EXTRA_HEADERS")
+                .map(|headers| parse_headers(&headers))
+                .unwrap_or_default(),
+            trace_path: std::env::var_os(TRACE_PROMPTS_ENV).map(PathBuf::from),
+            prompt_prefix: std::env::var("VIBELANG_PROMPT_PREFIX").unwrap_or_default(),
+            prompt_suffix: std::env::var("VIBELANG_PROMPT_SUFFIX").unwrap_or_default(),
+            stop: Vec::new(),
+            timeout_secs: std::env::var("VIBELANG_TIMEOUT_SECS")
+                .ok()
+                .and_then(|secs| secs.parse().ok()),
+            retries: std::env::var("VIBELANG_RETRIES")
+                .ok()
+                .and_then(|retries| retries.parse().ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// The providers a request is sent to, in order, until one of them answers.
+    pub fn provider_chain(&self) -> Vec<Backend> {
+        if self.providers.is_empty() {
+            vec![self.backend(&self.provider.into())]
+        } else {
+            self.providers
+                .iter()
+                .map(|settings| self.backend(settings))
+                .collect()
         }
     }
+
+    /// The backend for `settings`, with the settings it leaves unset taken from this config.
+    pub fn backend(&self, settings: &ProviderSettings) -> Backend {
+        Backend {
+            provider: settings.provider,
+            base_url: settings
+                .base_url
+                .clone()
+                .unwrap_or_else(|| self.ollama_base_url.clone()),
+            model: settings
+                .model
+                .clone()
+                .unwrap_or_else(|| self.ollama_model.clone()),
+            api_key: settings.api_key.clone().or_else(|| self.api_key.clone()),
+        }
+    }
+
+    /// The headers sent with every request to the backend: the extra headers, and the API
+    /// key as a bearer token when it is set.
+    #[cfg(feature = "runtime")]
+    pub fn request_headers(&self) -> Result<HeaderMap> {
+        self.headers_with_key(self.api_key.as_deref())
+    }
+
+    /// Like `request_headers`, with the API key of `backend`.
+    #[cfg(feature = "runtime")]
+    pub fn request_headers_for(&self, backend: &Backend) -> Result<HeaderMap> {
+        self.headers_with_key(backend.api_key.as_deref())
+    }
+
+    #[cfg(feature = "runtime")]
+    fn headers_with_key(&self, api_key: Option<&str>) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.extra_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| anyhow!("Invalid header name `{}`: {}", name, e))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| anyhow!("Invalid value for header `{}`: {}", name, e))?;
+            headers.insert(name, value);
+        }
+        if let Some(api_key) = api_key {
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", api_key))
+                .map_err(|e| anyhow!("Invalid API key: {}", e))?;
+            value.set_sensitive(true);
+            headers.insert(AUTHORIZATION, value);
+        }
+        Ok(headers)
+    }
+}
+
+/// Reads a provider of `VIBELANG_PROVIDERS` and its own settings, e.g. `VIBELANG_OPENAI_MODEL`
+/// for `openai`. Unknown names are skipped.
+fn provider_settings_from_env(name: &str) -> Option<ProviderSettings> {
+    let provider = Provider::from_name(name)?;
+    let setting = |suffix: &str| {
+        std::env::var(format!("VIBELANG_{}_{}", name.to_ascii_uppercase(), suffix))
+            .ok()
+            .filter(|value| !value.is_empty())
+    };
+    Some(ProviderSettings {
+        provider,
+        base_url: setting("BASE_URL"),
+        model: setting("MODEL"),
+        api_key: setting("API_KEY"),
+    })
+}
+
+/// Reads headers written as `Name: value` pairs separated by `;`, skipping malformed pairs.
+fn parse_headers(text: &str) -> HashMap<String, String> {
+    text.split(';')
+        .filter_map(|pair| pair.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+// Whether a header's value should be hidden when the configuration is shown.
+fn is_sensitive_header(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    ["auth", "key", "token", "secret", "cookie", "password"]
+        .iter()
+        .any(|word| name.contains(word))
 }
 
 // NEW: Implement the Default trait for Config.
@@ -25,6 +276,186 @@ impl Default for Config {
         Self {
             ollama_base_url: "http://localhost:11434".to_string(),
             ollama_model: "llama3.1".to_string(),
+            provider: Provider::default(),
+            providers: Vec::new(),
+            api_key: None,
+            extra_headers: HashMap::new(),
+            trace_path: None,
+            prompt_prefix: String::new(),
+            prompt_suffix: String::new(),
+            stop: Vec::new(),
+            timeout_secs: None,
+            retries: 0,
+        }
+    }
+}
+
+impl Config {
+    // The extra headers sorted by name, with sensitive-looking values redacted.
+    fn redacted_headers(&self) -> BTreeMap<&str, &str> {
+        self.extra_headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if is_sensitive_header(name) {
+                    "<redacted>"
+                } else {
+                    value.as_str()
+                };
+                (name.as_str(), value)
+            })
+            .collect()
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config")
+            .field("ollama_base_url", &self.ollama_base_url)
+            .field("ollama_model", &self.ollama_model)
+            .field("provider", &self.provider)
+            .field("providers", &self.providers)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .field("extra_headers", &self.redacted_headers())
+            .field("trace_path", &self.trace_path)
+            .field("prompt_prefix", &self.prompt_prefix)
+            .field("prompt_suffix", &self.prompt_suffix)
+            .field("stop", &self.stop)
+            .field("timeout_secs", &self.timeout_secs)
+            .field("retries", &self.retries)
+            .finish()
+    }
+}
+
+impl fmt::Display for Config {
+    /// Lists the settings one per line, with the API key and sensitive-looking header
+    /// values redacted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "provider:  {:?}", self.provider)?;
+        if !self.providers.is_empty() {
+            for backend in self.provider_chain() {
+                writeln!(
+                    f,
+                    "fallback:  {:?} at {}, model {}",
+                    backend.provider, backend.base_url, backend.model
+                )?;
+            }
+        }
+        writeln!(f, "base URL:  {}", self.ollama_base_url)?;
+        writeln!(f, "model:     {}", self.ollama_model)?;
+        let api_key = if self.api_key.is_some() {
+            "<redacted>"
+        } else {
+            "not set"
+        };
+        writeln!(f, "API key:   {}", api_key)?;
+        for (name, value) in self.redacted_headers() {
+            writeln!(f, "header:    {}: {}", name, value)?;
+        }
+        if !self.prompt_prefix.is_empty() {
+            writeln!(f, "prefix:    {:?}", self.prompt_prefix)?;
+        }
+        if !self.prompt_suffix.is_empty() {
+            writeln!(f, "suffix:    {:?}", self.prompt_suffix)?;
+        }
+        if !self.stop.is_empty() {
+            writeln!(f, "stop:      {:?}", self.stop)?;
+        }
+        if let Some(secs) = self.timeout_secs {
+            writeln!(f, "timeout:   {}s", secs)?;
         }
+        if self.retries > 0 {
+            writeln!(f, "retries:   {}", self.retries)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_redacts_the_api_key() {
+        let config = Config {
+            ollama_model: "mistral".to_string(),
+            api_key: Some("sk-secret".to_string()),
+            ..Config::default()
+        };
+
+        let text = config.to_string();
+
+        assert!(text.contains("model:     mistral"), "{}", text);
+        assert!(text.contains("API key:   <redacted>"), "{}", text);
+        assert!(!text.contains("sk-secret"), "{}", text);
+        assert!(Config::default().to_string().contains("API key:   not set"));
+    }
+
+    #[test]
+    fn test_extra_headers_are_parsed_and_redacted() {
+        let config = Config {
+            extra_headers: parse_headers("X-Route: eu-west; X-Gateway-Token: abc123;broken"),
+            ..Config::default()
+        };
+
+        assert_eq!(config.extra_headers.len(), 2);
+        let text = config.to_string();
+        assert!(text.contains("header:    X-Route: eu-west"), "{}", text);
+        assert!(
+            text.contains("header:    X-Gateway-Token: <redacted>"),
+            "{}",
+            text
+        );
+        assert!(!text.contains("abc123"), "{}", text);
+    }
+
+    #[test]
+    fn test_debug_redacts_like_display() {
+        let config = Config {
+            api_key: Some("sk-secret".to_string()),
+            extra_headers: parse_headers("X-Route: eu-west; X-Gateway-Token: abc123"),
+            ..Config::default()
+        };
+
+        let text = format!("{:?}", config);
+
+        assert!(text.contains("api_key: Some(\"<redacted>\")"), "{}", text);
+        assert!(text.contains("\"X-Route\": \"eu-west\""), "{}", text);
+        assert!(!text.contains("sk-secret"), "{}", text);
+        assert!(!text.contains("abc123"), "{}", text);
+        assert!(format!("{:?}", Config::default()).contains("api_key: None"));
+    }
+
+    #[test]
+    fn test_provider_chain_takes_unset_settings_from_the_config() {
+        let config = Config {
+            ollama_model: "llama3.1".to_string(),
+            api_key: Some("sk-shared".to_string()),
+            providers: vec![
+                Provider::Ollama.into(),
+                ProviderSettings {
+                    provider: Provider::Echo,
+                    base_url: Some("http://hosted.example".to_string()),
+                    model: Some("echo-model".to_string()),
+                    api_key: Some("sk-hosted".to_string()),
+                },
+            ],
+            ..Config::default()
+        };
+
+        let chain = config.provider_chain();
+        let text = format!("{}{:?}", config, config);
+
+        assert_eq!(chain[0].base_url, config.ollama_base_url);
+        assert_eq!(chain[0].model, "llama3.1");
+        assert_eq!(chain[0].api_key.as_deref(), Some("sk-shared"));
+        assert_eq!(chain[1].base_url, "http://hosted.example");
+        assert_eq!(chain[1].model, "echo-model");
+        assert_eq!(chain[1].api_key.as_deref(), Some("sk-hosted"));
+        assert!(
+            text.contains("fallback:  Echo at http://hosted.example, model echo-model"),
+            "{}",
+            text
+        );
+        assert!(!text.contains("sk-hosted"), "{}", text);
     }
 }
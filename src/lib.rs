@@ -1,6 +1,9 @@
+pub mod analyze;
 pub mod compiler;
 pub mod runtime;
 // mod tests;
 pub mod config;
+#[cfg(feature = "runtime")]
 pub mod runnable;
+pub mod testing;
 pub mod utils;
@@ -1,15 +1,22 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use clap::Parser;
 use std::path::PathBuf;
-use vibelang::runnable;
+use vibelang::analyze::diagnostic::Severity;
+use vibelang::compiler::codegen::RuntimeMode;
+use vibelang::compiler::project_builder::CrateType;
+use vibelang::config::Config;
+use vibelang::runnable::{self, Verbosity};
+use vibelang::runtime::health;
 
 /// A command-line tool to compile and execute a VibeLang .vibe file.
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    /// The path to the VibeLang source file to execute.
-    #[arg(required = true)]
-    input_file: PathBuf,
+    /// The paths to the VibeLang source files to execute, or `-` to read from standard input.
+    ///
+    /// With several files, each project is generated in a subdirectory of the output directory.
+    #[arg(required_unless_present_any = ["init", "doctor", "print_config"])]
+    input_files: Vec<PathBuf>,
 
     /// The directory where the generated Rust project will be placed.
     #[arg(short, long, default_value = ".generated")]
@@ -18,16 +25,240 @@ struct Cli {
     /// Generate as a library crate instead of a binary crate.
     #[arg(long, default_value_t = false)]
     as_lib: bool,
+
+    /// The `crate-type` of the generated library: rlib, cdylib, staticlib or dylib.
+    #[arg(long, value_name = "TYPE", default_value = "rlib", requires = "as_lib")]
+    crate_type: CrateType,
+
+    /// Ask the LLM to name the generated project after the meanings in the source, instead
+    /// of naming it after a hash of the source.
+    #[arg(long, default_value_t = false)]
+    suggest_names: bool,
+
+    /// Keep the generated project and report its path if the build fails.
+    #[arg(long, default_value_t = false)]
+    keep_on_error: bool,
+
+    /// Also generate `<name>_raw` functions returning the unparsed LLM responses.
+    #[arg(long, default_value_t = false)]
+    raw_responses: bool,
+
+    /// Also generate `<name>_with_meta` functions returning each call's latency and model.
+    #[arg(long, default_value_t = false)]
+    with_meta: bool,
+
+    /// Reject functions returning a non-String type without a meaning.
+    #[arg(long, default_value_t = false)]
+    strict_types: bool,
+
+    /// Print how each declared type maps to Rust instead of generating a project.
+    #[arg(long, default_value_t = false)]
+    explain: bool,
+
+    /// Print the meaning types as JSON, with their base type, meaning and extractor, instead
+    /// of generating a project.
+    #[arg(long, default_value_t = false)]
+    schema: bool,
+
+    /// Print the prompt this function would send, with the `--arg` values substituted,
+    /// without contacting the LLM.
+    #[arg(long, value_name = "FUNCTION")]
+    explain_prompt: Option<String>,
+
+    /// An argument for `--explain-prompt`, as `name=value`. Parameters without one take
+    /// example values.
+    #[arg(long = "arg", value_name = "NAME=VALUE", value_parser = parse_argument, requires = "explain_prompt")]
+    args: Vec<(String, String)>,
+
+    /// Report problems in the input files without generating anything; fails if any is an
+    /// error.
+    #[arg(long, default_value_t = false)]
+    lint: bool,
+
+    /// Write a starter `.vibe` file to the given path and exit.
+    #[arg(long, value_name = "PATH", conflicts_with = "input_files")]
+    init: Option<PathBuf>,
+
+    /// Check that the configured LLM backend is reachable and serves the model, then exit.
+    #[arg(long, default_value_t = false, conflicts_with = "input_files")]
+    doctor: bool,
+
+    /// Print the configuration in effect, with secrets redacted, then exit.
+    #[arg(long, default_value_t = false, conflicts_with = "input_files")]
+    print_config: bool,
+
+    /// Add generated projects to the Cargo workspace in this directory, creating it if needed.
+    ///
+    /// The output directory must be inside the workspace directory.
+    #[arg(long, value_name = "DIR")]
+    workspace: Option<PathBuf>,
+
+    /// Compile all input files into one project, with a module per file, instead of a
+    /// project per file.
+    #[arg(long, default_value_t = false)]
+    modules: bool,
+
+    /// How many times generated code asks again when an Int or Float answer contains no
+    /// number; 0 disables the retries. Defaults to 2.
+    #[arg(long, value_name = "N")]
+    number_retries: Option<usize>,
+
+    /// A shell command to run in each generated project before it is compiled, such as
+    /// `cargo fmt`.
+    #[arg(long, value_name = "COMMAND")]
+    post_generate: Option<String>,
+
+    /// Make the generated program append every prompt, response and latency to this JSONL
+    /// file.
+    #[arg(long, value_name = "PATH")]
+    trace_prompts: Option<PathBuf>,
+
+    /// Write each function's prompt, filled in with example arguments, to
+    /// `examples/<fn>.txt` in the output directory, for trying prompts by hand.
+    #[arg(long, default_value_t = false)]
+    example_prompts: bool,
+
+    /// Generate the project and print where it is, without building or running it.
+    #[arg(long, default_value_t = false)]
+    dry_run: bool,
+
+    /// Write only the generated library code to this `.rs` file of an existing crate, leaving
+    /// its `Cargo.toml` alone, and list the dependencies to add.
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["dry_run", "workspace"])]
+    generate_only: Option<PathBuf>,
+
+    /// Generate every function as `blocking` or as `async`, awaited from a tokio runtime,
+    /// instead of as declared.
+    #[arg(long, value_name = "MODE")]
+    runtime: Option<RuntimeMode>,
+
+    /// Only print errors and the output of the generated program, without progress lines.
+    #[arg(short, long, default_value_t = false)]
+    quiet: bool,
+
+    /// The maximum number of files compiled and run at the same time.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    max_parallel: u16,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
 
-    println!("--- VibeLang Project Runner ---");
+    if let Some(path) = &cli.init {
+        runnable::init_file(path)?;
+        println!("✅ Created starter file at {:?}", path);
+        return Ok(());
+    }
+
+    if cli.print_config {
+        print!("{}", Config::from_env());
+        return Ok(());
+    }
+
+    if cli.doctor {
+        let report = health::check_backend(&Config::from_env());
+        print!("{}", report);
+        if !report.is_healthy() {
+            return Err(anyhow!(
+                "The LLM backend at {} is not ready",
+                report.base_url
+            ));
+        }
+        println!("✅ The LLM backend is ready.");
+        return Ok(());
+    }
+
+    if cli.explain {
+        for input_file in &cli.input_files {
+            print!("{}", runnable::explain_file(input_file)?);
+        }
+        return Ok(());
+    }
+
+    if cli.schema {
+        for input_file in &cli.input_files {
+            println!("{}", runnable::schema_file(input_file)?);
+        }
+        return Ok(());
+    }
+
+    if let Some(function) = &cli.explain_prompt {
+        let args = cli.args.iter().cloned().collect();
+        for input_file in &cli.input_files {
+            println!(
+                "{}",
+                runnable::explain_prompt_file(input_file, function, &args)?
+            );
+        }
+        return Ok(());
+    }
+
+    if cli.lint {
+        let mut error_count = 0;
+        for input_file in &cli.input_files {
+            for issue in runnable::lint_file(input_file)? {
+                if issue.severity == Severity::Error {
+                    error_count += 1;
+                }
+                println!("{}: {}", input_file.display(), issue);
+            }
+        }
+        if error_count > 0 {
+            return Err(anyhow!("Lint found {} error(s)", error_count));
+        }
+        return Ok(());
+    }
+
+    let verbosity = if cli.quiet {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    };
+    if verbosity == Verbosity::Normal {
+        println!("--- VibeLang Project Runner ---");
+    }
 
     // Run parser and code generation.
-    runnable::run_file(&cli.input_file, &cli.output_dir, cli.as_lib)?;
+    let options = runnable::RunOptions {
+        as_lib: cli.as_lib,
+        keep_on_error: cli.keep_on_error,
+        raw_responses: cli.raw_responses,
+        suggest_names: cli.suggest_names,
+        meta_functions: cli.with_meta,
+        strict_types: cli.strict_types,
+        workspace: cli.workspace.clone(),
+        crate_type: cli.crate_type,
+        verbosity,
+        number_retries: cli.number_retries,
+        post_generate_hook: cli.post_generate.clone(),
+        trace_prompts: cli.trace_prompts.clone(),
+        example_prompts: cli.example_prompts,
+        dry_run: cli.dry_run,
+        runtime_mode: cli.runtime,
+        generate_only: cli.generate_only.clone(),
+    };
+    match cli.input_files.as_slice() {
+        input_files if cli.modules => {
+            runnable::run_modules(input_files, &cli.output_dir, &options)?
+        }
+        [input_file] => runnable::run_file_with_options(input_file, &cli.output_dir, &options)?,
+        input_files => runnable::run_batch(
+            input_files,
+            &cli.output_dir,
+            &options,
+            cli.max_parallel as usize,
+        )?,
+    }
 
-    println!("\n✅ Process finished successfully.");
+    if verbosity == Verbosity::Normal {
+        println!("\n✅ Process finished successfully.");
+    }
     Ok(())
 }
+
+/// Parses a `--arg` value written as `name=value`.
+fn parse_argument(text: &str) -> Result<(String, String), String> {
+    text.split_once('=')
+        .map(|(name, value)| (name.trim().to_string(), value.to_string()))
+        .ok_or_else(|| format!("expected `name=value`, found `{}`", text))
+}
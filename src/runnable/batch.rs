@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+use std::thread;
+
+/// Runs `job` on every item using at most `max_parallel` worker threads.
+///
+/// Results are returned in the order of `items`, whatever order the jobs finish in. A
+/// `max_parallel` of 0 is treated as 1, so the items run one after another.
+pub fn run_bounded<T, R, F>(items: &[T], max_parallel: usize, job: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = max_parallel.clamp(1, items.len().max(1));
+    let queue = Mutex::new(items.iter().enumerate());
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                loop {
+                    // Take the lock only to pick the next item, so jobs run concurrently.
+                    let next = queue.lock().unwrap().next();
+                    let Some((index, item)) = next else {
+                        break;
+                    };
+                    let result = job(item);
+                    results.lock().unwrap().push((index, result));
+                }
+            });
+        }
+    });
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::llm_provider::{LlmProvider, MockLlmProvider};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    /// Runs a batch of mocked LLM calls and returns the highest number running at once.
+    fn peak_concurrency(max_parallel: usize) -> usize {
+        let mut mock_client = MockLlmProvider::new();
+        mock_client.expect_generate().times(6).returning(|prompt| {
+            thread::sleep(Duration::from_millis(20));
+            Ok(prompt.to_uppercase())
+        });
+
+        let prompts = ["a", "b", "c", "d", "e", "f"];
+        let running = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+
+        let results = run_bounded(&prompts, max_parallel, |prompt| {
+            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(now, Ordering::SeqCst);
+            let response = mock_client.generate(prompt);
+            running.fetch_sub(1, Ordering::SeqCst);
+            response.unwrap()
+        });
+
+        assert_eq!(results, vec!["A", "B", "C", "D", "E", "F"]);
+        peak.load(Ordering::SeqCst)
+    }
+
+    #[test]
+    fn test_max_parallel_one_runs_sequentially() {
+        assert_eq!(peak_concurrency(1), 1);
+    }
+
+    #[test]
+    fn test_max_parallel_bounds_concurrency() {
+        assert!(peak_concurrency(3) <= 3);
+    }
+}
@@ -1,12 +1,101 @@
-use crate::compiler;
-use crate::compiler::project_builder::ProjectBuilder;
-use crate::config::Config;
+use crate::analyze::diagnostic::Diagnostic;
+use crate::analyze::lint;
+use crate::compiler::codegen::{CodeGenerator, FunctionSettings, RuntimeMode};
+use crate::compiler::parser::parse_source;
+use crate::compiler::project_builder::{CrateType, ProjectBuilder};
+use crate::config::{Config, TRACE_PROMPTS_ENV};
 use crate::runtime::client::LlmClient;
-use anyhow::Result;
+use crate::utils::ast::{AstNode, AstNodeType};
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::fs;
-use std::path::Path;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
+pub mod batch;
+
+/// Options controlling how `run_file_with_options` builds and runs a project.
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Generate a library crate instead of a binary crate.
+    pub as_lib: bool,
+    /// On a failed build, keep the generated project and report where it is.
+    pub keep_on_error: bool,
+    /// Generate `<name>_raw` companions returning the unparsed LLM responses.
+    pub raw_responses: bool,
+    /// Ask the LLM to name the generated project after the meanings in the source.
+    pub suggest_names: bool,
+    /// Generate `<name>_with_meta` companions returning the call's latency and model.
+    pub meta_functions: bool,
+    /// Reject non-String return types that have no meaning.
+    pub strict_types: bool,
+    /// Add the generated project to the Cargo workspace rooted at this directory.
+    pub workspace: Option<PathBuf>,
+    /// The `crate-type` of the generated library, when `as_lib` is set.
+    pub crate_type: CrateType,
+    /// How much progress output to print.
+    pub verbosity: Verbosity,
+    /// How often numeric answers without a number are asked again, if not the default.
+    pub number_retries: Option<usize>,
+    /// A shell command run in the generated project directory before it is compiled.
+    pub post_generate_hook: Option<String>,
+    /// Makes the generated program append each prompt and response to this file.
+    pub trace_prompts: Option<PathBuf>,
+    /// Write each function's prompt, filled in with example arguments, to `examples/<fn>.txt`.
+    pub example_prompts: bool,
+    /// Stop after generating the project, without `cargo run`, and print where it is.
+    pub dry_run: bool,
+    /// Generate every function as blocking or async, instead of as declared.
+    pub runtime_mode: Option<RuntimeMode>,
+    /// Write only the generated library code to this `.rs` file, for use in an existing
+    /// crate, instead of generating a project.
+    pub generate_only: Option<PathBuf>,
+}
+
+/// How much progress output the runner prints to stdout.
+///
+/// Errors are reported either way, and the generated program's own output is not affected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// No progress output.
+    Quiet,
+    /// The numbered pipeline steps and the location of the result.
+    #[default]
+    Normal,
+}
+
+impl RunOptions {
+    // The code generator configured by these options.
+    fn code_generator(&self) -> CodeGenerator {
+        let generator = CodeGenerator::new()
+            .with_raw_responses(self.raw_responses)
+            .with_meta_functions(self.meta_functions)
+            .with_strict_types(self.strict_types);
+        let generator = match self.runtime_mode {
+            Some(mode) => generator.with_runtime_mode(mode),
+            None => generator,
+        };
+        match self.number_retries {
+            Some(retries) => generator.with_number_retries(retries),
+            None => generator,
+        }
+    }
+
+    // Whether library code is generated: for a library crate, or a module of an existing one.
+    fn generates_library(&self) -> bool {
+        self.as_lib || self.generate_only.is_some()
+    }
+
+    // Prints a progress line unless the options ask for quiet output.
+    fn progress(&self, message: std::fmt::Arguments<'_>) {
+        if self.verbosity != Verbosity::Quiet {
+            println!("{}", message);
+        }
+    }
+}
+
 /// Compiles a VibeLang source file, scaffolds a project, and runs it.
 ///
 /// This function handles the end-to-end process:
@@ -15,47 +104,603 @@ use std::process::Command;
 /// 3. Executes `cargo run` within the new project's directory to compile and run the binary.
 ///
 /// # Arguments
-/// * `source_path` - Path to the input `.vibe` file.
+/// * `source_path` - Path to the input `.vibe` file, or `-` to read it from standard input.
 /// * `output_dir` - Path where the "generated" project directory will be created.
 pub fn run_file<P: AsRef<Path>>(source_path: P, output_dir: P, as_lib: bool) -> Result<()> {
+    let options = RunOptions {
+        as_lib,
+        ..Default::default()
+    };
+    run_file_with_options(source_path, output_dir, &options)
+}
+
+/// Same as `run_file`, with the behaviour controlled by `RunOptions`.
+pub fn run_file_with_options<P: AsRef<Path>>(
+    source_path: P,
+    output_dir: P,
+    options: &RunOptions,
+) -> Result<()> {
     let source_path = source_path.as_ref();
     let output_dir = output_dir.as_ref();
 
     // Step 1: Generate the Rust code from the source file.
-    println!(
+    options.progress(format_args!(
         "⚙️  [1/3] Compiling VibeLang source from: {:?}",
         source_path
-    );
-    let source_code = fs::read_to_string(source_path)?;
-    let generated_code = compiler::compile(&source_code, as_lib)?;
+    ));
+    let (source_code, source_name) = read_source(source_path)?;
+    let ast = parse_source(&source_code)?;
+    let settings = load_function_settings(source_path, &ast)?;
+    compile_and_run(
+        &ast,
+        &source_code,
+        &source_name,
+        settings,
+        output_dir,
+        options,
+    )
+}
+
+/// Compiles VibeLang source held in memory, scaffolds a project, and runs it, as
+/// `run_file_with_options` does for a file.
+///
+/// Meant for embedding VibeLang in other tools and tests. The generated header names the
+/// source `<source>`, and no sidecar settings are read.
+///
+/// # Arguments
+/// * `source` - The VibeLang source code.
+/// * `output_dir` - Path where the "generated" project directory will be created.
+/// * `options` - How the project is generated and run.
+pub fn run_source<P: AsRef<Path>>(source: &str, output_dir: P, options: &RunOptions) -> Result<()> {
+    options.progress(format_args!("⚙️  [1/3] Compiling VibeLang source"));
+    let ast = parse_source(source)?;
+    compile_and_run(
+        &ast,
+        source,
+        "<source>",
+        HashMap::new(),
+        output_dir.as_ref(),
+        options,
+    )
+}
+
+// Generates the Rust code for a parsed program, then writes and runs the project.
+fn compile_and_run(
+    ast: &AstNode,
+    source_code: &str,
+    source_name: &str,
+    settings: HashMap<String, FunctionSettings>,
+    output_dir: &Path,
+    options: &RunOptions,
+) -> Result<()> {
+    let generator = options
+        .code_generator()
+        .with_function_settings(settings)
+        .with_header(source_name, source_code);
+    let generated_code = generator.generate(ast, options.generates_library())?;
+    if options.example_prompts {
+        let prompts = options.code_generator().example_prompts(ast)?;
+        write_example_prompts(output_dir, &prompts)?;
+    }
+
+    let async_runtime = generator.has_async_functions(ast);
+    build_and_run(
+        output_dir,
+        source_code,
+        &generated_code,
+        async_runtime,
+        options,
+    )
+}
+
+// Writes each function's example prompt to `examples/<fn>.txt` in the project directory.
+fn write_example_prompts(output_dir: &Path, prompts: &[(String, String)]) -> Result<()> {
+    let examples_dir = output_dir.join("examples");
+    fs::create_dir_all(&examples_dir)?;
+    for (name, prompt) in prompts {
+        fs::write(
+            examples_dir.join(format!("{}.txt", name)),
+            format!("{}\n", prompt),
+        )?;
+    }
+    Ok(())
+}
+
+// Steps 2 and 3 of `run_file_with_options`: writes the project and runs its binary.
+// `async_runtime` is whether the generated code has async functions, run on tokio.
+fn build_and_run(
+    output_dir: &Path,
+    source_code: &str,
+    generated_code: &str,
+    async_runtime: bool,
+    options: &RunOptions,
+) -> Result<()> {
+    let as_lib = options.as_lib;
+    let config = Config::from_env();
+    let llm_client = LlmClient::new(config)?;
+
+    if let Some(module_path) = &options.generate_only {
+        let dependencies = ProjectBuilder::new(&llm_client)
+            .with_async_runtime(async_runtime)
+            .write_module(module_path, generated_code)?;
+        options.progress(format_args!(
+            "\n✅ Module has been written to {:?}. Its crate needs these dependencies:",
+            module_path
+        ));
+        // Printed even when quiet: the crate cannot build the module without them.
+        println!("{}", dependencies.join("\n"));
+        return Ok(());
+    }
 
     // Step 2: Build the project structure in the 'generated' directory.
-    println!(
+    options.progress(format_args!(
         "⚙️  [2/3] Generating project structure at: {:?}",
         output_dir
-    );
-    let config = Config::from_env();
-    let llm_client = LlmClient::new(config)?;
-    let project_builder = ProjectBuilder::new(&llm_client);
-    project_builder.build(output_dir, &source_code, &generated_code, as_lib)?;
+    ));
+    let mut project_builder = ProjectBuilder::new(&llm_client)
+        .with_crate_type(options.crate_type)
+        .with_suggested_names(options.suggest_names)
+        .with_async_runtime(async_runtime);
+    if let Some(workspace) = &options.workspace {
+        project_builder = project_builder.with_workspace(workspace);
+    }
+    if let Some(command) = &options.post_generate_hook {
+        project_builder = project_builder.with_post_generate_hook(command);
+    }
+    project_builder.build(output_dir, source_code, generated_code, as_lib)?;
 
-    if as_lib == true {
-        println!("\n✅ Library file has been created at {:?}", output_dir);
+    if as_lib {
+        options.progress(format_args!(
+            "\n✅ Library file has been created at {:?}",
+            output_dir
+        ));
+        return Ok(());
+    }
+    if options.dry_run {
+        // Printed even when quiet, since the path is what a dry run is for.
+        println!("{}", output_dir.display());
         return Ok(());
     }
 
     // Step 3: Compile and run the generated project's binary.
-    println!("⚙️  [3/3] Compiling and running the generated project...");
-    let status = Command::new("cargo")
-        .arg("run")
-        .current_dir(output_dir)
-        .status()?;
+    options.progress(format_args!(
+        "⚙️  [3/3] Compiling and running the generated project..."
+    ));
+    let mut cargo = Command::new("cargo");
+    cargo.arg("run").current_dir(output_dir);
+    if options.verbosity == Verbosity::Quiet {
+        cargo.arg("--quiet");
+    }
+    if let Some(trace_path) = &options.trace_prompts {
+        // The program runs in the project directory, so a relative path would move.
+        cargo.env(TRACE_PROMPTS_ENV, std::path::absolute(trace_path)?);
+    }
+    let status = cargo.status()?;
 
     if !status.success() {
-        anyhow::bail!(
+        return Err(build_failure_error(output_dir, options.keep_on_error));
+    }
+
+    Ok(())
+}
+
+/// Compiles and runs several VibeLang files, with at most `max_parallel` at a time.
+///
+/// Each file gets its own project in a subdirectory of `output_dir` named after the file.
+/// Every file is attempted; if any fail, the returned error lists them.
+///
+/// # Arguments
+/// * `source_paths` - Paths to the input `.vibe` files.
+/// * `output_dir` - Directory under which the per-file project directories are created.
+/// * `max_parallel` - The maximum number of files processed concurrently.
+pub fn run_batch<P: AsRef<Path> + Sync>(
+    source_paths: &[P],
+    output_dir: &Path,
+    options: &RunOptions,
+    max_parallel: usize,
+) -> Result<()> {
+    let results = batch::run_bounded(source_paths, max_parallel, |source_path| {
+        let source_path = source_path.as_ref();
+        let project_name = source_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| "vibe".to_string());
+        run_file_with_options(source_path, &output_dir.join(project_name), options)
+    });
+
+    let failures: Vec<String> = source_paths
+        .iter()
+        .zip(results)
+        .filter_map(|(path, result)| {
+            result
+                .err()
+                .map(|e| format!("{}: {}", path.as_ref().display(), e))
+        })
+        .collect();
+
+    if !failures.is_empty() {
+        return Err(anyhow!(
+            "{} of {} files failed:\n{}",
+            failures.len(),
+            source_paths.len(),
+            failures.join("\n")
+        ));
+    }
+    Ok(())
+}
+
+/// Compiles several VibeLang files into one project, with a module per file, and runs it.
+///
+/// Each file becomes a `pub mod` named after it, so `weather.vibe` is the `weather` module,
+/// and types declared in one file can be used in the others.
+///
+/// # Arguments
+/// * `source_paths` - Paths to the input `.vibe` files.
+/// * `output_dir` - Path where the project directory will be created.
+pub fn run_modules<P: AsRef<Path>>(
+    source_paths: &[P],
+    output_dir: &Path,
+    options: &RunOptions,
+) -> Result<()> {
+    options.progress(format_args!(
+        "⚙️  [1/3] Compiling {} VibeLang files",
+        source_paths.len()
+    ));
+    let mut sources = Vec::new();
+    let mut modules: Vec<(String, AstNode)> = Vec::new();
+    for source_path in source_paths {
+        let source_path = source_path.as_ref();
+        let name = module_name(source_path)?;
+        if modules.iter().any(|(existing, _)| existing == &name) {
+            return Err(anyhow!(
+                "Two input files map to the module `{}`; rename one of them",
+                name
+            ));
+        }
+        let (source_code, source_name) = read_source(source_path)?;
+        let ast =
+            parse_source(&source_code).map_err(|e| anyhow!("{}: {}", source_path.display(), e))?;
+        sources.push((source_name, source_code));
+        modules.push((name, ast));
+    }
+
+    let source_names: Vec<&str> = sources.iter().map(|(name, _)| name.as_str()).collect();
+    // One file per line at least, so that a comment ending one file cannot swallow the
+    // first line of the next when the project builder parses them together.
+    let source_code = sources
+        .iter()
+        .map(|(_, code)| code.as_str())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let generator = options
+        .code_generator()
+        .with_header(&source_names.join(", "), &source_code);
+    let generated_code = generator.generate_modules(&modules, options.generates_library())?;
+
+    let async_runtime = modules
+        .iter()
+        .any(|(_, ast)| generator.has_async_functions(ast));
+    build_and_run(
+        output_dir,
+        &source_code,
+        &generated_code,
+        async_runtime,
+        options,
+    )
+}
+
+/// The module name for a source file: its stem, with characters invalid in identifiers
+/// replaced by `_`.
+fn module_name(source_path: &Path) -> Result<String> {
+    let stem = source_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let name: String = stem
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    // Rejects names that are still not identifiers, such as keywords or a leading digit.
+    if syn::parse_str::<syn::Ident>(&name).is_err() {
+        return Err(anyhow!(
+            "Cannot use {} as a module: `{}` is not a valid Rust module name",
+            source_path.display(),
+            name
+        ));
+    }
+    Ok(name)
+}
+
+/// Explains how the types declared in a VibeLang file map to Rust, without generating a project.
+///
+/// # Arguments
+/// * `source_path` - Path to the input `.vibe` file.
+pub fn explain_file<P: AsRef<Path>>(source_path: P) -> Result<String> {
+    let (source_code, _) = read_source(source_path.as_ref())?;
+    let ast = parse_source(&source_code)?;
+    Ok(CodeGenerator::new().explain(&ast))
+}
+
+/// Describes the meaning types declared in a VibeLang file as pretty-printed JSON, without
+/// generating a project.
+///
+/// # Arguments
+/// * `source_path` - Path to the input `.vibe` file.
+pub fn schema_file<P: AsRef<Path>>(source_path: P) -> Result<String> {
+    let (source_code, _) = read_source(source_path.as_ref())?;
+    let ast = parse_source(&source_code)?;
+    Ok(serde_json::to_string_pretty(
+        &CodeGenerator::new().schema(&ast),
+    )?)
+}
+
+/// Renders the prompt a function of a VibeLang file sends for the given arguments, without
+/// contacting the LLM.
+///
+/// The prompt includes the meaning context and the prefix and suffix configured in the
+/// environment, as the generated program would send it.
+///
+/// # Arguments
+/// * `source_path` - Path to the input `.vibe` file.
+/// * `function` - The name of the function.
+/// * `args` - Argument values by parameter name; the others take example values.
+pub fn explain_prompt_file<P: AsRef<Path>>(
+    source_path: P,
+    function: &str,
+    args: &HashMap<String, String>,
+) -> Result<String> {
+    let (source_code, _) = read_source(source_path.as_ref())?;
+    let ast = parse_source(&source_code)?;
+    let prompt = CodeGenerator::new().render_prompt(&ast, function, args)?;
+    let config = Config::from_env();
+    Ok(format!(
+        "{}{}{}",
+        config.prompt_prefix, prompt, config.prompt_suffix
+    ))
+}
+
+/// Lints a VibeLang file, without generating a project.
+///
+/// # Arguments
+/// * `source_path` - Path to the input `.vibe` file.
+pub fn lint_file<P: AsRef<Path>>(source_path: P) -> Result<Vec<Diagnostic>> {
+    let (source_code, _) = read_source(source_path.as_ref())?;
+    Ok(lint::lint(&source_code))
+}
+
+/// The input path that reads the VibeLang source from standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Reads a VibeLang source file, or standard input when `source_path` is `STDIN_PATH`.
+///
+/// Returns the source and the name recorded in the generated header.
+fn read_source(source_path: &Path) -> Result<(String, String)> {
+    if source_path == Path::new(STDIN_PATH) {
+        let mut source_code = String::new();
+        io::stdin().read_to_string(&mut source_code)?;
+        return Ok((source_code, "<stdin>".to_string()));
+    }
+
+    let source_code = fs::read_to_string(source_path)?;
+    let source_name = source_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok((source_code, source_name))
+}
+
+/// Reads the per-function client settings from the sidecar of a source file, e.g.
+/// `weather.vibe.json` for `weather.vibe`, if there is one.
+///
+/// The sidecar is a JSON object mapping function names to `FunctionSettings`, such as
+/// `{"get_forecast": {"model": "mistral", "temperature": 0.2, "stop": ["\n"]}}`.
+fn load_function_settings(
+    source_path: &Path,
+    ast: &AstNode,
+) -> Result<HashMap<String, FunctionSettings>> {
+    let mut sidecar_path = OsString::from(source_path);
+    sidecar_path.push(".json");
+    let sidecar_path = PathBuf::from(sidecar_path);
+    if source_path == Path::new(STDIN_PATH) || !sidecar_path.exists() {
+        return Ok(HashMap::new());
+    }
+
+    let text = fs::read_to_string(&sidecar_path)?;
+    let settings: HashMap<String, FunctionSettings> = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid settings file {}", sidecar_path.display()))?;
+    for name in settings.keys() {
+        let declared = ast.children.iter().any(|node| {
+            node.node_type == AstNodeType::FunctionDecl && node.get_string("name") == Some(name)
+        });
+        if !declared {
+            return Err(anyhow!(
+                "{} has settings for `{}`, which is not a function of {}",
+                sidecar_path.display(),
+                name,
+                source_path.display()
+            ));
+        }
+    }
+    Ok(settings)
+}
+
+/// The well-commented program written by `init_file`.
+const STARTER_SOURCE: &str = include_str!("../../templates/starter.vibe");
+
+/// Writes a starter `.vibe` file to `path`, to be edited into a new program.
+///
+/// Fails instead of overwriting an existing file.
+///
+/// # Arguments
+/// * `path` - Path where the starter file will be created.
+pub fn init_file<P: AsRef<Path>>(path: P) -> Result<()> {
+    let path = path.as_ref();
+    if path.exists() {
+        return Err(anyhow!(
+            "Refusing to overwrite existing file {}",
+            path.display()
+        ));
+    }
+    fs::write(path, STARTER_SOURCE)?;
+    Ok(())
+}
+
+/// Builds the error reported when `cargo run` fails on the generated project.
+///
+/// With `keep_on_error`, the message points at the preserved project so it can be inspected.
+fn build_failure_error(output_dir: &Path, keep_on_error: bool) -> anyhow::Error {
+    if !keep_on_error {
+        return anyhow!(
             "Failed to compile or run the generated project. Review the output above for errors."
         );
     }
 
-    Ok(())
+    let project_path = output_dir
+        .canonicalize()
+        .unwrap_or_else(|_| output_dir.to_path_buf());
+    let entry_point = project_path.join("src").join("main.rs");
+    anyhow!(
+        "Failed to compile or run the generated project. It was kept at {} for debugging; inspect {} for the generated code.",
+        project_path.display(),
+        entry_point.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_init_file_writes_a_parsable_starter() {
+        let temp_dir = tempdir().unwrap();
+        let path = temp_dir.path().join("hello.vibe");
+
+        init_file(&path).unwrap();
+
+        let source = fs::read_to_string(&path).unwrap();
+        let ast = parse_source(&source).unwrap();
+        assert!(!ast.children.is_empty());
+        assert!(
+            init_file(&path).is_err(),
+            "An existing file must not be overwritten"
+        );
+    }
+
+    #[test]
+    fn test_build_failure_error_reports_output_dir_when_kept() {
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().canonicalize().unwrap();
+
+        let message = build_failure_error(&output_dir, true).to_string();
+
+        assert!(message.contains(&output_dir.display().to_string()));
+        assert!(message.contains("main.rs"));
+    }
+
+    #[test]
+    fn test_build_failure_error_is_generic_by_default() {
+        let temp_dir = tempdir().unwrap();
+
+        let message = build_failure_error(temp_dir.path(), false).to_string();
+
+        assert!(!message.contains(&temp_dir.path().display().to_string()));
+    }
+
+    #[test]
+    fn test_sidecar_settings_apply_to_their_function_only() {
+        let temp_dir = tempdir().unwrap();
+        let source_path = temp_dir.path().join("trivia.vibe");
+        let source = r#"
+            fn capital(country: String) -> String { prompt "What is the capital of {country}?"; }
+            fn river(country: String) -> String { prompt "Name the longest river in {country}."; }
+        "#;
+        fs::write(&source_path, source).unwrap();
+        fs::write(
+            temp_dir.path().join("trivia.vibe.json"),
+            r#"{"capital": {"model": "mistral", "temperature": 0.1, "stop": ["\n"]}}"#,
+        )
+        .unwrap();
+        let ast = parse_source(source).unwrap();
+
+        let settings = load_function_settings(&source_path, &ast).unwrap();
+        // The temperature is written exactly, whatever the float format of test values.
+        let code = CodeGenerator::new()
+            .with_function_settings(settings)
+            .with_float_format(crate::runtime::types::FloatFormat::Fixed(0))
+            .generate(&ast, true)
+            .unwrap();
+
+        let (capital, river) = code.split_once("pub fn river").unwrap();
+        assert!(
+            capital
+                .contains("let llm = &llm.clone().with_model(\"mistral\").with_stop(&[\"\\n\"]);"),
+            "{}",
+            capital
+        );
+        assert!(capital.contains("let temperature = Some(0.1);"));
+        assert!(!river.contains("with_model"));
+        assert!(river.contains("let temperature = None;"));
+
+        fs::write(
+            temp_dir.path().join("trivia.vibe.json"),
+            r#"{"river": {"temperature": 5}}"#,
+        )
+        .unwrap();
+        let settings = load_function_settings(&source_path, &ast).unwrap();
+        let error = CodeGenerator::new()
+            .with_function_settings(settings)
+            .generate(&ast, true)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Line 3: Invalid temperature 5 in the settings of `river`: expected a value between 0 and 2"
+        );
+
+        fs::write(
+            temp_dir.path().join("trivia.vibe.json"),
+            r#"{"lake": {"model": "mistral"}}"#,
+        )
+        .unwrap();
+        let error = load_function_settings(&source_path, &ast).unwrap_err();
+        assert!(
+            error.to_string().contains("settings for `lake`"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_run_source_generates_a_project_without_a_source_file() {
+        // Without meanings the project is named without asking the LLM, so this runs offline.
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().join("generated");
+        let options = RunOptions {
+            as_lib: true,
+            verbosity: Verbosity::Quiet,
+            ..Default::default()
+        };
+
+        run_source(
+            r#"fn get_year() -> Int { prompt "What year is it?"; }"#,
+            &output_dir,
+            &options,
+        )
+        .unwrap();
+
+        let code = fs::read_to_string(output_dir.join("src/lib.rs")).unwrap();
+        assert!(code.contains("pub fn get_year(llm: &LlmClient) -> i32"));
+        assert!(syn::parse_file(&code).is_ok());
+        assert!(output_dir.join("Cargo.toml").exists());
+    }
+
+    #[test]
+    fn test_module_name_from_file_stem() {
+        assert_eq!(
+            module_name(Path::new("examples/Weather-Report.vibe")).unwrap(),
+            "weather_report"
+        );
+        assert!(module_name(Path::new("type.vibe")).is_err());
+        assert!(module_name(Path::new("2024.vibe")).is_err());
+    }
 }
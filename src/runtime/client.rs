@@ -1,39 +1,280 @@
-use crate::config::Config;
+use crate::config::{Backend, Config, Provider};
+use crate::runtime::echo::EchoProvider;
 use crate::runtime::llm_provider::LlmProvider; // Import the new trait
+use crate::runtime::response_adapter::adapter_for;
+use crate::runtime::transform::{PromptTransformer, ResponseTransformer};
 use anyhow::{Result, anyhow};
+use once_cell::sync::OnceCell;
 use reqwest::blocking::Client;
-use serde_json::json;
+use serde_json::{Value, json};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use thiserror::Error;
 
+/// The sampling temperature used when a call does not set one.
+pub const DEFAULT_TEMPERATURE: f64 = 0.5;
+
+/// Errors raised by `LlmClient` that callers may want to tell apart from transport failures.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LlmClientError {
+    /// The request did not complete within the allowed time and was cancelled.
+    #[error("LLM request timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+#[derive(Clone)]
 pub struct LlmClient {
-    client: Client,
+    // Created on first blocking call, so async-only programs never build it inside a runtime.
+    client: OnceCell<Client>,
+    async_client: reqwest::Client,
     config: Config,
+    prompt_transformers: Vec<Arc<dyn PromptTransformer>>,
+    response_transformers: Vec<Arc<dyn ResponseTransformer>>,
+    // Set by `with_llm_provider`: answers in place of the configured providers.
+    llm_provider: Option<Arc<dyn LlmProvider + Send + Sync>>,
+    // The backend that answered the latest call, shared with clones; see `answered_by`.
+    answered_by: Arc<Mutex<Option<Backend>>>,
 }
 
 impl LlmClient {
     pub fn new(config: Config) -> Result<Self> {
         Ok(Self {
-            client: Client::new(),
+            client: OnceCell::new(),
+            async_client: reqwest::Client::new(),
             config,
+            prompt_transformers: Vec::new(),
+            response_transformers: Vec::new(),
+            llm_provider: None,
+            answered_by: Arc::default(),
         })
     }
-}
 
-// Implement the LlmProvider trait for the real LlmClient
-impl LlmProvider for LlmClient {
-    fn generate(&self, prompt: &str) -> Result<String> {
-        let request_body = json!({
-            "model": &self.config.ollama_model,
-            "prompt": prompt,
-            "stream": false,
-            "options": {
-                "temperature": 0.5
+    /// The configuration the client was created with.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The provider of the chain that answered the latest call made with this client or a
+    /// clone sharing its record, with the model it was asked. `None` before any call, after
+    /// a failed one, and when a provider set with `with_llm_provider` answered.
+    pub fn answered_by(&self) -> Option<Backend> {
+        self.answered_by.lock().unwrap().clone()
+    }
+
+    /// Gives the client a record of `answered_by` of its own, no longer shared with the
+    /// client it was cloned from. Generated `_with_meta` companions call this, so that
+    /// concurrent calls do not see each other's answers.
+    pub fn with_own_answer_record(mut self) -> Self {
+        self.answered_by = Arc::default();
+        self
+    }
+
+    /// Registers a transformer that rewrites every prompt before it is sent.
+    ///
+    /// Transformers run in registration order, each receiving the previous one's output.
+    pub fn with_prompt_transformer(
+        mut self,
+        transformer: impl PromptTransformer + 'static,
+    ) -> Self {
+        self.prompt_transformers.push(Arc::new(transformer));
+        self
+    }
+
+    /// Registers a transformer that rewrites every response before it is returned.
+    ///
+    /// Transformers run in registration order, each receiving the previous one's output.
+    pub fn with_response_transformer(
+        mut self,
+        transformer: impl ResponseTransformer + 'static,
+    ) -> Self {
+        self.response_transformers.push(Arc::new(transformer));
+        self
+    }
+
+    /// Sends every request to `provider` instead of the configured providers, e.g. to a
+    /// `MockLlmProvider` to run generated functions offline in tests.
+    ///
+    /// The prompt it receives is the one a backend would: transformed, with the configured
+    /// prefix and suffix. Its responses go through the response transformers.
+    pub fn with_llm_provider(mut self, provider: impl LlmProvider + Send + Sync + 'static) -> Self {
+        self.llm_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Asks `model` instead of the configured one. Generated functions use this on a clone
+    /// of the client for settings given in a sidecar file. Providers of the chain with a
+    /// model of their own keep it.
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.config.ollama_model = model.to_string();
+        self
+    }
+
+    /// Makes the model stop generating at any of the `stop` sequences.
+    pub fn with_stop(mut self, stop: &[&str]) -> Self {
+        self.config.stop = stop.iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    /// Abandons each request to the backend after `secs` seconds. Generated functions use
+    /// this for a `@timeout(secs)` annotation.
+    pub fn with_timeout_secs(mut self, secs: u64) -> Self {
+        self.config.timeout_secs = Some(secs);
+        self
+    }
+
+    /// Sends a failed request again, up to `retries` times. Generated functions use this
+    /// for a `@retries(n)` annotation.
+    pub fn with_retries(mut self, retries: usize) -> Self {
+        self.config.retries = retries;
+        self
+    }
+
+    /// Sends a prompt to the LLM without blocking the current thread.
+    ///
+    /// This is the client call used by functions declared `async fn` in VibeLang source.
+    pub async fn generate_async(&self, prompt: &str) -> Result<String> {
+        self.generate_async_with_temperature(prompt, None).await
+    }
+
+    /// Like `generate_async`, sampling at `temperature` instead of `DEFAULT_TEMPERATURE`
+    /// when it is set.
+    pub async fn generate_async_with_temperature(
+        &self,
+        prompt: &str,
+        temperature: Option<f64>,
+    ) -> Result<String> {
+        let started = Instant::now();
+        let response = self.request_async(prompt, temperature).await;
+        self.record(prompt, response, started)
+    }
+
+    // Sends the request, and again up to `Config::retries` times while it fails.
+    async fn request_async(&self, prompt: &str, temperature: Option<f64>) -> Result<Answer> {
+        let mut result = self.request_chain_async(prompt, temperature).await;
+        for _ in 0..self.config.retries {
+            if result.is_ok() {
+                break;
             }
-        });
+            result = self.request_chain_async(prompt, temperature).await;
+        }
+        result
+    }
+
+    // Asks each provider of the chain in turn until one answers.
+    async fn request_chain_async(&self, prompt: &str, temperature: Option<f64>) -> Result<Answer> {
+        if let Some(llm_provider) = &self.llm_provider {
+            return Ok((self.ask_llm_provider(llm_provider.as_ref(), prompt)?, None));
+        }
+        let mut failures = Vec::new();
+        for backend in self.config.provider_chain() {
+            match self.send_async(&backend, prompt, temperature).await {
+                Ok(text) => return Ok((text, Some(backend))),
+                Err(e) => failures.push((backend, e)),
+            }
+        }
+        Err(chain_error(failures))
+    }
+
+    async fn send_async(
+        &self,
+        backend: &Backend,
+        prompt: &str,
+        temperature: Option<f64>,
+    ) -> Result<String> {
+        if backend.provider == Provider::Echo {
+            return self.extract_content(backend.provider, &self.echo_response(backend, prompt));
+        }
+
+        let mut request = self
+            .async_client
+            .post(generate_url(backend))
+            .headers(self.config.request_headers_for(backend)?)
+            .json(&self.request_body(backend, prompt, temperature));
+        if let Some(secs) = self.config.timeout_secs {
+            request = request.timeout(Duration::from_secs(secs));
+        }
+        let response = request.send().await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow!(
+                "LLM API request failed with status {}: {}",
+                response.status(),
+                response.text().await?
+            ));
+        }
+
+        let response_json: Value = response.json().await?;
+        self.extract_content(backend.provider, &response_json)
+    }
+
+    /// Like `generate_async`, but gives up after `timeout`.
+    ///
+    /// On timeout the in-flight request is dropped, which aborts it, and the returned error
+    /// downcasts to `LlmClientError::Timeout`.
+    pub async fn generate_async_with_timeout(
+        &self,
+        prompt: &str,
+        timeout: Duration,
+    ) -> Result<String> {
+        match tokio::time::timeout(timeout, self.generate_async(prompt)).await {
+            Ok(result) => result,
+            Err(_) => Err(LlmClientError::Timeout(timeout).into()),
+        }
+    }
+
+    /// Like `generate`, sampling at `temperature` instead of `DEFAULT_TEMPERATURE` when it
+    /// is set.
+    pub fn generate_with_temperature(
+        &self,
+        prompt: &str,
+        temperature: Option<f64>,
+    ) -> Result<String> {
+        let started = Instant::now();
+        let response = self.request_blocking(prompt, temperature);
+        self.record(prompt, response, started)
+    }
+
+    // Sends the request, and again up to `Config::retries` times while it fails.
+    fn request_blocking(&self, prompt: &str, temperature: Option<f64>) -> Result<Answer> {
+        let mut result = self.request_chain_blocking(prompt, temperature);
+        for _ in 0..self.config.retries {
+            if result.is_ok() {
+                break;
+            }
+            result = self.request_chain_blocking(prompt, temperature);
+        }
+        result
+    }
+
+    // Asks each provider of the chain in turn until one answers.
+    fn request_chain_blocking(&self, prompt: &str, temperature: Option<f64>) -> Result<Answer> {
+        if let Some(llm_provider) = &self.llm_provider {
+            return Ok((self.ask_llm_provider(llm_provider.as_ref(), prompt)?, None));
+        }
+        let mut failures = Vec::new();
+        for backend in self.config.provider_chain() {
+            match self.send_blocking(&backend, prompt, temperature) {
+                Ok(text) => return Ok((text, Some(backend))),
+                Err(e) => failures.push((backend, e)),
+            }
+        }
+        Err(chain_error(failures))
+    }
+
+    fn send_blocking(
+        &self,
+        backend: &Backend,
+        prompt: &str,
+        temperature: Option<f64>,
+    ) -> Result<String> {
+        if backend.provider == Provider::Echo {
+            return self.extract_content(backend.provider, &self.echo_response(backend, prompt));
+        }
 
         let response = self
-            .client
-            .post(format!("{}/api/generate", &self.config.ollama_base_url))
-            .json(&request_body)
+            .blocking_request(backend, prompt, temperature)?
             .send()?;
 
         if !response.status().is_success() {
@@ -44,22 +285,571 @@ impl LlmProvider for LlmClient {
             ));
         }
 
-        let response_json: serde_json::Value = response.json()?;
-        let content = response_json["response"].as_str().ok_or_else(|| {
-            anyhow!(
-                "Invalid response format from LLM API: `response` field missing or not a string"
-            )
-        })?;
+        let response_json: Value = response.json()?;
+        self.extract_content(backend.provider, &response_json)
+    }
+
+    // Notes which backend answered, for `answered_by` and the trace, and returns the text.
+    fn record(&self, prompt: &str, answer: Result<Answer>, started: Instant) -> Result<String> {
+        let (response, backend) = match answer {
+            Ok((text, backend)) => (Ok(text), backend),
+            Err(e) => (Err(e), None),
+        };
+        self.trace(prompt, &response, backend.as_ref(), started);
+        *self.answered_by.lock().unwrap() = backend;
+        response
+    }
+
+    /// Appends the call to the trace file, if `Config::trace_path` is set, as a JSON line
+    /// with the prompt, the provider that answered and its model, the response and latency.
+    /// A failed call records its error, with the first provider of the chain.
+    fn trace(
+        &self,
+        prompt: &str,
+        response: &Result<String>,
+        backend: Option<&Backend>,
+        started: Instant,
+    ) {
+        let Some(path) = &self.config.trace_path else {
+            return;
+        };
+        let backend = backend
+            .cloned()
+            .unwrap_or_else(|| self.config.provider_chain().remove(0));
+        let mut entry = json!({
+            "prompt": prompt,
+            "provider": format!("{:?}", backend.provider).to_lowercase(),
+            "model": backend.model,
+            "latency_ms": started.elapsed().as_millis() as u64,
+        });
+        match response {
+            Ok(text) => entry["response"] = json!(text),
+            Err(e) => {
+                entry["response"] = Value::Null;
+                entry["error"] = json!(e.to_string());
+            }
+        }
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(format!("{}\n", entry).as_bytes()));
+        // Tracing is for auditing; a trace that cannot be written must not fail the call.
+        if let Err(e) = written {
+            eprintln!("Could not write prompt trace to {}: {}", path.display(), e);
+        }
+    }
+
+    fn blocking_client(&self) -> &Client {
+        self.client.get_or_init(Client::new)
+    }
+
+    fn blocking_request(
+        &self,
+        backend: &Backend,
+        prompt: &str,
+        temperature: Option<f64>,
+    ) -> Result<reqwest::blocking::RequestBuilder> {
+        let request = self
+            .blocking_client()
+            .post(generate_url(backend))
+            .headers(self.config.request_headers_for(backend)?)
+            .json(&self.request_body(backend, prompt, temperature));
+        Ok(match self.config.timeout_secs {
+            Some(secs) => request.timeout(Duration::from_secs(secs)),
+            None => request,
+        })
+    }
+
+    // The prompt as sent to the backend: transformed, then wrapped in the prefix and suffix.
+    fn outgoing_prompt(&self, prompt: &str) -> String {
+        let prompt = self
+            .prompt_transformers
+            .iter()
+            .fold(prompt.to_string(), |prompt, transformer| {
+                transformer.transform(&prompt)
+            });
+        format!(
+            "{}{}{}",
+            self.config.prompt_prefix, prompt, self.config.prompt_suffix
+        )
+    }
+
+    fn request_body(&self, backend: &Backend, prompt: &str, temperature: Option<f64>) -> Value {
+        let temperature = temperature.unwrap_or(DEFAULT_TEMPERATURE);
+        let prompt = self.outgoing_prompt(prompt);
+
+        let mut body = match backend.provider {
+            Provider::Ollama | Provider::Echo => json!({
+                "model": &backend.model,
+                "prompt": prompt,
+                "stream": false,
+                "options": {
+                    "temperature": temperature
+                }
+            }),
+            #[cfg(feature = "openai")]
+            Provider::OpenAi => json!({
+                "model": &backend.model,
+                "messages": [
+                    {
+                        "role": "user",
+                        "content": prompt
+                    }
+                ],
+                "temperature": temperature
+            }),
+        };
+        if !self.config.stop.is_empty() {
+            match backend.provider {
+                Provider::Ollama | Provider::Echo => {
+                    body["options"]["stop"] = json!(self.config.stop)
+                }
+                #[cfg(feature = "openai")]
+                Provider::OpenAi => body["stop"] = json!(self.config.stop),
+            }
+        }
+        body
+    }
+
+    /// Answers locally with `EchoProvider`, as an Ollama-shaped response built from the
+    /// request body, so prompt and response transformers apply as for a real backend.
+    fn echo_response(&self, backend: &Backend, prompt: &str) -> Value {
+        let body = self.request_body(backend, prompt, None);
+        let prompt = body["prompt"].as_str().unwrap_or_default();
+        json!({ "response": EchoProvider.answer(prompt) })
+    }
+
+    fn extract_content(&self, provider: Provider, response_json: &Value) -> Result<String> {
+        let content = adapter_for(provider).extract_text(response_json)?;
+        Ok(self.transform_response(content))
+    }
 
-        Ok(content.to_string())
+    fn transform_response(&self, content: String) -> String {
+        self.response_transformers
+            .iter()
+            .fold(content, |content, transformer| {
+                transformer.transform(&content)
+            })
+    }
+
+    fn ask_llm_provider(&self, llm_provider: &dyn LlmProvider, prompt: &str) -> Result<String> {
+        let response = llm_provider.generate(&self.outgoing_prompt(prompt))?;
+        Ok(self.transform_response(response))
+    }
+}
+
+// A response, with the backend of the chain that gave it; `None` for a `with_llm_provider`.
+type Answer = (String, Option<Backend>);
+
+fn generate_url(backend: &Backend) -> String {
+    match backend.provider {
+        Provider::Ollama | Provider::Echo => format!("{}/api/generate", backend.base_url),
+        #[cfg(feature = "openai")]
+        Provider::OpenAi => format!("{}/v1/chat/completions", backend.base_url),
+    }
+}
+
+/// The error for a provider chain in which every provider failed. A single provider's error
+/// is returned as it is, so that callers can still downcast it.
+fn chain_error(mut failures: Vec<(Backend, anyhow::Error)>) -> anyhow::Error {
+    if failures.len() == 1 {
+        return failures.remove(0).1;
+    }
+    let reasons: Vec<String> = failures
+        .iter()
+        .map(|(backend, e)| format!("{:?}: {}", backend.provider, e))
+        .collect();
+    anyhow!(
+        "All {} providers failed: {}",
+        failures.len(),
+        reasons.join("; ")
+    )
+}
+
+// Implement the LlmProvider trait for the real LlmClient
+impl LlmProvider for LlmClient {
+    fn generate(&self, prompt: &str) -> Result<String> {
+        self.generate_with_temperature(prompt, None)
     }
 }
 
 impl Default for LlmClient {
     fn default() -> Self {
         Self {
-            client: Client::new(),
+            client: OnceCell::new(),
+            async_client: reqwest::Client::new(),
             config: Config::default(),
+            prompt_transformers: Vec::new(),
+            response_transformers: Vec::new(),
+            llm_provider: None,
+            answered_by: Arc::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ProviderSettings;
+    use crate::runtime::llm_provider::MockLlmProvider;
+    use crate::runtime::types::VibeValue;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    // The backend the client would use for `provider`, with its top-level settings.
+    fn backend(client: &LlmClient, provider: Provider) -> Backend {
+        client.config().backend(&provider.into())
+    }
+
+    #[test]
+    fn test_prompt_transformer_rewrites_request_body() {
+        let client = LlmClient::default()
+            .with_prompt_transformer(|prompt: &str| prompt.to_uppercase())
+            .with_prompt_transformer(|prompt: &str| format!("{}!", prompt));
+
+        let body = client.request_body(
+            &backend(&client, Provider::Ollama),
+            "what year is it?",
+            None,
+        );
+
+        assert_eq!(body["prompt"], "WHAT YEAR IS IT?!");
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_prompt_prefix_and_suffix_wrap_the_prompt() {
+        let config = Config {
+            prompt_prefix: "### Instruction:\n".to_string(),
+            prompt_suffix: "\nAnswer tersely.".to_string(),
+            ..Config::default()
+        };
+        let client = LlmClient::new(config).unwrap();
+
+        let body = client.request_body(
+            &backend(&client, Provider::OpenAi),
+            "What year is it?",
+            None,
+        );
+        let default_client = LlmClient::default();
+        let default_body = default_client.request_body(
+            &backend(&default_client, Provider::Ollama),
+            "What year is it?",
+            None,
+        );
+
+        assert_eq!(
+            body["messages"][0]["content"],
+            "### Instruction:\nWhat year is it?\nAnswer tersely."
+        );
+        assert_eq!(default_body["prompt"], "What year is it?");
+    }
+
+    #[test]
+    fn test_llm_provider_answers_in_place_of_the_backend() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt == "Q: What year is it?")
+            .times(1)
+            .returning(|_| Ok("2024".to_string()));
+        let client = LlmClient::new(Config {
+            prompt_prefix: "Q: ".to_string(),
+            ..Config::default()
+        })
+        .unwrap()
+        .with_response_transformer(|response: &str| format!("{} AD", response))
+        .with_llm_provider(mock);
+
+        let response = client.generate("What year is it?").unwrap();
+
+        assert_eq!(response, "2024 AD");
+    }
+
+    #[test]
+    fn test_configured_headers_are_sent_with_the_request() {
+        let config = Config {
+            api_key: Some("sk-test".to_string()),
+            extra_headers: [("X-Route".to_string(), "eu-west".to_string())].into(),
+            ..Config::default()
+        };
+        let client = LlmClient::new(config).unwrap();
+
+        let request = client
+            .blocking_request(&backend(&client, Provider::Ollama), "Hello", None)
+            .unwrap()
+            .build()
+            .unwrap();
+
+        assert_eq!(request.headers()["X-Route"], "eu-west");
+        assert_eq!(request.headers()["Authorization"], "Bearer sk-test");
+        assert_eq!(request.headers()["Content-Type"], "application/json");
+    }
+
+    #[test]
+    fn test_trace_path_records_a_json_line_per_call() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("trace.jsonl");
+        let client = LlmClient::new(Config {
+            provider: Provider::Echo,
+            trace_path: Some(trace_path.clone()),
+            ..Config::default()
+        })
+        .unwrap();
+
+        let first = client.generate("What year is it?").unwrap();
+        client.generate("Name a color.").unwrap();
+
+        let trace = std::fs::read_to_string(&trace_path).unwrap();
+        let entries: Vec<Value> = trace
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["prompt"], "What year is it?");
+        assert_eq!(entries[0]["provider"], "echo");
+        assert_eq!(entries[0]["model"], "llama3.1");
+        assert_eq!(entries[0]["response"], first.as_str());
+        assert!(entries[0]["latency_ms"].is_u64());
+        assert_eq!(entries[1]["prompt"], "Name a color.");
+    }
+
+    // A base URL on which nothing is listening.
+    fn unreachable_url() -> String {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_provider_chain_falls_back_to_the_next_provider() {
+        let client = LlmClient::new(Config {
+            ollama_base_url: unreachable_url(),
+            providers: vec![Provider::Ollama.into(), Provider::Echo.into()],
+            ..Config::default()
+        })
+        .unwrap();
+
+        let answer = client.generate("What year is it?").unwrap();
+
+        assert_eq!(answer, EchoProvider.answer("What year is it?"));
+    }
+
+    #[test]
+    fn test_provider_chain_records_the_provider_that_answered() {
+        let dir = tempfile::tempdir().unwrap();
+        let trace_path = dir.path().join("trace.jsonl");
+        let client = LlmClient::new(Config {
+            ollama_base_url: unreachable_url(),
+            providers: vec![
+                Provider::Ollama.into(),
+                ProviderSettings {
+                    model: Some("echo-model".to_string()),
+                    ..Provider::Echo.into()
+                },
+            ],
+            trace_path: Some(trace_path.clone()),
+            ..Config::default()
+        })
+        .unwrap();
+
+        client.generate("What year is it?").unwrap();
+
+        let answered_by = client.answered_by().unwrap();
+        assert_eq!(answered_by.provider, Provider::Echo);
+        assert_eq!(answered_by.model, "echo-model");
+        let entry: Value =
+            serde_json::from_str(&std::fs::read_to_string(&trace_path).unwrap()).unwrap();
+        assert_eq!(entry["provider"], "echo");
+        assert_eq!(entry["model"], "echo-model");
+    }
+
+    #[test]
+    fn test_each_provider_is_sent_its_own_endpoint_model_and_key() {
+        let client = LlmClient::new(Config {
+            api_key: Some("sk-local".to_string()),
+            providers: vec![
+                Provider::Ollama.into(),
+                ProviderSettings {
+                    base_url: Some("http://hosted.example".to_string()),
+                    model: Some("mistral".to_string()),
+                    api_key: Some("sk-hosted".to_string()),
+                    ..Provider::Ollama.into()
+                },
+            ],
+            ..Config::default()
+        })
+        .unwrap();
+        let chain = client.config().provider_chain();
+
+        let request = |backend: &Backend| {
+            client
+                .blocking_request(backend, "Hello", None)
+                .unwrap()
+                .build()
+                .unwrap()
+        };
+        let (local, hosted) = (request(&chain[0]), request(&chain[1]));
+
+        let model = |request: &reqwest::blocking::Request| {
+            let body: Value =
+                serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+            body["model"].clone()
+        };
+        assert_eq!(local.url().as_str(), "http://localhost:11434/api/generate");
+        assert_eq!(model(&local), "llama3.1");
+        assert_eq!(local.headers()["Authorization"], "Bearer sk-local");
+        assert_eq!(hosted.url().as_str(), "http://hosted.example/api/generate");
+        assert_eq!(model(&hosted), "mistral");
+        assert_eq!(hosted.headers()["Authorization"], "Bearer sk-hosted");
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_provider_chain_reports_every_failure() {
+        let client = LlmClient::new(Config {
+            ollama_base_url: unreachable_url(),
+            providers: vec![Provider::Ollama.into(), Provider::OpenAi.into()],
+            ..Config::default()
+        })
+        .unwrap();
+
+        let error = client.generate("What year is it?").unwrap_err().to_string();
+
+        assert!(
+            error.starts_with("All 2 providers failed: Ollama: "),
+            "{}",
+            error
+        );
+        assert!(error.contains("; OpenAi: "), "{}", error);
+    }
+
+    #[test]
+    fn test_model_and_stop_overrides_reach_the_request_body() {
+        let client = LlmClient::default();
+        let overridden = client.clone().with_model("mistral").with_stop(&["\n\n"]);
+
+        let body = overridden.request_body(&backend(&overridden, Provider::Ollama), "Hello", None);
+        let default_body = client.request_body(&backend(&client, Provider::Ollama), "Hello", None);
+
+        assert_eq!(body["model"], "mistral");
+        assert_eq!(body["options"]["stop"], json!(["\n\n"]));
+        assert_eq!(default_body["model"], "llama3.1");
+        assert!(default_body["options"].get("stop").is_none());
+    }
+
+    #[test]
+    fn test_failed_requests_are_retried_and_time_out() {
+        // A server that counts connections and never answers them.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = connections.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut buffer = [0u8; 1024];
+                    while stream.read(&mut buffer).map(|n| n > 0).unwrap_or(false) {}
+                });
+            }
+        });
+        let client = LlmClient::new(Config {
+            ollama_base_url: format!("http://{}", address),
+            ..Config::default()
+        })
+        .unwrap()
+        .with_timeout_secs(1)
+        .with_retries(1);
+
+        let started = Instant::now();
+        let error = client.generate("Hello").unwrap_err();
+
+        assert!(format!("{:#}", error).contains("timed out"), "{:#}", error);
+        assert_eq!(connections.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_request_body_uses_temperature_override() {
+        let client = LlmClient::default();
+
+        let default_body = client.request_body(&backend(&client, Provider::Ollama), "Hello", None);
+        let body = client.request_body(&backend(&client, Provider::Ollama), "Hello", Some(0.2));
+
+        assert_eq!(default_body["options"]["temperature"], DEFAULT_TEMPERATURE);
+        assert_eq!(body["options"]["temperature"], 0.2);
+    }
+
+    #[test]
+    fn test_response_transformer_cleans_text_before_parsing() {
+        let client = LlmClient::default().with_response_transformer(|response: &str| {
+            response.trim_start_matches("Answer:").trim().to_string()
+        });
+
+        let content = client
+            .extract_content(Provider::Ollama, &json!({ "response": "Answer: 42" }))
+            .unwrap();
+
+        assert_eq!(content, "42");
+        assert_eq!(VibeValue::String(content).into_i32(), 42);
+    }
+
+    #[tokio::test]
+    async fn test_echo_provider_answers_without_a_backend() {
+        // Nothing listens on this address, so any request would fail.
+        let client = LlmClient::new(Config {
+            ollama_base_url: "http://127.0.0.1:9".to_string(),
+            provider: Provider::Echo,
+            ..Config::default()
+        })
+        .unwrap();
+        let prompt =
+            crate::runtime::transform::enhance_prompt("How warm is it?", "temperature in Celsius");
+
+        let blocking = tokio::task::spawn_blocking({
+            let prompt = prompt.clone();
+            move || client.generate(&prompt).map(|answer| (client, answer))
+        });
+        let (client, answer) = blocking.await.unwrap().unwrap();
+
+        assert_eq!(answer, "20");
+        assert_eq!(client.generate_async(&prompt).await.unwrap(), "20");
+    }
+
+    #[tokio::test]
+    async fn test_generate_async_with_timeout_returns_timeout_error() {
+        // A server that accepts the connection but never answers.
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 1024];
+            while stream.read(&mut buffer).map(|n| n > 0).unwrap_or(false) {}
+        });
+
+        let client = LlmClient::new(Config {
+            ollama_base_url: format!("http://{}", address),
+            ..Config::default()
+        })
+        .unwrap();
+
+        let timeout = Duration::from_millis(100);
+        let started = Instant::now();
+        let error = client
+            .generate_async_with_timeout("Hello", timeout)
+            .await
+            .expect_err("The request should time out");
+
+        assert_eq!(
+            error.downcast_ref::<LlmClientError>(),
+            Some(&LlmClientError::Timeout(timeout))
+        );
+        // The caller gets control back right away instead of waiting on the stalled request.
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
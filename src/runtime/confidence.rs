@@ -0,0 +1,68 @@
+/// The answer of a function annotated `@with_confidence`, with how sure the model said it was.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Confident<T> {
+    pub value: T,
+    /// From 0 to 1. `None` when the response gave no readable confidence.
+    pub confidence: Option<f64>,
+}
+
+/// Splits the `Confidence: 0.8` line off an LLM response, returning the rest of the response
+/// and the confidence.
+///
+/// The last such line is used; `**Confidence:** 0.8` and percentages such as `85%` or `85`
+/// are read too. The response is returned unchanged when it has no confidence line.
+pub fn split_confidence(response: &str) -> (String, Option<f64>) {
+    let mut lines: Vec<&str> = response.lines().collect();
+    let Some(index) = lines
+        .iter()
+        .rposition(|line| confidence_text(line).is_some())
+    else {
+        return (response.to_string(), None);
+    };
+    let confidence = confidence_text(lines[index]).and_then(parse_score);
+    lines.remove(index);
+    (lines.join("\n").trim().to_string(), confidence)
+}
+
+// The text after `Confidence:` when the line is a confidence line.
+fn confidence_text(line: &str) -> Option<&str> {
+    let line = line.trim().trim_start_matches(['*', '-', ' ']);
+    let label = line.get(.."confidence".len())?;
+    if !label.eq_ignore_ascii_case("confidence") {
+        return None;
+    }
+    let rest = line["confidence".len()..].trim_start_matches(['*', ' ']);
+    Some(rest.strip_prefix(':')?.trim_matches(['*', ' ']))
+}
+
+// A score from 0 to 1, read as a percentage when it is written with `%` or is above 1.
+fn parse_score(text: &str) -> Option<f64> {
+    let word = text.split_whitespace().next()?.trim_end_matches(['.', ',']);
+    let score = match word.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => {
+            let score = word.parse::<f64>().ok()?;
+            if score > 1.0 { score / 100.0 } else { score }
+        }
+    };
+    (0.0..=1.0).contains(&score).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_confidence_is_split_from_the_response() {
+        let (answer, confidence) = split_confidence("42\n\nConfidence: 0.85");
+        assert_eq!(answer, "42");
+        assert_eq!(confidence, Some(0.85));
+
+        assert_eq!(split_confidence("Paris.\n**Confidence:** 90%").1, Some(0.9));
+        assert_eq!(split_confidence("Confidence: very").1, None);
+        assert_eq!(
+            split_confidence("No score here."),
+            ("No score here.".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use crate::runtime::llm_provider::LlmProvider;
+use crate::runtime::transform::MEANING_PROMPT;
+use anyhow::Result;
+
+/// An offline provider that answers every prompt with a deterministic canned reply.
+///
+/// The reply is derived from the meaning the generated code puts in the prompt: numeric
+/// meanings such as `"temperature in Celsius"` get a plain number, other meanings get a short
+/// text naming the meaning, and prompts without a meaning are echoed back. Select it with
+/// `VIBELANG_PROVIDER=echo` to run generated programs without an LLM, e.g. in docs or CI.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EchoProvider;
+
+// Meaning keywords answered with a number, checked in order.
+const NUMERIC_ANSWERS: &[(&str, &str)] = &[
+    ("temperature", "20"),
+    ("year", "2024"),
+    ("age", "30"),
+    ("population", "42"),
+    ("count", "42"),
+    ("number", "42"),
+    ("amount", "42"),
+    ("score", "42"),
+];
+
+impl EchoProvider {
+    /// Returns the canned reply for `prompt`.
+    pub fn answer(&self, prompt: &str) -> String {
+        let Some(meaning) = meaning_of(prompt) else {
+            return format!("Echo: {}", prompt);
+        };
+
+        let lowercase = meaning.to_lowercase();
+        NUMERIC_ANSWERS
+            .iter()
+            .find(|(keyword, _)| lowercase.contains(keyword))
+            .map(|(_, answer)| answer.to_string())
+            .unwrap_or_else(|| format!("Echo {}", meaning))
+    }
+}
+
+impl LlmProvider for EchoProvider {
+    fn generate(&self, prompt: &str) -> Result<String> {
+        Ok(self.answer(prompt))
+    }
+}
+
+/// Reads the meaning from a prompt built by `enhance_prompt`.
+fn meaning_of(prompt: &str) -> Option<&str> {
+    let (marker, _) = MEANING_PROMPT.split_once("{meaning}").unwrap();
+    let start = prompt.find(marker)? + marker.len();
+    let length = prompt[start..].find('"')?;
+    Some(&prompt[start..start + length])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::transform::enhance_prompt;
+    use crate::runtime::types::VibeValue;
+
+    #[test]
+    fn test_echo_answers_are_deterministic_and_parseable() {
+        let provider = EchoProvider;
+
+        let temperature = provider.generate(&enhance_prompt(
+            "How warm is Rome?",
+            "temperature in Celsius",
+        ));
+        let year = provider.generate(&enhance_prompt("When?", "year when established"));
+        let weather = provider.generate(&enhance_prompt("Weather?", "weather description"));
+
+        assert_eq!(VibeValue::String(temperature.unwrap()).into_f64(), 20.0);
+        assert_eq!(VibeValue::String(year.unwrap()).into_i32(), 2024);
+        assert_eq!(weather.unwrap(), "Echo weather description");
+        assert_eq!(provider.generate("Say hi").unwrap(), "Echo: Say hi");
+    }
+}
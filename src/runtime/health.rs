@@ -0,0 +1,190 @@
+use crate::config::{Config, Provider};
+use anyhow::{Result, anyhow};
+use reqwest::blocking::Client;
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+// How long the probe waits for the backend before reporting it unreachable.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The result of probing the configured LLM backend with `check_backend`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthReport {
+    pub provider: Provider,
+    pub base_url: String,
+    pub model: String,
+    /// Whether the backend answered the probe request successfully.
+    pub reachable: bool,
+    /// Whether the backend lists the configured model, if it could be asked.
+    pub model_available: Option<bool>,
+    /// Why the probe failed, when it did.
+    pub error: Option<String>,
+}
+
+impl HealthReport {
+    /// True when the backend is reachable and serves the configured model.
+    pub fn is_healthy(&self) -> bool {
+        self.reachable && self.model_available != Some(false)
+    }
+}
+
+impl fmt::Display for HealthReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "provider:  {:?}", self.provider)?;
+        writeln!(f, "base URL:  {}", self.base_url)?;
+        writeln!(f, "model:     {}", self.model)?;
+        let reachable = if self.reachable { "yes" } else { "no" };
+        writeln!(f, "reachable: {}", reachable)?;
+        match self.model_available {
+            Some(true) => writeln!(f, "model available: yes")?,
+            Some(false) => writeln!(f, "model available: no, pull or configure it first")?,
+            None => {}
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "error:     {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Probes the backend described by `config` without generating anything.
+///
+/// Ollama is asked for its installed models at `/api/tags` and OpenAI-compatible backends at
+/// `/v1/models`; the echo provider needs no backend and is always healthy.
+pub fn check_backend(config: &Config) -> HealthReport {
+    let mut report = HealthReport {
+        provider: config.provider,
+        base_url: config.ollama_base_url.clone(),
+        model: config.ollama_model.clone(),
+        reachable: false,
+        model_available: None,
+        error: None,
+    };
+
+    let (path, list_key, name_key) = match config.provider {
+        Provider::Echo => {
+            report.reachable = true;
+            return report;
+        }
+        Provider::Ollama => ("/api/tags", "models", "name"),
+        #[cfg(feature = "openai")]
+        Provider::OpenAi => ("/v1/models", "data", "id"),
+    };
+
+    match fetch_json(&format!("{}{}", config.ollama_base_url, path), config) {
+        Ok(body) => {
+            report.reachable = true;
+            report.model_available = body[list_key].as_array().map(|models| {
+                models.iter().any(|model| {
+                    model[name_key]
+                        .as_str()
+                        .is_some_and(|name| is_same_model(name, &config.ollama_model))
+                })
+            });
+        }
+        Err(e) => report.error = Some(e.to_string()),
+    }
+    report
+}
+
+fn fetch_json(url: &str, config: &Config) -> Result<Value> {
+    let response = Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()?
+        .get(url)
+        .headers(config.request_headers()?)
+        .send()
+        .map_err(|e| anyhow!("Could not connect to {}: {}", url, e))?;
+    if !response.status().is_success() {
+        return Err(anyhow!(
+            "{} answered with status {}",
+            url,
+            response.status()
+        ));
+    }
+    Ok(response.json()?)
+}
+
+// Ollama lists models with a tag, so `llama3.1` matches `llama3.1:latest`.
+fn is_same_model(listed: &str, configured: &str) -> bool {
+    listed == configured
+        || listed
+            .strip_prefix(configured)
+            .is_some_and(|tag| tag.starts_with(':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    // Answers a single request with a 200 and the given JSON body.
+    fn serve_once(body: &'static str) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = [0u8; 1024];
+            let _ = stream.read(&mut buffer);
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        });
+        format!("http://{}", address)
+    }
+
+    #[test]
+    fn test_check_backend_reports_available_model() {
+        let config = Config {
+            ollama_base_url: serve_once(r#"{"models":[{"name":"llama3.1:latest"}]}"#),
+            ..Config::default()
+        };
+
+        let report = check_backend(&config);
+
+        assert!(report.is_healthy(), "{}", report);
+        assert_eq!(report.model_available, Some(true));
+        assert!(report.to_string().contains("reachable: yes"));
+    }
+
+    #[test]
+    fn test_check_backend_reports_missing_model() {
+        let config = Config {
+            ollama_base_url: serve_once(r#"{"models":[{"name":"mistral:latest"}]}"#),
+            ..Config::default()
+        };
+
+        let report = check_backend(&config);
+
+        assert!(report.reachable);
+        assert!(!report.is_healthy());
+        assert!(report.to_string().contains("model available: no"));
+    }
+
+    #[test]
+    fn test_check_backend_reports_connection_error() {
+        // Bind and release a port so nothing is listening on it.
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let config = Config {
+            ollama_base_url: format!("http://{}", address),
+            ..Config::default()
+        };
+
+        let report = check_backend(&config);
+
+        assert!(!report.is_healthy());
+        assert!(!report.reachable);
+        let error = report.error.unwrap();
+        assert!(error.contains("Could not connect to"), "{}", error);
+        assert!(error.contains(&address.to_string()), "{}", error);
+    }
+}
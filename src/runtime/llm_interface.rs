@@ -1,8 +1,20 @@
+use crate::runtime::placeholder::PlaceholderStyle;
+use crate::runtime::response_adapter::{OpenAiAdapter, ResponseAdapter};
 use anyhow::{Result, anyhow};
 use reqwest;
 use serde_json::{Value, json};
 use std::collections::HashMap;
 use std::env;
+use thiserror::Error;
+
+/// Errors raised while reading a value out of an LLM response.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ResponseError {
+    /// A numeric answer was expected, but the response contains no number. Reported instead
+    /// of a zero, so that "the answer is 0" can be told apart from "no answer".
+    #[error("No number found in LLM response '{0}'")]
+    NoNumber(String),
+}
 
 pub struct LlmInterface {
     client: reqwest::blocking::Client,
@@ -57,7 +69,7 @@ impl LlmInterface {
     fn is_ollama_available(url: &str) -> bool {
         let client = reqwest::blocking::Client::new();
         match client
-            .get(&format!("{}/api/tags", url))
+            .get(format!("{}/api/tags", url))
             .timeout(std::time::Duration::from_secs(2))
             .send()
         {
@@ -67,7 +79,7 @@ impl LlmInterface {
     }
 
     pub fn execute_prompt(&self, prompt: &str, meaning: Option<&str>) -> Result<VibeValue> {
-        let response = self.send_to_llm(prompt).unwrap();
+        let response = self.send_to_llm(prompt)?;
         self.parse_response(&response, meaning)
     }
 
@@ -85,7 +97,7 @@ impl LlmInterface {
 
         let response = self
             .client
-            .post(&format!("{}/chat/completions", self.base_url))
+            .post(format!("{}/chat/completions", self.base_url))
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send();
@@ -99,26 +111,16 @@ impl LlmInterface {
 
         let response_json: Value = payload.json()?;
 
-        let content = response_json
-            .get("choices")
-            .and_then(|c| c.as_array())
-            .and_then(|arr| arr.first())
-            .and_then(|choice| choice.get("message"))
-            .and_then(|msg| msg.get("content"))
-            .and_then(|content| content.as_str())
-            .ok_or_else(|| anyhow!("Invalid response format from LLM API"))?;
-
-        Ok(content.to_string())
+        OpenAiAdapter.extract_text(&response_json)
     }
 
     fn parse_response(&self, response: &str, meaning: Option<&str>) -> Result<VibeValue> {
         match meaning {
             Some("temperature in Celsius") => {
-                let temperature: f64 = response
-                    .trim()
-                    .parse()
-                    .or_else(|_| self.extract_number_from_text(response))
-                    .unwrap_or(0.0);
+                let temperature: f64 = match response.trim().parse() {
+                    Ok(value) => value,
+                    Err(_) => self.extract_number_from_text(response)?,
+                };
 
                 Ok(VibeValue {
                     value_type: VibeValueType::Number,
@@ -136,7 +138,7 @@ impl LlmInterface {
         }
     }
 
-    fn extract_number_from_text(&self, text: &str) -> Result<f64, std::num::ParseFloatError> {
+    fn extract_number_from_text(&self, text: &str) -> Result<f64, ResponseError> {
         text.split_whitespace()
             .find_map(|word| {
                 word.chars()
@@ -145,16 +147,24 @@ impl LlmInterface {
                     .parse::<f64>()
                     .ok()
             })
-            .ok_or_else(|| "0.0".parse::<f64>().unwrap_err())
+            .ok_or_else(|| ResponseError::NoNumber(text.to_string()))
     }
 }
 
 pub fn format_prompt(template: &str, variables: &HashMap<String, String>) -> String {
+    format_prompt_with(template, variables, PlaceholderStyle::Braces)
+}
+
+/// Like `format_prompt`, for a template whose placeholders are written in `style`.
+pub fn format_prompt_with(
+    template: &str,
+    variables: &HashMap<String, String>,
+    style: PlaceholderStyle,
+) -> String {
     let mut result = template.to_string();
 
     for (name, value) in variables {
-        let placeholder = format!("{{{}}}", name);
-        result = result.replace(&placeholder, value);
+        result = result.replace(&style.placeholder(name), value);
     }
 
     result
@@ -219,3 +229,51 @@ impl VibeValue {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn interface() -> LlmInterface {
+        LlmInterface {
+            client: reqwest::blocking::Client::new(),
+            base_url: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_response_without_a_number_is_an_error() {
+        let error = interface()
+            .parse_response("It is quite mild today.", Some("temperature in Celsius"))
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<ResponseError>(),
+            Some(&ResponseError::NoNumber(
+                "It is quite mild today.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_zero_is_a_valid_answer() {
+        let value = interface()
+            .parse_response("It is 0 degrees.", Some("temperature in Celsius"))
+            .unwrap();
+
+        assert_eq!(value.get_number(), Some(0.0));
+    }
+
+    #[test]
+    fn test_format_prompt_with_dollar_placeholders_keeps_braces() {
+        let variables = HashMap::from([("city".to_string(), "Oslo".to_string())]);
+
+        let prompt = format_prompt_with(
+            r#"Reply as {"city": "${city}"}, not {city}."#,
+            &variables,
+            PlaceholderStyle::Dollar,
+        );
+
+        assert_eq!(prompt, r#"Reply as {"city": "Oslo"}, not {city}."#);
+    }
+}
@@ -0,0 +1,81 @@
+use crate::config::Provider;
+use crate::runtime::client::LlmClient;
+use std::time::{Duration, Instant};
+
+/// How a generated function's LLM call went, as returned by its `<name>_with_meta` companion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallMeta {
+    /// Time from the start of the call until the parsed value was ready.
+    pub latency: Duration,
+    /// The model asked by the provider that answered.
+    pub model: String,
+    /// The provider of the chain that answered, which may be a fallback. Without an answer,
+    /// as with a `with_llm_provider`, the first provider of the chain.
+    pub provider: Provider,
+}
+
+impl CallMeta {
+    /// Describes a call made with `llm` that started at `started` and has just finished.
+    pub fn since(llm: &LlmClient, started: Instant) -> Self {
+        let backend = llm
+            .answered_by()
+            .unwrap_or_else(|| llm.config().provider_chain().remove(0));
+        Self {
+            latency: started.elapsed(),
+            model: backend.model,
+            provider: backend.provider,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ProviderSettings};
+    use crate::runtime::llm_provider::LlmProvider;
+
+    #[test]
+    fn test_call_meta_describes_the_call() {
+        let llm = LlmClient::new(Config {
+            provider: Provider::Echo,
+            ollama_model: "echo-model".to_string(),
+            ..Config::default()
+        })
+        .unwrap();
+
+        // Shaped like a generated `<name>_with_meta` companion.
+        let started = Instant::now();
+        let value = llm.generate("What year is it?").unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        let (value, meta) = (value, CallMeta::since(&llm, started));
+
+        assert!(!value.is_empty());
+        assert_eq!(meta.model, "echo-model");
+        assert_eq!(meta.provider, Provider::Echo);
+        assert!(meta.latency >= Duration::from_millis(5));
+    }
+
+    #[test]
+    fn test_call_meta_reports_the_fallback_that_answered() {
+        // Nothing listens on this address, so Ollama fails and the echo provider answers.
+        let llm = LlmClient::new(Config {
+            ollama_base_url: "http://127.0.0.1:9".to_string(),
+            providers: vec![
+                Provider::Ollama.into(),
+                ProviderSettings {
+                    model: Some("echo-model".to_string()),
+                    ..Provider::Echo.into()
+                },
+            ],
+            ..Config::default()
+        })
+        .unwrap();
+
+        let started = Instant::now();
+        llm.generate("What year is it?").unwrap();
+        let meta = CallMeta::since(&llm, started);
+
+        assert_eq!(meta.provider, Provider::Echo);
+        assert_eq!(meta.model, "echo-model");
+    }
+}
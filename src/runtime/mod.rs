@@ -1,4 +1,17 @@
+#[cfg(feature = "runtime")]
 pub mod client;
+pub mod confidence;
+pub mod echo;
+#[cfg(feature = "runtime")]
+pub mod health;
+#[cfg(feature = "runtime")]
 pub mod llm_interface;
 pub mod llm_provider;
+#[cfg(feature = "runtime")]
+pub mod meta;
+pub mod placeholder;
+pub mod response_adapter;
+pub mod retry;
+pub mod transform;
 pub mod types;
+pub mod validation;
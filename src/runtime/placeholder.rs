@@ -0,0 +1,87 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static BRACES_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+static DOUBLE_BRACES_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\{\{([A-Za-z_][A-Za-z0-9_]*)\}\}").unwrap());
+static DOLLAR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\$\{([A-Za-z_][A-Za-z0-9_]*)\}").unwrap());
+
+/// How a prompt marks the places its arguments are substituted.
+///
+/// Chosen in the source with e.g. `@placeholders("${name}")`, for prompts whose text needs
+/// braces of its own, such as JSON examples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PlaceholderStyle {
+    /// `{name}`.
+    #[default]
+    Braces,
+    /// `{{name}}`.
+    DoubleBraces,
+    /// `${name}`.
+    Dollar,
+}
+
+impl PlaceholderStyle {
+    /// Looks up a style by an example placeholder: `{name}`, `{{name}}` or `${name}`.
+    pub fn from_syntax(syntax: &str) -> Option<Self> {
+        match syntax {
+            "{name}" => Some(Self::Braces),
+            "{{name}}" => Some(Self::DoubleBraces),
+            "${name}" => Some(Self::Dollar),
+            _ => None,
+        }
+    }
+
+    /// The text before and after the name of a placeholder.
+    pub fn delimiters(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Braces => ("{", "}"),
+            Self::DoubleBraces => ("{{", "}}"),
+            Self::Dollar => ("${", "}"),
+        }
+    }
+
+    /// The placeholder for `name`, e.g. `${city}`.
+    pub fn placeholder(self, name: &str) -> String {
+        let (open, close) = self.delimiters();
+        format!("{}{}{}", open, name, close)
+    }
+
+    /// Lists the distinct placeholder names in `template`, in order of first appearance.
+    pub fn names_in(self, template: &str) -> Vec<String> {
+        let re = match self {
+            Self::Braces => &BRACES_RE,
+            Self::DoubleBraces => &DOUBLE_BRACES_RE,
+            Self::Dollar => &DOLLAR_RE,
+        };
+        let mut names: Vec<String> = Vec::new();
+        for captures in re.captures_iter(template) {
+            let name = &captures[1];
+            if !names.iter().any(|existing| existing == name) {
+                names.push(name.to_string());
+            }
+        }
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dollar_style_leaves_braces_alone() {
+        let template = r#"Fill {"city": "${city}", "days": ${days}} for ${city}."#;
+
+        let names = PlaceholderStyle::Dollar.names_in(template);
+
+        assert_eq!(names, ["city", "days"]);
+        assert_eq!(PlaceholderStyle::Dollar.placeholder("city"), "${city}");
+        assert_eq!(
+            PlaceholderStyle::from_syntax("{{name}}"),
+            Some(PlaceholderStyle::DoubleBraces)
+        );
+        assert_eq!(PlaceholderStyle::from_syntax("<name>"), None);
+    }
+}
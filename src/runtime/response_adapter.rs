@@ -0,0 +1,108 @@
+use crate::config::Provider;
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+
+/// Extracts the generated text from a provider's raw JSON response.
+///
+/// Each provider wraps the completion differently; an adapter keeps that knowledge in one place.
+pub trait ResponseAdapter {
+    fn extract_text(&self, response_json: &Value) -> Result<String>;
+}
+
+/// Reads the `response` field returned by Ollama's `/api/generate`.
+pub struct OllamaAdapter;
+
+impl ResponseAdapter for OllamaAdapter {
+    fn extract_text(&self, response_json: &Value) -> Result<String> {
+        let content = response_json["response"].as_str().ok_or_else(|| {
+            anyhow!(
+                "Invalid response format from LLM API: `response` field missing or not a string"
+            )
+        })?;
+
+        Ok(content.to_string())
+    }
+}
+
+/// Reads `choices[0].message.content` as returned by OpenAI-compatible chat completions.
+pub struct OpenAiAdapter;
+
+impl ResponseAdapter for OpenAiAdapter {
+    fn extract_text(&self, response_json: &Value) -> Result<String> {
+        let content = response_json
+            .get("choices")
+            .and_then(|c| c.as_array())
+            .and_then(|arr| arr.first())
+            .and_then(|choice| choice.get("message"))
+            .and_then(|msg| msg.get("content"))
+            .and_then(|content| content.as_str())
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid response format from LLM API: `choices[0].message.content` missing or not a string"
+                )
+            })?;
+
+        Ok(content.to_string())
+    }
+}
+
+/// Returns the adapter that understands `provider`'s responses.
+pub fn adapter_for(provider: Provider) -> &'static dyn ResponseAdapter {
+    match provider {
+        // Echo replies never come from a request; they use the Ollama shape if ever parsed.
+        Provider::Ollama | Provider::Echo => &OllamaAdapter,
+        #[cfg(feature = "openai")]
+        Provider::OpenAi => &OpenAiAdapter,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_ollama_adapter_reads_generate_payload() {
+        let payload = json!({
+            "model": "llama3.1",
+            "created_at": "2024-01-01T00:00:00Z",
+            "response": "Paris",
+            "done": true
+        });
+
+        let text = adapter_for(Provider::Ollama)
+            .extract_text(&payload)
+            .unwrap();
+
+        assert_eq!(text, "Paris");
+    }
+
+    #[test]
+    #[cfg(feature = "openai")]
+    fn test_openai_adapter_reads_chat_completion_payload() {
+        let payload = json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Paris" },
+                "finish_reason": "stop"
+            }]
+        });
+
+        let text = adapter_for(Provider::OpenAi)
+            .extract_text(&payload)
+            .unwrap();
+
+        assert_eq!(text, "Paris");
+    }
+
+    #[test]
+    fn test_adapters_reject_the_other_providers_payload() {
+        let ollama_payload = json!({ "response": "Paris" });
+        let openai_payload = json!({ "choices": [{ "message": { "content": "Paris" } }] });
+
+        assert!(OpenAiAdapter.extract_text(&ollama_payload).is_err());
+        assert!(OllamaAdapter.extract_text(&openai_payload).is_err());
+    }
+}
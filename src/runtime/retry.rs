@@ -0,0 +1,116 @@
+use crate::runtime::types::VibeValue;
+use anyhow::Result;
+use std::future::Future;
+
+/// Appended to the prompt when a numeric answer contains no number.
+pub const NUMBER_CLARIFICATION: &str = "Reply with ONLY a number.";
+
+/// The number of times generated code asks again for a numeric answer, unless configured.
+pub const DEFAULT_NUMBER_RETRIES: usize = 2;
+
+/// Whether some word of `text` is a number, possibly written as a percentage or an amount.
+pub fn has_number(text: &str) -> bool {
+    text.split_whitespace()
+        .any(|word| VibeValue::parse_number(word).is_some())
+}
+
+/// Sends `prompt` with `generate`, then asks again with `NUMBER_CLARIFICATION` appended,
+/// up to `retries` times, while the response contains no number.
+///
+/// Returns the first response with a number, or the last response if none has one. Errors
+/// from `generate` are returned immediately.
+pub fn retry_until_number<F>(prompt: &str, retries: usize, mut generate: F) -> Result<String>
+where
+    F: FnMut(&str) -> Result<String>,
+{
+    let mut response = generate(prompt)?;
+    let clarified = clarified_prompt(prompt);
+    for _ in 0..retries {
+        if has_number(&response) {
+            break;
+        }
+        response = generate(&clarified)?;
+    }
+    Ok(response)
+}
+
+/// Like `retry_until_number`, for an async `generate`.
+pub async fn retry_until_number_async<F, Fut>(
+    prompt: &str,
+    retries: usize,
+    mut generate: F,
+) -> Result<String>
+where
+    F: FnMut(String) -> Fut,
+    Fut: Future<Output = Result<String>>,
+{
+    let mut response = generate(prompt.to_string()).await?;
+    let clarified = clarified_prompt(prompt);
+    for _ in 0..retries {
+        if has_number(&response) {
+            break;
+        }
+        response = generate(clarified.clone()).await?;
+    }
+    Ok(response)
+}
+
+fn clarified_prompt(prompt: &str) -> String {
+    format!("{}\n\n{}", prompt, NUMBER_CLARIFICATION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::llm_provider::{LlmProvider, MockLlmProvider};
+    use mockall::Sequence;
+
+    #[test]
+    fn test_retry_returns_the_first_answer_with_a_number() {
+        let mut llm = MockLlmProvider::new();
+        let mut sequence = Sequence::new();
+        llm.expect_generate()
+            .withf(|prompt| prompt == "Population of Oslo?")
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok("Quite a lot of people.".to_string()));
+        llm.expect_generate()
+            .withf(|prompt| prompt.ends_with(NUMBER_CLARIFICATION))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok("709000".to_string()));
+
+        let response =
+            retry_until_number("Population of Oslo?", 3, |prompt| llm.generate(prompt)).unwrap();
+
+        assert_eq!(response, "709000");
+        assert_eq!(VibeValue::String(response).into_f64(), 709000.0);
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_the_configured_attempts() {
+        let mut llm = MockLlmProvider::new();
+        llm.expect_generate()
+            .times(2)
+            .returning(|_| Ok("I cannot say.".to_string()));
+
+        let response = retry_until_number("How warm is it?", 1, |prompt| llm.generate(prompt));
+
+        assert_eq!(response.unwrap(), "I cannot say.");
+    }
+
+    #[tokio::test]
+    async fn test_async_retry_asks_again_without_a_number() {
+        let mut answers = vec!["42 degrees".to_string(), "No idea".to_string()];
+
+        let response = retry_until_number_async("How warm is it?", 2, |_| {
+            let answer = answers.pop().unwrap();
+            async move { Ok(answer) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(response, "42 degrees");
+        assert!(answers.is_empty());
+    }
+}
@@ -0,0 +1,50 @@
+/// Rewrites a prompt before `LlmClient` sends it to the provider.
+///
+/// Register one with `LlmClient::with_prompt_transformer` to add guardrails, translate, or
+/// redact prompts without editing generated code. Closures taking and returning the prompt
+/// implement this trait too.
+pub trait PromptTransformer: Send + Sync {
+    fn transform(&self, prompt: &str) -> String;
+}
+
+impl<F> PromptTransformer for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn transform(&self, prompt: &str) -> String {
+        self(prompt)
+    }
+}
+
+/// The prompt generated code sends when the answer has a semantic meaning, with `{meaning}`
+/// and `{prompt}` standing for the meaning and the function's prompt.
+pub const MEANING_PROMPT: &str = "Context: You are an AI assistant that must perform a specific task. The user wants a value that has the semantic meaning of \"{meaning}\". Please provide a concise answer based on the following query.\n\nQuery: {prompt}";
+
+/// Fills in `MEANING_PROMPT`. Generated code calls this to build its prompts.
+pub fn enhance_prompt(prompt: &str, meaning: &str) -> String {
+    let (context, query) = MEANING_PROMPT.split_once("{prompt}").unwrap();
+    format!(
+        "{}{}{}",
+        context.replace("{meaning}", meaning),
+        prompt,
+        query
+    )
+}
+
+/// Rewrites the raw text returned by the provider before it reaches semantic parsing.
+///
+/// Register one with `LlmClient::with_response_transformer` to normalize provider quirks, such
+/// as a fixed preamble, for every generated function at once. Closures taking and returning the
+/// response text implement this trait too.
+pub trait ResponseTransformer: Send + Sync {
+    fn transform(&self, response: &str) -> String;
+}
+
+impl<F> ResponseTransformer for F
+where
+    F: Fn(&str) -> String + Send + Sync,
+{
+    fn transform(&self, response: &str) -> String {
+        self(response)
+    }
+}
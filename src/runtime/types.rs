@@ -1,34 +1,200 @@
 // src/runtime/types.rs
+use anyhow::{Result, anyhow};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt;
 
-#[derive(Debug, Clone)]
+// A whole word, signed, such as `45%`, `-2.5%` or `.5%`; the `%` may be left out.
+static PERCENTAGE_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([+-]?)(\d+(?:\.\d+)?|\.\d+)%?$").unwrap());
+// A whole word such as `$1,200`, `-$4.99` or `1,200€`: commas must separate groups of three.
+static CURRENCY_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^([+-]?)[$€£¥]?([+-]?)(\d{1,3}(?:,\d{3})+(?:\.\d+)?|\d+(?:\.\d+)?|\.\d+)[$€£¥]?$")
+        .unwrap()
+});
+
+/// How floating-point numbers are written as text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatFormat {
+    /// The shortest text that parses back to the same number, e.g. `45.6`. Large values
+    /// are written out in full rather than in scientific notation.
+    #[default]
+    Shortest,
+    /// A fixed number of decimal places, e.g. `45.60` with two.
+    Fixed(usize),
+}
+
+impl FloatFormat {
+    /// Formats `n` according to this policy. Never uses scientific notation.
+    pub fn format(self, n: f64) -> String {
+        match self {
+            FloatFormat::Shortest => n.to_string(),
+            FloatFormat::Fixed(decimals) => format!("{:.*}", decimals, n),
+        }
+    }
+
+    /// Formats `n` as a Rust `f64` literal, which always contains a decimal point.
+    pub fn format_literal(self, n: f64) -> String {
+        let text = self.format(n);
+        if text.contains('.') || !n.is_finite() {
+            text
+        } else {
+            format!("{}.0", text)
+        }
+    }
+}
+
+/// How a number is written in an LLM response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberStyle {
+    /// A bare number such as `45` or `4.5`.
+    #[default]
+    Plain,
+    /// A percentage such as `45%`.
+    Percentage,
+    /// An amount of money such as `$1,200` or `€4.99`.
+    Currency,
+}
+
+impl NumberStyle {
+    /// Picks the style from keywords in a semantic meaning, e.g. "percentage" or "price".
+    pub fn for_meaning(meaning: &str) -> Self {
+        let meaning = meaning.to_lowercase();
+        if meaning.contains("percent") {
+            NumberStyle::Percentage
+        } else if ["price", "currency", "cost"]
+            .iter()
+            .any(|keyword| meaning.contains(keyword))
+        {
+            NumberStyle::Currency
+        } else {
+            NumberStyle::Plain
+        }
+    }
+
+    /// The name used for the style in generated code, or `None` for plain numbers.
+    pub fn name(self) -> Option<&'static str> {
+        match self {
+            NumberStyle::Plain => None,
+            NumberStyle::Percentage => Some("percentage"),
+            NumberStyle::Currency => Some("currency"),
+        }
+    }
+
+    /// Parses a single word written in this style, such as `45%` or `$1,200`.
+    ///
+    /// Quotes, brackets and punctuation ending a sentence around the word are ignored, but the
+    /// rest of it must be the number: `abc45` is not a number in any style.
+    pub fn parse(self, word: &str) -> Option<f64> {
+        let word = word
+            .trim_start_matches(['"', '\'', '('])
+            .trim_end_matches(['"', '\'', ')', '.', ',', ';', ':', '!', '?']);
+        let (negative, digits) = match self {
+            NumberStyle::Plain => return word.parse().ok(),
+            NumberStyle::Percentage => {
+                let captures = PERCENTAGE_RE.captures(word)?;
+                (&captures[1] == "-", captures[2].to_string())
+            }
+            NumberStyle::Currency => {
+                let captures = CURRENCY_RE.captures(word)?;
+                if !captures[1].is_empty() && !captures[2].is_empty() {
+                    return None;
+                }
+                (
+                    &captures[1] == "-" || &captures[2] == "-",
+                    captures[3].replace(',', ""),
+                )
+            }
+        };
+        let value = digits.parse::<f64>().ok()?;
+        Some(if negative { -value } else { value })
+    }
+
+    /// Returns the first number in `text` written in this style.
+    pub fn find_number(self, text: &str) -> Option<f64> {
+        text.split_whitespace().find_map(|word| self.parse(word))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum VibeValue {
     Null,
     Boolean(bool),
     Number(f64),
     String(String),
+    /// A JSON array, as converted with `From<serde_json::Value>`.
+    Array(Vec<VibeValue>),
+    /// A JSON object, with its keys in sorted order.
+    Object(BTreeMap<String, VibeValue>),
 }
 
 impl VibeValue {
+    /// Parses a number that may be written as a percentage or an amount of money, so
+    /// `"45%"` gives `45.0` and `"$1,200"` gives `1200.0`.
+    pub fn parse_number(text: &str) -> Option<f64> {
+        let text = text.trim();
+        [
+            NumberStyle::Plain,
+            NumberStyle::Percentage,
+            NumberStyle::Currency,
+        ]
+        .iter()
+        .find_map(|style| style.parse(text))
+    }
+
+    // Whole numbers written as percentages or amounts of money are accepted as integers.
+    fn parse_integer(text: &str) -> Option<i32> {
+        Self::parse_whole_number(text, i32::MIN as f64, i32::MAX as f64).map(|n| n as i32)
+    }
+
+    fn parse_whole_number(text: &str, min: f64, max: f64) -> Option<f64> {
+        Self::parse_number(text).filter(|n| n.fract() == 0.0 && *n >= min && *n <= max)
+    }
+
     /// Converts VibeValue to an i32.
     /// Panics if the conversion is not possible or logical.
     pub fn into_i32(self) -> i32 {
         match self {
             VibeValue::Number(n) => n as i32,
-            VibeValue::String(s) => s
-                .parse::<i32>()
-                .unwrap_or_else(|e| panic!("Failed to convert LLM response '{}' to i32: {}", s, e)),
+            VibeValue::String(s) => s.parse::<i32>().unwrap_or_else(|e| {
+                Self::parse_integer(&s).unwrap_or_else(|| {
+                    panic!("Failed to convert LLM response '{}' to i32: {}", s, e)
+                })
+            }),
             _ => panic!("Cannot convert {:?} to i32", self),
         }
     }
 
+    /// Converts VibeValue to an i64, for whole numbers too large for an i32.
+    /// Panics if the conversion is not possible or logical.
+    pub fn into_i64(self) -> i64 {
+        if let VibeValue::String(s) = &self
+            && let Ok(n) = s.parse::<i64>()
+        {
+            return n;
+        }
+        Self::whole_number(self, "i64", i64::MIN as f64, i64::MAX as f64)
+            .unwrap_or_else(|e| panic!("{}", e)) as i64
+    }
+
+    /// Converts VibeValue to a u32.
+    /// Panics if the conversion is not possible or logical.
+    pub fn into_u32(self) -> u32 {
+        Self::whole_number(self, "u32", 0.0, u32::MAX as f64).unwrap_or_else(|e| panic!("{}", e))
+            as u32
+    }
+
     /// Converts VibeValue to an f64.
     /// Panics if the conversion is not possible or logical.
     pub fn into_f64(self) -> f64 {
         match self {
             VibeValue::Number(n) => n,
-            VibeValue::String(s) => s
-                .parse::<f64>()
-                .unwrap_or_else(|e| panic!("Failed to convert LLM response '{}' to f64: {}", s, e)),
+            VibeValue::String(s) => s.parse::<f64>().unwrap_or_else(|e| {
+                Self::parse_number(&s).unwrap_or_else(|| {
+                    panic!("Failed to convert LLM response '{}' to f64: {}", s, e)
+                })
+            }),
             _ => panic!("Cannot convert {:?} to f64", self),
         }
     }
@@ -52,22 +218,370 @@ impl VibeValue {
         }
     }
 
+    /// Converts the value to the VibeValue variant used for the Rust `target` type.
+    ///
+    /// `target` is a generated base type name: `i32`, `i64`, `u32`, `f64`, `bool` or `String`.
+    /// Unlike the `into_*` methods this never panics; values that cannot be converted are an
+    /// error. Generated functions convert their responses with this, then unwrap the result.
+    pub fn coerce(self, target: &str) -> Result<VibeValue> {
+        match (target, self) {
+            ("i32", value) => Self::whole_number(value, "i32", i32::MIN as f64, i32::MAX as f64)
+                .map(VibeValue::Number),
+            ("i64", value) => Self::whole_number(value, "i64", i64::MIN as f64, i64::MAX as f64)
+                .map(VibeValue::Number),
+            ("u32", value) => {
+                Self::whole_number(value, "u32", 0.0, u32::MAX as f64).map(VibeValue::Number)
+            }
+            ("f64", VibeValue::Number(n)) => Ok(VibeValue::Number(n)),
+            ("f64", VibeValue::String(s)) => match s.trim().parse::<f64>() {
+                Ok(n) => Ok(VibeValue::Number(n)),
+                Err(e) => Self::parse_number(&s)
+                    .map(VibeValue::Number)
+                    .ok_or_else(|| anyhow!("Failed to convert LLM response '{}' to f64: {}", s, e)),
+            },
+            ("bool", VibeValue::Boolean(b)) => Ok(VibeValue::Boolean(b)),
+            ("bool", VibeValue::String(s)) => match s.trim().to_lowercase().as_str() {
+                "true" => Ok(VibeValue::Boolean(true)),
+                "false" => Ok(VibeValue::Boolean(false)),
+                _ => Err(anyhow!("Cannot convert LLM response '{}' to bool", s)),
+            },
+            ("String", value) => Ok(VibeValue::String(value.into_string())),
+            ("f64" | "bool", value) => Err(anyhow!("Cannot convert {:?} to {}", value, target)),
+            (_, _) => Err(anyhow!("Unknown conversion target type `{}`", target)),
+        }
+    }
+
+    // A number must be whole and in range for `target`, whether it was parsed already or is
+    // still text.
+    fn whole_number(value: VibeValue, target: &str, min: f64, max: f64) -> Result<f64> {
+        let number = match &value {
+            VibeValue::Number(n) => Some(*n).filter(|n| n.fract() == 0.0 && *n >= min && *n <= max),
+            VibeValue::String(s) => Self::parse_whole_number(s, min, max),
+            _ => return Err(anyhow!("Cannot convert {:?} to {}", value, target)),
+        };
+        number.ok_or_else(|| {
+            anyhow!(
+                "Failed to convert LLM response '{}' to {}: not a whole number in range",
+                value,
+                target
+            )
+        })
+    }
+
+    /// Parses a JSON scalar such as `42`, `"Paris"`, `true` or `null` into a VibeValue.
+    ///
+    /// Arrays and objects are an error here; convert a `serde_json::Value` with `From` to keep
+    /// them.
+    pub fn from_json_str(json: &str) -> Result<VibeValue> {
+        let value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| anyhow!("Invalid JSON '{}': {}", json, e))?;
+        Self::from_json_value(value, json)
+    }
+
+    /// Reads the JSON array in an LLM response, such as `["Paris", 2100000]`, converting each
+    /// element to the matching Rust type in `targets` as `coerce` does.
+    ///
+    /// Text around the array is ignored. Fails unless the array has one element per target.
+    pub fn parse_tuple(text: &str, targets: &[&str]) -> Result<Vec<VibeValue>> {
+        let array = match (text.find('['), text.rfind(']')) {
+            (Some(start), Some(end)) if start < end => &text[start..=end],
+            _ => {
+                return Err(anyhow!(
+                    "Expected a JSON array of {} values in LLM response '{}'",
+                    targets.len(),
+                    text
+                ));
+            }
+        };
+        let elements: Vec<serde_json::Value> = serde_json::from_str(array)
+            .map_err(|e| anyhow!("Invalid JSON array '{}': {}", array, e))?;
+        if elements.len() != targets.len() {
+            return Err(anyhow!(
+                "Expected {} values in LLM response '{}', found {}",
+                targets.len(),
+                array,
+                elements.len()
+            ));
+        }
+        elements
+            .into_iter()
+            .zip(targets)
+            .map(|(element, target)| {
+                let json = element.to_string();
+                Self::from_json_value(element, &json)?.coerce(target)
+            })
+            .collect()
+    }
+
+    // Converts a parsed JSON scalar; `json` is its text, for the error message.
+    fn from_json_value(value: serde_json::Value, json: &str) -> Result<VibeValue> {
+        match value {
+            serde_json::Value::Null => Ok(VibeValue::Null),
+            serde_json::Value::Bool(b) => Ok(VibeValue::Boolean(b)),
+            serde_json::Value::Number(n) => n
+                .as_f64()
+                .map(VibeValue::Number)
+                .ok_or_else(|| anyhow!("JSON number {} does not fit in an f64", n)),
+            serde_json::Value::String(s) => Ok(VibeValue::String(s)),
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => Err(anyhow!(
+                "Cannot convert JSON '{}' to a VibeValue: only null, booleans, numbers and strings are supported",
+                json
+            )),
+        }
+    }
+
+    /// The name of the variant, for logs and error messages: `"null"`, `"boolean"`,
+    /// `"number"`, `"string"`, `"array"` or `"object"`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            VibeValue::Null => "null",
+            VibeValue::Boolean(_) => "boolean",
+            VibeValue::Number(_) => "number",
+            VibeValue::String(_) => "string",
+            VibeValue::Array(_) => "array",
+            VibeValue::Object(_) => "object",
+        }
+    }
+
+    /// The value as JSON, for storing or logging structured results.
+    ///
+    /// Converting the result back with `From<serde_json::Value>` gives an equal value, except
+    /// for numbers JSON cannot hold, such as NaN, which become `null`.
+    pub fn as_json(&self) -> serde_json::Value {
+        match self {
+            VibeValue::Null => serde_json::Value::Null,
+            VibeValue::Boolean(b) => serde_json::Value::Bool(*b),
+            VibeValue::Number(n) => serde_json::Number::from_f64(*n)
+                .map_or(serde_json::Value::Null, serde_json::Value::Number),
+            VibeValue::String(s) => serde_json::Value::String(s.clone()),
+            VibeValue::Array(items) => {
+                serde_json::Value::Array(items.iter().map(VibeValue::as_json).collect())
+            }
+            VibeValue::Object(fields) => serde_json::Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.as_json()))
+                    .collect(),
+            ),
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, VibeValue::Null)
+    }
+
+    pub fn is_boolean(&self) -> bool {
+        matches!(self, VibeValue::Boolean(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, VibeValue::Number(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, VibeValue::String(_))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self, VibeValue::Array(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, VibeValue::Object(_))
+    }
+
+    /// Adds two values as numbers. Numeric strings such as `"5"` or `"$1,200"` count as
+    /// numbers; anything else is an error.
+    pub fn add(&self, other: &VibeValue) -> Result<VibeValue> {
+        self.arithmetic(other, "add", |a, b| a + b)
+    }
+
+    /// Subtracts `other` from this value as numbers, coercing numeric strings like `add`.
+    pub fn sub(&self, other: &VibeValue) -> Result<VibeValue> {
+        self.arithmetic(other, "subtract", |a, b| a - b)
+    }
+
+    /// Multiplies two values as numbers, coercing numeric strings like `add`.
+    pub fn mul(&self, other: &VibeValue) -> Result<VibeValue> {
+        self.arithmetic(other, "multiply", |a, b| a * b)
+    }
+
+    /// Divides this value by `other` as numbers, coercing numeric strings like `add`.
+    /// Dividing by zero is an error rather than an infinite result.
+    pub fn div(&self, other: &VibeValue) -> Result<VibeValue> {
+        if other.as_number() == Some(0.0) {
+            return Err(anyhow!("Cannot divide {} by zero", self));
+        }
+        self.arithmetic(other, "divide", |a, b| a / b)
+    }
+
+    /// Orders two values as numbers, coercing numeric strings like `add`.
+    ///
+    /// Values that are not numbers, and NaN, cannot be ordered and are an error.
+    pub fn compare(&self, other: &VibeValue) -> Result<std::cmp::Ordering> {
+        let (a, b) = self.operands(other, "compare")?;
+        a.partial_cmp(&b)
+            .ok_or_else(|| anyhow!("Cannot compare {} and {}: NaN has no order", a, b))
+    }
+
+    // The number a value stands for in arithmetic: a number, or a string holding one.
+    fn as_number(&self) -> Option<f64> {
+        match self {
+            VibeValue::Number(n) => Some(*n),
+            VibeValue::String(s) => s.trim().parse().ok().or_else(|| Self::parse_number(s)),
+            _ => None,
+        }
+    }
+
+    // Both values as numbers, or an error naming the `action` that needed them.
+    fn operands(&self, other: &VibeValue, action: &str) -> Result<(f64, f64)> {
+        match (self.as_number(), other.as_number()) {
+            (Some(a), Some(b)) => Ok((a, b)),
+            _ => Err(anyhow!(
+                "Cannot {} {} `{}` and {} `{}`: both must be numbers or numeric strings",
+                action,
+                self.type_name(),
+                self,
+                other.type_name(),
+                other
+            )),
+        }
+    }
+
+    fn arithmetic(
+        &self,
+        other: &VibeValue,
+        action: &str,
+        op: fn(f64, f64) -> f64,
+    ) -> Result<VibeValue> {
+        let (a, b) = self.operands(other, action)?;
+        Ok(VibeValue::Number(op(a, b)))
+    }
+
     /// Converts VibeValue to a String.
     /// This conversion is always possible.
     pub fn into_string(self) -> String {
+        self.into_string_with(FloatFormat::default())
+    }
+
+    /// Converts VibeValue to a String, writing numbers with the given `FloatFormat`.
+    pub fn into_string_with(self, float_format: FloatFormat) -> String {
         match self {
             VibeValue::String(s) => s,
-            VibeValue::Number(n) => n.to_string(),
+            VibeValue::Number(n) => float_format.format(n),
             VibeValue::Boolean(b) => b.to_string(),
             VibeValue::Null => String::new(),
+            // Arrays and objects are written as JSON.
+            value @ (VibeValue::Array(_) | VibeValue::Object(_)) => value.as_json().to_string(),
+        }
+    }
+}
+
+impl From<i32> for VibeValue {
+    fn from(n: i32) -> Self {
+        VibeValue::Number(n as f64)
+    }
+}
+
+impl From<i64> for VibeValue {
+    fn from(n: i64) -> Self {
+        VibeValue::Number(n as f64)
+    }
+}
+
+impl From<u32> for VibeValue {
+    fn from(n: u32) -> Self {
+        VibeValue::Number(n as f64)
+    }
+}
+
+impl From<f64> for VibeValue {
+    fn from(n: f64) -> Self {
+        VibeValue::Number(n)
+    }
+}
+
+impl From<bool> for VibeValue {
+    fn from(b: bool) -> Self {
+        VibeValue::Boolean(b)
+    }
+}
+
+impl From<String> for VibeValue {
+    fn from(s: String) -> Self {
+        VibeValue::String(s)
+    }
+}
+
+impl From<&str> for VibeValue {
+    fn from(s: &str) -> Self {
+        VibeValue::String(s.to_string())
+    }
+}
+
+impl From<serde_json::Value> for VibeValue {
+    fn from(value: serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => VibeValue::Null,
+            serde_json::Value::Bool(b) => VibeValue::Boolean(b),
+            serde_json::Value::Number(n) => n.as_f64().map_or(VibeValue::Null, VibeValue::Number),
+            serde_json::Value::String(s) => VibeValue::String(s),
+            serde_json::Value::Array(items) => {
+                VibeValue::Array(items.into_iter().map(VibeValue::from).collect())
+            }
+            serde_json::Value::Object(fields) => VibeValue::Object(
+                fields
+                    .into_iter()
+                    .map(|(key, value)| (key, VibeValue::from(value)))
+                    .collect(),
+            ),
         }
     }
 }
 
+impl fmt::Display for VibeValue {
+    /// Writes the same text as `into_string`; a precision such as `{:.2}` formats numbers
+    /// with that many decimals.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let float_format = f
+            .precision()
+            .map_or(FloatFormat::Shortest, FloatFormat::Fixed);
+        f.write_str(&self.clone().into_string_with(float_format))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_vibe_value_arithmetic_coerces_numbers() {
+        let sum = VibeValue::Number(2.5).add(&VibeValue::Number(1.5)).unwrap();
+        assert_eq!(sum, VibeValue::Number(4.0));
+
+        let coerced = VibeValue::from("5").add(&VibeValue::Number(1.0)).unwrap();
+        assert_eq!(coerced, VibeValue::Number(6.0));
+        assert_eq!(
+            VibeValue::from("$1,200").mul(&VibeValue::from(2)).unwrap(),
+            VibeValue::Number(2400.0)
+        );
+        assert_eq!(
+            VibeValue::from("10")
+                .compare(&VibeValue::Number(9.5))
+                .unwrap(),
+            std::cmp::Ordering::Greater
+        );
+
+        let error = VibeValue::Boolean(true)
+            .add(&VibeValue::Number(1.0))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Cannot add boolean `true` and number `1`: both must be numbers or numeric strings"
+        );
+        assert!(VibeValue::from(4).div(&VibeValue::from("0")).is_err());
+    }
+
     // --- Tests for into_i32 ---
     #[test]
     fn test_vibe_value_into_i32_from_number() {
@@ -80,6 +594,37 @@ mod tests {
         assert_eq!(VibeValue::String("123".to_string()).into_i32(), 123);
     }
 
+    #[test]
+    fn test_vibe_value_into_wider_integers() {
+        assert_eq!(VibeValue::Number(8_100_000_000.0).into_i64(), 8_100_000_000);
+        assert_eq!(
+            VibeValue::String("8,100,000,000".to_string()).into_i64(),
+            8_100_000_000
+        );
+        assert_eq!(
+            VibeValue::String("4000000000".to_string()).into_u32(),
+            4_000_000_000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to convert LLM response '-5' to u32")]
+    fn test_vibe_value_into_u32_panics_on_negative_string() {
+        VibeValue::String("-5".to_string()).into_u32();
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to convert LLM response '-5' to u32: not a whole number")]
+    fn test_vibe_value_into_u32_panics_on_negative_number() {
+        VibeValue::Number(-5.0).into_u32();
+    }
+
+    #[test]
+    #[should_panic(expected = "Failed to convert LLM response '2.5' to i64: not a whole number")]
+    fn test_vibe_value_into_i64_panics_on_fraction() {
+        VibeValue::Number(2.5).into_i64();
+    }
+
     #[test]
     #[should_panic(expected = "Failed to convert LLM response 'abc' to i32")]
     fn test_vibe_value_into_i32_panics_on_invalid_string() {
@@ -95,16 +640,16 @@ mod tests {
     // --- Tests for into_f64 ---
     #[test]
     fn test_vibe_value_into_f64() {
-        assert_eq!(VibeValue::Number(3.14).into_f64(), 3.14);
+        assert_eq!(VibeValue::Number(2.5).into_f64(), 2.5);
         assert_eq!(VibeValue::String("99.9".to_string()).into_f64(), 99.9);
     }
 
     // --- Tests for into_bool ---
     #[test]
     fn test_vibe_value_into_bool() {
-        assert_eq!(VibeValue::Boolean(true).into_bool(), true);
-        assert_eq!(VibeValue::String("true".to_string()).into_bool(), true);
-        assert_eq!(VibeValue::String("FALSE".to_string()).into_bool(), false);
+        assert!(VibeValue::Boolean(true).into_bool());
+        assert!(VibeValue::String("true".to_string()).into_bool());
+        assert!(!VibeValue::String("FALSE".to_string()).into_bool());
     }
 
     #[test]
@@ -124,4 +669,252 @@ mod tests {
         assert_eq!(VibeValue::Boolean(true).into_string(), "true");
         assert_eq!(VibeValue::Null.into_string(), "");
     }
+
+    // --- Tests for percentages and currency ---
+    #[test]
+    fn test_percentages_and_currency_parse_to_numbers() {
+        let text = |s: &str| VibeValue::String(s.to_string());
+
+        assert_eq!(text("45%").into_f64(), 45.0);
+        assert_eq!(text("$1,200").into_f64(), 1200.0);
+        assert_eq!(text("-2.5%").coerce("f64").unwrap().into_f64(), -2.5);
+        assert_eq!(text("$1,200").into_i32(), 1200);
+        assert_eq!(text("€4.99").coerce("f64").unwrap().into_f64(), 4.99);
+        assert!(text("$4.99").coerce("i32").is_err());
+    }
+
+    #[test]
+    fn test_number_style_from_meaning_keywords() {
+        assert_eq!(
+            NumberStyle::for_meaning("unemployment percentage"),
+            NumberStyle::Percentage
+        );
+        assert_eq!(
+            NumberStyle::for_meaning("ticket price in USD"),
+            NumberStyle::Currency
+        );
+        assert_eq!(NumberStyle::for_meaning("age in years"), NumberStyle::Plain);
+        assert_eq!(
+            NumberStyle::Percentage.find_number("Roughly 45% of voters"),
+            Some(45.0)
+        );
+        assert_eq!(
+            NumberStyle::Currency.find_number("It costs USD $1,200."),
+            Some(1200.0)
+        );
+        assert_eq!(NumberStyle::Plain.find_number("It costs $1,200."), None);
+    }
+
+    #[test]
+    fn test_number_styles_only_accept_whole_words() {
+        assert_eq!(NumberStyle::Percentage.parse(".5%"), Some(0.5));
+        assert_eq!(NumberStyle::Percentage.parse("(-2.5%)"), Some(-2.5));
+        assert_eq!(NumberStyle::Percentage.parse("45%."), Some(45.0));
+        assert_eq!(NumberStyle::Percentage.parse("abc45"), None);
+        assert_eq!(NumberStyle::Percentage.parse("45%abc"), None);
+        assert_eq!(NumberStyle::Percentage.parse("1-2%"), None);
+
+        assert_eq!(NumberStyle::Currency.parse("$.5"), Some(0.5));
+        assert_eq!(NumberStyle::Currency.parse("-$4.99"), Some(-4.99));
+        assert_eq!(NumberStyle::Currency.parse("1,200€"), Some(1200.0));
+        assert_eq!(NumberStyle::Currency.parse("$1,20"), None);
+        assert_eq!(NumberStyle::Currency.parse("US$5"), None);
+        assert_eq!(NumberStyle::Currency.parse("-$-5"), None);
+    }
+
+    // --- Tests for float formatting ---
+    #[test]
+    fn test_float_format_pins_representative_values() {
+        let shortest = FloatFormat::Shortest;
+        assert_eq!(shortest.format(45.6), "45.6");
+        assert_eq!(shortest.format(7.0), "7");
+        assert_eq!(shortest.format(0.1 + 0.2), "0.30000000000000004");
+        assert_eq!(shortest.format(1e21), "1000000000000000000000");
+        assert_eq!(shortest.format_literal(7.0), "7.0");
+
+        let fixed = FloatFormat::Fixed(2);
+        assert_eq!(fixed.format(45.6), "45.60");
+        assert_eq!(fixed.format(-0.125), "-0.12");
+        assert_eq!(fixed.format(1e21), "1000000000000000000000.00");
+        assert_eq!(FloatFormat::Fixed(0).format_literal(45.6), "46.0");
+    }
+
+    #[test]
+    fn test_vibe_value_display_matches_into_string() {
+        assert_eq!(VibeValue::Number(45.6).to_string(), "45.6");
+        assert_eq!(format!("{:.2}", VibeValue::Number(45.6)), "45.60");
+        assert_eq!(VibeValue::Boolean(false).to_string(), "false");
+        assert_eq!(
+            VibeValue::Number(45.6).into_string_with(FloatFormat::Fixed(3)),
+            "45.600"
+        );
+    }
+
+    // --- Tests for coerce ---
+    #[test]
+    fn test_vibe_value_coerce_string_to_each_target() {
+        let text = |s: &str| VibeValue::String(s.to_string());
+
+        assert_eq!(text(" 42 ").coerce("i32").unwrap().into_i32(), 42);
+        assert_eq!(text("2.5").coerce("f64").unwrap().into_f64(), 2.5);
+        assert!(text("True").coerce("bool").unwrap().into_bool());
+        assert!(!text("false").coerce("bool").unwrap().into_bool());
+        assert_eq!(
+            text("hello").coerce("String").unwrap().into_string(),
+            "hello"
+        );
+        assert_eq!(
+            text("$3,000,000,000").coerce("i64").unwrap().into_i64(),
+            3_000_000_000
+        );
+        assert_eq!(
+            text("4000000000").coerce("u32").unwrap().into_u32(),
+            4_000_000_000
+        );
+    }
+
+    #[test]
+    fn test_vibe_value_coerce_other_variants() {
+        assert_eq!(
+            VibeValue::Number(42.0).coerce("i32").unwrap().into_f64(),
+            42.0
+        );
+        assert_eq!(
+            VibeValue::Number(7.0)
+                .coerce("String")
+                .unwrap()
+                .into_string(),
+            "7"
+        );
+        assert_eq!(VibeValue::Null.coerce("String").unwrap().into_string(), "");
+    }
+
+    #[test]
+    fn test_vibe_value_coerce_errors() {
+        let text = |s: &str| VibeValue::String(s.to_string());
+
+        let error = text("abc").coerce("i32").unwrap_err().to_string();
+        assert!(error.contains("Failed to convert LLM response 'abc' to i32"));
+        assert!(text("2.5").coerce("i32").is_err());
+        assert!(text("warm").coerce("f64").is_err());
+        assert!(text("maybe").coerce("bool").is_err());
+        assert!(VibeValue::Boolean(true).coerce("f64").is_err());
+        assert!(VibeValue::Null.coerce("i32").is_err());
+        assert!(VibeValue::Number(42.9).coerce("i32").is_err());
+        let error = VibeValue::Number(2.5)
+            .coerce("i64")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("Failed to convert LLM response '2.5' to i64"));
+        assert!(VibeValue::Number(-1.0).coerce("u32").is_err());
+        assert!(text("5000000000").coerce("u32").is_err());
+        let error = text("1").coerce("Date").unwrap_err().to_string();
+        assert!(error.contains("Unknown conversion target type `Date`"));
+    }
+
+    #[test]
+    fn test_vibe_value_from_rust_values() {
+        assert!(matches!(VibeValue::from(42), VibeValue::Number(n) if n == 42.0));
+        assert!(matches!(VibeValue::from(2.5), VibeValue::Number(n) if n == 2.5));
+        assert!(matches!(VibeValue::from(true), VibeValue::Boolean(true)));
+        assert!(
+            matches!(VibeValue::from("Paris".to_string()), VibeValue::String(s) if s == "Paris")
+        );
+        assert!(matches!(VibeValue::from("Rome"), VibeValue::String(s) if s == "Rome"));
+    }
+
+    #[test]
+    fn test_vibe_value_type_name_and_predicates() {
+        let values = [
+            VibeValue::Null,
+            VibeValue::Boolean(true),
+            VibeValue::Number(1.5),
+            VibeValue::String("Oslo".to_string()),
+        ];
+        let names: Vec<&str> = values.iter().map(VibeValue::type_name).collect();
+        assert_eq!(names, ["null", "boolean", "number", "string"]);
+
+        for (index, value) in values.iter().enumerate() {
+            let predicates = [
+                value.is_null(),
+                value.is_boolean(),
+                value.is_number(),
+                value.is_string(),
+            ];
+            // Each value satisfies exactly the predicate for its own variant.
+            for (position, holds) in predicates.into_iter().enumerate() {
+                assert_eq!(holds, position == index, "{:?}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_vibe_value_parse_tuple() {
+        let values = VibeValue::parse_tuple(
+            "Sure! [\"Paris\", \"2100000\", true]",
+            &["String", "i32", "bool"],
+        )
+        .unwrap();
+        assert!(matches!(&values[0], VibeValue::String(s) if s == "Paris"));
+        assert!(matches!(values[1], VibeValue::Number(n) if n == 2_100_000.0));
+        assert!(matches!(values[2], VibeValue::Boolean(true)));
+
+        let error = VibeValue::parse_tuple("[1]", &["i32", "String"])
+            .unwrap_err()
+            .to_string();
+        assert_eq!(error, "Expected 2 values in LLM response '[1]', found 1");
+        assert!(VibeValue::parse_tuple("Paris, 3", &["String", "i32"]).is_err());
+    }
+
+    #[test]
+    fn test_vibe_value_from_json_str() {
+        assert!(matches!(
+            VibeValue::from_json_str("null").unwrap(),
+            VibeValue::Null
+        ));
+        assert!(matches!(
+            VibeValue::from_json_str("false").unwrap(),
+            VibeValue::Boolean(false)
+        ));
+        assert!(
+            matches!(VibeValue::from_json_str(" 1.5 ").unwrap(), VibeValue::Number(n) if n == 1.5)
+        );
+        assert!(matches!(
+            VibeValue::from_json_str("\"Oslo\"").unwrap(),
+            VibeValue::String(s) if s == "Oslo"
+        ));
+
+        let error = VibeValue::from_json_str("[1, 2]").unwrap_err().to_string();
+        assert!(
+            error.contains("only null, booleans, numbers and strings"),
+            "{}",
+            error
+        );
+        assert!(VibeValue::from_json_str("not json").is_err());
+    }
+
+    #[test]
+    fn test_vibe_value_as_json_round_trips_nested_objects() {
+        let value = VibeValue::Object(BTreeMap::from([
+            ("city".to_string(), VibeValue::from("Oslo")),
+            (
+                "forecast".to_string(),
+                VibeValue::Array(vec![
+                    VibeValue::Object(BTreeMap::from([
+                        ("day".to_string(), VibeValue::from("Monday")),
+                        ("celsius".to_string(), VibeValue::from(-3.5)),
+                        ("sunny".to_string(), VibeValue::from(false)),
+                    ])),
+                    VibeValue::Null,
+                ]),
+            ),
+        ]));
+
+        let json = value.as_json();
+
+        assert_eq!(json["forecast"][0]["celsius"], serde_json::json!(-3.5));
+        assert_eq!(VibeValue::from(json.clone()), value);
+        assert_eq!(value.to_string(), json.to_string());
+        assert_eq!(value.type_name(), "object");
+    }
 }
@@ -0,0 +1,88 @@
+use crate::runtime::types::VibeValue;
+use anyhow::{Result, anyhow};
+use std::fmt;
+
+/// Bounds declared on a numeric meaning with `range:`, `min:` or `max:`, e.g.
+/// `Meaning<Int>("age in years", range: 0..150)`. Both ends are inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Bounds {
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl Bounds {
+    /// Returns an error unless `value` lies within the bounds.
+    pub fn check(&self, value: f64) -> Result<()> {
+        let too_low = self.min.is_some_and(|min| value < min);
+        let too_high = self.max.is_some_and(|max| value > max);
+        if too_low || too_high || value.is_nan() {
+            return Err(anyhow!("{} is out of range: expected {}", value, self));
+        }
+        Ok(())
+    }
+
+    /// Like `check`, for a value produced by the generated code.
+    ///
+    /// Numbers and numeric strings are checked; anything else, such as the `Null` returned
+    /// when the LLM call failed, is an error.
+    pub fn check_value(&self, value: &VibeValue) -> Result<()> {
+        match value {
+            VibeValue::Number(n) => self.check(*n),
+            VibeValue::String(s) => match VibeValue::parse_number(s) {
+                Some(n) => self.check(n),
+                None => Err(anyhow!("'{}' is not a number: expected {}", s, self)),
+            },
+            other => Err(anyhow!("{:?} is not a number: expected {}", other, self)),
+        }
+    }
+}
+
+impl fmt::Display for Bounds {
+    /// Describes the bounds for error messages and prompts, e.g. "between 0 and 150".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => write!(f, "a number between {} and {}", min, max),
+            (Some(min), None) => write!(f, "a number of at least {}", min),
+            (None, Some(max)) => write!(f, "a number of at most {}", max),
+            (None, None) => write!(f, "a number"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::llm_provider::{LlmProvider, MockLlmProvider};
+
+    #[test]
+    fn test_bounds_reject_out_of_range_responses() {
+        let mut llm = MockLlmProvider::new();
+        llm.expect_generate().returning(|_| Ok("200".to_string()));
+        let bounds = Bounds {
+            min: Some(0.0),
+            max: Some(150.0),
+        };
+
+        let response = VibeValue::String(llm.generate("How old is Rome?").unwrap());
+        let error = bounds.check_value(&response).unwrap_err().to_string();
+
+        assert_eq!(
+            error,
+            "200 is out of range: expected a number between 0 and 150"
+        );
+        assert!(bounds.check_value(&VibeValue::Number(150.0)).is_ok());
+        assert!(bounds.check_value(&VibeValue::Null).is_err());
+    }
+
+    #[test]
+    fn test_one_sided_bounds() {
+        let at_least_zero = Bounds {
+            min: Some(0.0),
+            max: None,
+        };
+
+        assert!(at_least_zero.check(1e9).is_ok());
+        assert!(at_least_zero.check(-0.5).is_err());
+        assert_eq!(at_least_zero.to_string(), "a number of at least 0");
+    }
+}
@@ -0,0 +1,29 @@
+use crate::compiler;
+use anyhow::{Result, anyhow};
+
+/// Compiles VibeLang source code and checks that the generated Rust code is valid.
+///
+/// Everything runs in-process: the source is parsed, both the binary and the library
+/// outputs are generated, and each one is parsed back with `syn`. No Cargo project is
+/// scaffolded or built, which keeps this fast enough for unit tests.
+///
+/// # Arguments
+/// * `source` - A string slice containing the VibeLang source code.
+///
+/// # Returns
+/// The generated binary-crate code, or an error if compilation fails or the generated
+/// code is not syntactically valid Rust.
+pub fn compile_and_check(source: &str) -> Result<String> {
+    let library_code = compiler::compile(source, true)?;
+    check_rust_syntax(&library_code)?;
+
+    let binary_code = compiler::compile(source, false)?;
+    check_rust_syntax(&binary_code)?;
+
+    Ok(binary_code)
+}
+
+/// Parses Rust code with `syn`, returning the syntax tree or a descriptive error.
+pub fn check_rust_syntax(code: &str) -> Result<syn::File> {
+    syn::parse_file(code).map_err(|e| anyhow!("Generated code is not valid Rust: {}", e))
+}
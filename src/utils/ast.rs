@@ -12,10 +12,13 @@ pub enum AstNodeType {
     ClassBody,
     MemberVar,
     Import,
+    ConstDecl,
 
     // Type system
     BasicType,
     MeaningType,
+    UnionType,
+    TupleType,
 
     // Parameters and arguments
     ParamList,
@@ -26,10 +29,12 @@ pub enum AstNodeType {
     ExprStmt,
     ReturnStmt,
     PromptBlock,
+    TempStmt,
 
     // Expressions
     CallExpr,
     Identifier,
+    Comparison,
 
     // Literals
     StringLiteral,
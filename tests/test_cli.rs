@@ -0,0 +1,256 @@
+#![cfg(feature = "runtime")]
+
+use anyhow::Result;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_source_is_read_from_stdin() -> Result<()> {
+    let vibe_source = r#"
+        type Temperature = Meaning<Int>("temperature in Celsius");
+
+        fn get_temperature(city: String) -> Temperature {
+            prompt "What is the temperature in {city}?";
+        }
+    "#;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .args(["--explain", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(vibe_source.as_bytes())?;
+    let output = child.wait_with_output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("Temperature -> i32"), "{}", stdout);
+    Ok(())
+}
+
+#[test]
+fn test_quiet_run_prints_nothing_on_success() -> Result<()> {
+    // Without meanings the project is named without asking the LLM, so this runs offline.
+    let temp_dir = tempfile::tempdir()?;
+    let source_path = temp_dir.path().join("year.vibe");
+    std::fs::write(
+        &source_path,
+        r#"fn get_year() -> Int { prompt "What year is it?"; }"#,
+    )?;
+    let output_dir = temp_dir.path().join("generated");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg(&source_path)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .args(["--as-lib", "--quiet"])
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(String::from_utf8(output.stdout)?, "");
+    assert!(output_dir.join("src/lib.rs").exists());
+    Ok(())
+}
+
+#[test]
+fn test_print_config_shows_model_and_redacts_api_key() -> Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg("--print-config")
+        .env("OLLAMA_MODEL", "mistral:7b")
+        .env("VIBELANG_API_KEY", "sk-test-secret")
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.contains("model:     mistral:7b"), "{}", stdout);
+    assert!(stdout.contains("API key:   <redacted>"), "{}", stdout);
+    assert!(!stdout.contains("sk-test-secret"), "{}", stdout);
+    Ok(())
+}
+
+#[test]
+fn test_lint_fails_on_unknown_placeholder() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let source_path = dir.path().join("greet.vibe");
+    std::fs::write(
+        &source_path,
+        "fn greet(name: String) -> String {\n    prompt \"Say hello to {nmae} politely.\";\n}\n",
+    )?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg("--lint")
+        .arg(&source_path)
+        .output()?;
+
+    assert!(!output.status.success());
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(
+        stdout.contains(
+            "error at line 2: The prompt of `greet` uses `{nmae}`, which is not a parameter or a `let` binding declared before it"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(String::from_utf8(output.stderr)?.contains("Lint found 1 error(s)"));
+    Ok(())
+}
+
+#[test]
+fn test_example_prompts_are_written_per_function() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let source_path = temp_dir.path().join("trivia.vibe");
+    std::fs::write(
+        &source_path,
+        r#"
+        fn capital_of(country: String) -> String {
+            prompt "What is the capital of {country}?";
+        }
+
+        fn add(a: Int, b: Int) -> Int {
+            let unit = "apples";
+            prompt "How much is {a} {unit} plus {b} {unit}?";
+        }
+        "#,
+    )?;
+    let output_dir = temp_dir.path().join("generated");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg(&source_path)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .args(["--as-lib", "--quiet", "--example-prompts"])
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(output_dir.join("examples/capital_of.txt"))?,
+        "What is the capital of Test Topic?\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(output_dir.join("examples/add.txt"))?,
+        "How much is 123 apples plus 123 apples?\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_explain_prompt_prints_the_substituted_prompt() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let source_path = temp_dir.path().join("weather.vibe");
+    std::fs::write(
+        &source_path,
+        r#"fn get_weather(city: String) -> String { prompt "What is the weather in {city}?"; }"#,
+    )?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg(&source_path)
+        .args(["--explain-prompt", "get_weather", "--arg", "city=Paris"])
+        .env_remove("VIBELANG_PROMPT_PREFIX")
+        .env_remove("VIBELANG_PROMPT_SUFFIX")
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        "What is the weather in Paris?\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dry_run_generates_the_project_without_building_it() -> Result<()> {
+    // Without meanings the project is named without asking the LLM, so this runs offline.
+    let temp_dir = tempfile::tempdir()?;
+    let source_path = temp_dir.path().join("year.vibe");
+    std::fs::write(
+        &source_path,
+        r#"fn get_year() -> Int { prompt "What year is it?"; }"#,
+    )?;
+    let output_dir = temp_dir.path().join("generated");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg(&source_path)
+        .arg("--output-dir")
+        .arg(&output_dir)
+        .args(["--dry-run", "--quiet"])
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout)?,
+        format!("{}\n", output_dir.display())
+    );
+    assert!(output_dir.join("src/main.rs").exists());
+    assert!(output_dir.join("Cargo.toml").exists());
+    assert!(!output_dir.join("target").exists());
+    Ok(())
+}
+
+#[test]
+fn test_generate_only_writes_the_module_into_an_existing_crate() -> Result<()> {
+    let temp_dir = tempfile::tempdir()?;
+    let source_path = temp_dir.path().join("year.vibe");
+    std::fs::write(
+        &source_path,
+        r#"fn get_year() -> Int { prompt "What year is it?"; }"#,
+    )?;
+    let crate_dir = temp_dir.path().join("app");
+    std::fs::create_dir_all(&crate_dir)?;
+    let manifest = "[package]\nname = \"app\"\nversion = \"0.1.0\"\nedition = \"2024\"\n";
+    std::fs::write(crate_dir.join("Cargo.toml"), manifest)?;
+    let module_path = crate_dir.join("src/vibe.rs");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_vibelang"))
+        .arg(&source_path)
+        .arg("--generate-only")
+        .arg(&module_path)
+        .arg("--quiet")
+        .output()?;
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let module = std::fs::read_to_string(&module_path)?;
+    assert!(module.contains("pub fn get_year(llm: &LlmClient) -> i32 {"));
+    assert!(!module.contains("fn main()"));
+    assert_eq!(
+        std::fs::read_to_string(crate_dir.join("Cargo.toml"))?,
+        manifest
+    );
+    let stdout = String::from_utf8(output.stdout)?;
+    assert!(stdout.starts_with("vibelang = \""), "{}", stdout);
+    assert!(stdout.contains("\nserde_json = \"1.0\""), "{}", stdout);
+    assert!(!stdout.contains("tokio"), "{}", stdout);
+    Ok(())
+}
@@ -0,0 +1,46 @@
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use vibelang::compiler::{codegen::CodeGenerator, parser::parse_source};
+
+/// Compares the generated `main.rs` for `source_path` with the committed snapshot.
+///
+/// Set `VIBELANG_UPDATE_SNAPSHOTS=1` to rewrite the snapshots after an intended template change.
+fn assert_matches_snapshot(source_path: &str, snapshot_name: &str) -> Result<()> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let source = fs::read_to_string(root.join(source_path))?;
+    let snapshot_path = root
+        .join("tests/fixtures")
+        .join(format!("{}.main.rs.expected", snapshot_name));
+
+    let generated_code = CodeGenerator::new().generate(&parse_source(&source)?, false)?;
+
+    if std::env::var_os("VIBELANG_UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&snapshot_path, &generated_code)?;
+        return Ok(());
+    }
+    let expected = fs::read_to_string(&snapshot_path)?;
+    assert_eq!(
+        generated_code,
+        expected,
+        "Generated code for {} differs from {}",
+        source_path,
+        snapshot_path.display()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_weather_program_matches_snapshot() -> Result<()> {
+    assert_matches_snapshot("tests/fixtures/weather.vibe", "weather")
+}
+
+#[test]
+fn test_joke_program_matches_snapshot() -> Result<()> {
+    assert_matches_snapshot("tests/fixtures/joke.vibe", "joke")
+}
+
+#[test]
+fn test_knowledge_program_matches_snapshot() -> Result<()> {
+    assert_matches_snapshot("examples/knowledge_retrieval.vibe", "knowledge_retrieval")
+}
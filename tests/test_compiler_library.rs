@@ -0,0 +1,35 @@
+// These tests need no Cargo features, so they also run with `cargo test --no-default-features`,
+// where vibelang is a compiler library without the HTTP runtime.
+
+use anyhow::Result;
+use vibelang::compiler::{compile, parser::parse_source};
+use vibelang::utils::ast::AstNodeType;
+
+const SOURCE: &str = r#"
+    type Temperature = Meaning<Int>("temperature in Celsius");
+
+    fn get_temperature(city: String) -> Temperature {
+        prompt "What is the temperature in {city}?";
+    }
+"#;
+
+#[test]
+fn test_parse_source_without_runtime() -> Result<()> {
+    let ast = parse_source(SOURCE)?;
+
+    let node_types: Vec<&AstNodeType> = ast.children.iter().map(|node| &node.node_type).collect();
+    assert_eq!(
+        node_types,
+        [&AstNodeType::TypeDecl, &AstNodeType::FunctionDecl]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compile_without_runtime() -> Result<()> {
+    let code = compile(SOURCE, true)?;
+
+    assert!(code.contains("pub type Temperature = i32;"), "{}", code);
+    assert!(code.contains("pub fn get_temperature(llm: &LlmClient, city: String) -> Temperature"));
+    Ok(())
+}
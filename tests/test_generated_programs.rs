@@ -0,0 +1,579 @@
+#![cfg(feature = "runtime")]
+// These tests build generated projects against this checkout of vibelang and run them
+// offline, with a `MockLlmProvider` answering in place of a model. The first test to run
+// compiles vibelang for the generated projects, which share a target directory afterwards.
+
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use vibelang::compiler::codegen::{CodeGenerator, FunctionSettings};
+use vibelang::compiler::parser::parse_source;
+use vibelang::compiler::project_builder::{ProjectBuilder, USER_REGION_BEGIN, USER_REGION_END};
+use vibelang::runtime::llm_provider::MockLlmProvider;
+
+// Writes the project `ProjectBuilder` builds for `code`, depending on this checkout.
+fn write_project(source: &str, code: &str, as_lib: bool, async_runtime: bool) -> Result<TempDir> {
+    let project = tempfile::tempdir()?;
+    build_project(project.path(), source, code, as_lib, async_runtime)?;
+    Ok(project)
+}
+
+// Builds the project for `code` in `project`, which may hold an earlier build.
+fn build_project(
+    project: &Path,
+    source: &str,
+    code: &str,
+    as_lib: bool,
+    async_runtime: bool,
+) -> Result<()> {
+    ProjectBuilder::new(&MockLlmProvider::new())
+        .with_async_runtime(async_runtime)
+        .build(project, source, code, as_lib)?;
+
+    let manifest_path = project.join("Cargo.toml");
+    let manifest: Vec<String> = std::fs::read_to_string(&manifest_path)?
+        .lines()
+        .map(|line| {
+            if line.starts_with("vibelang = ") {
+                format!("vibelang = {{ path = {:?} }}", env!("CARGO_MANIFEST_DIR"))
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    // Its own workspace, so that Cargo does not look for one in the directories above.
+    std::fs::write(
+        &manifest_path,
+        format!("{}\n\n[workspace]\n", manifest.join("\n")),
+    )?;
+    // The versions this checkout is tested with, so that the build needs no network.
+    std::fs::copy(
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.lock"),
+        project.join("Cargo.lock"),
+    )?;
+    Ok(())
+}
+
+// Runs `cargo` with `args` in the project, returning its output if it succeeds.
+fn cargo(project: &Path, args: &[&str]) -> Result<String> {
+    cargo_with_env(project, args, &[])
+}
+
+// Like `cargo`, with the environment variables `envs` set for it and the program it runs.
+fn cargo_with_env(project: &Path, args: &[&str], envs: &[(&str, &str)]) -> Result<String> {
+    let target_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("generated-programs");
+    let output = Command::new(env!("CARGO"))
+        .args(args)
+        .arg("--offline")
+        .current_dir(project)
+        .env("CARGO_TARGET_DIR", target_dir)
+        .envs(envs.iter().copied())
+        .output()?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    if !output.status.success() {
+        return Err(anyhow!("`cargo {}` failed:\n{}", args.join(" "), text));
+    }
+    Ok(text)
+}
+
+// Generates a library from `source`, adds `tests` to it as a test module that can use the
+// generated functions and `MockLlmProvider`, and runs them.
+fn test_library(source: &str, generator: CodeGenerator, tests: &str) -> Result<()> {
+    let ast = parse_source(source)?;
+    let code = generator.generate(&ast, true)?;
+    let project = write_project(source, &code, true, generator.has_async_functions(&ast))?;
+
+    let lib_path = project.path().join("src/lib.rs");
+    let code = std::fs::read_to_string(&lib_path)?;
+    std::fs::write(
+        &lib_path,
+        format!(
+            "{}\n#[cfg(test)]\nmod mock_tests {{\n    use super::*;\n    use vibelang::runtime::llm_provider::MockLlmProvider;\n{}\n}}\n",
+            code, tests
+        ),
+    )?;
+
+    let output = cargo(project.path(), &["test", "--lib", "mock_tests"])?;
+    if output.contains("running 0 tests") {
+        return Err(anyhow!("No test was run:\n{}", output));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_raw_companion_returns_the_unparsed_response() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        type Year = Meaning<Int>("a calendar year");
+
+        fn landing_year(mission: String) -> Year {
+            prompt "In which year did {mission} land on the Moon?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn raw_and_typed() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().returning(|_| Ok("In 1969".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(landing_year_raw(&llm, "Apollo 11".to_string()), "In 1969");
+        assert_eq!(landing_year(&llm, "Apollo 11".to_string()), 1969);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new().with_raw_responses(true), tests)
+}
+
+#[test]
+fn test_responses_are_coerced_to_the_return_type() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn world_population(year: Int) -> Long {
+            prompt "How many people lived on Earth in {year}?";
+        }
+
+        fn is_capital(city: String) -> Bool {
+            prompt "Is {city} a capital city?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn coerced() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.contains("How many people"))
+            .returning(|_| Ok("8000000000".to_string()));
+        mock.expect_generate().returning(|_| Ok("true".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(world_population(&llm, 2023), 8_000_000_000);
+        assert!(is_capital(&llm, "Paris".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot convert Null to bool")]
+    fn not_coerced() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().returning(|_| Err(anyhow::anyhow!("offline")));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        is_capital(&llm, "Lyon".to_string());
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_percentage_and_currency_extractors_agree_with_coerce() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        type Share = Meaning<Float>("market share percentage");
+        type Price = Meaning<Int>("ticket price in dollars");
+
+        fn share(company: String) -> Share {
+            prompt "What is the market share of {company}?";
+        }
+
+        fn price(show: String) -> Price {
+            prompt "How much is a ticket for {show}?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn extracted() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.contains("market share"))
+            .returning(|_| Ok("About .5% of the market".to_string()));
+        mock.expect_generate()
+            .returning(|_| Ok("It costs $4.99 online, or $5 at the door.".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(share(&llm, "Acme".to_string()), 0.5);
+        assert_eq!(price(&llm, "Hamlet".to_string()), 5);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_wide_integer_extractors_skip_numbers_the_type_cannot_hold() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        type Population = Meaning<Long>("number of inhabitants");
+        type Floor = Meaning<UInt>("floor number");
+
+        fn population(country: String) -> Population {
+            prompt "How many people live in {country}?";
+        }
+
+        fn floor(building: String) -> Floor {
+            prompt "On which floor is the restaurant of {building}?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn extracted() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.contains("How many people"))
+            .returning(|_| Ok("Growing 0.5 a year, about 8100000000 now".to_string()));
+        mock.expect_generate()
+            .returning(|_| Ok("Not -1 but 42 .".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(population(&llm, "Earth".to_string()), 8_100_000_000);
+        assert_eq!(floor(&llm, "the tower".to_string()), 42);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_wide_integers_can_be_bound_and_returned_in_tuples() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn population_and_rank(country: String) -> (Long, UInt) {
+            let year: Long = 2024;
+            prompt "How many people lived in {country} in {year}, and what was its rank?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn returned() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| {
+                prompt.contains("in 2024,")
+                    && prompt.contains("an integer, then a whole number of 0 or more.")
+            })
+            .returning(|_| Ok("[8100000000, 3]".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(population_and_rank(&llm, "Earth".to_string()), (8_100_000_000, 3));
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_out_of_range_response_is_asked_for_again_then_rejected() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        type Age = Meaning<Int>("age in years", range: 0..150);
+
+        fn age_of(person: String) -> Age {
+            prompt "How old is {person}?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn asked_again() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.ends_with("Reply with a number between 0 and 150."))
+            .times(1)
+            .returning(|_| Ok("42".to_string()));
+        mock.expect_generate().times(1).returning(|_| Ok("200".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(age_of(&llm, "Ada".to_string()), 42);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid LLM response for `age_of`: 200 is out of range")]
+    fn rejected() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().times(2).returning(|_| Ok("200".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        age_of(&llm, "Ada".to_string());
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_where_guards_reject_calls_breaking_them() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn advise(age: Int where age >= 0 && age < 150) -> String {
+            prompt "Give advice to someone aged {age}.";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn kept() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().returning(|_| Ok("Rest well.".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(advise(&llm, 149), "Rest well.");
+    }
+
+    #[test]
+    #[should_panic(expected = "`advise` requires `age >= 0 && age < 150`, got age = 150")]
+    fn broken() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().never();
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        advise(&llm, 150);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_demo_main_calls_guarded_functions_with_values_passing_their_checks() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn rate(ratio: Float where ratio < 0.5) -> String {
+            prompt "Describe a ratio of {ratio}.";
+        }
+
+        fn advise(age: Int where age >= 0 && age < 100) -> String {
+            prompt "Give advice to someone aged {age}.";
+        }
+    "#;
+    let code = CodeGenerator::new().generate(&parse_source(source)?, false)?;
+    let project = write_project(source, &code, false, false)?;
+
+    // --- Act & Assert ---
+    // Run with the offline echo provider: a demo call panics if its values break a check.
+    cargo_with_env(project.path(), &["run"], &[("VIBELANG_PROVIDER", "echo")])?;
+    Ok(())
+}
+
+#[test]
+fn test_retries_annotation_sends_a_failed_request_again() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        @retries(2)
+        fn summarize(text: String) -> String {
+            prompt "Summarize {text}.";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn sent_again() {
+        let failures = std::sync::atomic::AtomicUsize::new(0);
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().times(3).returning(move |_| {
+            if failures.fetch_add(1, std::sync::atomic::Ordering::SeqCst) < 2 {
+                Err(anyhow::anyhow!("busy"))
+            } else {
+                Ok("Short.".to_string())
+            }
+        });
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(summarize(&llm, "a long text".to_string()), "Short.");
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_answer_without_a_number_is_asked_for_again() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn count_moons(planet: String) -> Int {
+            prompt "How many moons does {planet} have?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn asked_again() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.ends_with("Reply with ONLY a number."))
+            .times(1)
+            .returning(|_| Ok("7".to_string()));
+        mock.expect_generate().times(1).returning(|_| Ok("I don't know".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(count_moons(&llm, "Uranus".to_string()), 7);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_with_confidence_splits_the_score_from_the_answer() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        @with_confidence
+        fn guess_year(event: String) -> Int {
+            prompt "In which year did {event} happen?";
+        }
+    "#;
+    let tests = r#"
+    #[test]
+    fn confident() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate()
+            .withf(|prompt| prompt.contains("write Confidence:"))
+            .returning(|_| Ok("42\nConfidence: 0.85".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        assert_eq!(
+            guess_year(&llm, "the answer".to_string()),
+            vibelang::runtime::confidence::Confident { value: 42, confidence: Some(0.85) }
+        );
+    }
+
+    #[test]
+    fn without_a_score() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().returning(|_| Ok("1969".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        let guess = guess_year(&llm, "the Moon landing".to_string());
+
+        assert_eq!((guess.value, guess.confidence), (1969, None));
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, CodeGenerator::new(), tests)
+}
+
+#[test]
+fn test_program_of_two_modules_compiles() -> Result<()> {
+    // --- Arrange ---
+    let geography = r#"
+        type City = Meaning<String>("the name of a city");
+
+        fn get_capital(country: String, language: String) -> City {
+            prompt "What is the capital of {country}, as written in {language}?";
+        }
+    "#;
+    let travel = r#"
+        fn describe(city: City, days: Int) -> String {
+            prompt "Plan {days} days in {city}.";
+        }
+    "#;
+    let modules = vec![
+        ("geography".to_string(), parse_source(geography)?),
+        ("travel".to_string(), parse_source(travel)?),
+    ];
+    let code = CodeGenerator::new().generate_modules(&modules, false)?;
+
+    // --- Act ---
+    let project = write_project(&format!("{}\n{}", geography, travel), &code, false, false)?;
+
+    // --- Assert ---
+    cargo(project.path(), &["build"])?;
+    Ok(())
+}
+
+#[test]
+fn test_edited_main_is_kept_when_the_program_is_generated_again() -> Result<()> {
+    // --- Arrange ---
+    let first = r#"
+        fn get_capital(country: String) -> String {
+            prompt "What is the capital of {country}?";
+        }
+    "#;
+    let second = r#"
+        fn get_capital(country: String) -> String {
+            prompt "What is the capital of {country}?";
+        }
+
+        fn get_river(city: String) -> String {
+            prompt "Which river flows through {city}?";
+        }
+    "#;
+    let generate = |source: &str| -> Result<String> {
+        CodeGenerator::new().generate(&parse_source(source)?, false)
+    };
+    let project = write_project(first, &generate(first)?, false, false)?;
+    let main_path = project.path().join("src/main.rs");
+    let code = std::fs::read_to_string(&main_path)?;
+    let start = code.find(USER_REGION_BEGIN).unwrap();
+    let end = code.find(USER_REGION_END).unwrap();
+    let edited_main = "fn main() {\n    println!(\"edited\");\n}\n";
+    std::fs::write(
+        &main_path,
+        format!(
+            "{}{}\n{}{}",
+            &code[..start],
+            USER_REGION_BEGIN,
+            edited_main,
+            &code[end..]
+        ),
+    )?;
+
+    // --- Act ---
+    build_project(project.path(), second, &generate(second)?, false, false)?;
+
+    // --- Assert ---
+    let code = std::fs::read_to_string(&main_path)?;
+    assert_eq!(code.matches("fn main()").count(), 1, "{}", code);
+    assert!(code.contains(edited_main));
+    assert!(code.contains("pub fn get_river("));
+    cargo(project.path(), &["build"])?;
+    Ok(())
+}
+
+#[test]
+fn test_meta_companion_reports_the_model_it_asked() -> Result<()> {
+    // --- Arrange ---
+    let source = r#"
+        fn get_capital(country: String) -> String {
+            prompt "What is the capital of {country}?";
+        }
+    "#;
+    let settings = HashMap::from([(
+        "get_capital".to_string(),
+        FunctionSettings {
+            model: Some("mistral".to_string()),
+            ..FunctionSettings::default()
+        },
+    )]);
+    let generator = CodeGenerator::new()
+        .with_meta_functions(true)
+        .with_function_settings(settings);
+    let tests = r#"
+    #[test]
+    fn with_meta() {
+        let mut mock = MockLlmProvider::new();
+        mock.expect_generate().returning(|_| Ok("Paris".to_string()));
+        let llm = LlmClient::default().with_llm_provider(mock);
+
+        let (capital, meta) = get_capital_with_meta(&llm, "France".to_string());
+
+        assert_eq!(capital, "Paris");
+        assert_eq!(meta.model, "mistral");
+        assert!(meta.latency > std::time::Duration::ZERO);
+    }
+    "#;
+
+    // --- Act & Assert ---
+    test_library(source, generator, tests)
+}
@@ -1,32 +1,46 @@
 // tests/compiler_tests.rs
+//
+// These tests run offline: project names, when asked for, come from a mock LLM. Tests that
+// contact a real model are only built with `cargo test --features live-llm`.
 use anyhow::Result;
+use std::path::Path;
 use tempfile::tempdir;
 use vibelang::compiler::{
     codegen::CodeGenerator, parser::parse_source, project_builder::ProjectBuilder,
 };
-use vibelang::config::Config;
-use vibelang::runtime::client::LlmClient;
+use vibelang::runtime::llm_provider::MockLlmProvider;
+use vibelang::testing::check_rust_syntax;
+
+const CAPITAL_SOURCE: &str = r#"
+    type Capital = Meaning<String>("the capital city of a country");
+    fn get_capital(country: String) -> Capital {
+        prompt "What is the capital of {country}?";
+    }
+"#;
+
+// Answers the project naming prompt, the only LLM call made while building a project, and
+// only when names are asked for with `with_suggested_names`.
+fn mock_namer() -> MockLlmProvider {
+    let mut llm_client = MockLlmProvider::new();
+    llm_client.expect_generate().returning(|_| {
+        Ok(r#"{"packagename": "capital_finder", "binname": "capitals"}"#.to_string())
+    });
+    llm_client
+}
 
 #[test]
 fn test_end_to_end_compilation() -> Result<()> {
     // 1. Setup: Define source code and create a temporary output directory.
-    let vibe_source = r#"
-        type Capital = Meaning<String>("the capital city of a country");
-        fn get_capital(country: String) -> Capital {
-            prompt "What is the capital of {country}?";
-        }
-    "#;
     let temp_dir = tempdir()?;
     let output_path = temp_dir.path();
 
     // 2. Execution: Run the core compiler logic.
-    let config = Config::from_env();
-    let llm_client = LlmClient::new(config)?;
-    let ast = parse_source(vibe_source)?;
+    let llm_client = mock_namer();
+    let ast = parse_source(CAPITAL_SOURCE)?;
     let generated_code = CodeGenerator::new().generate(&ast, false)?;
 
-    let builder = ProjectBuilder::new(&llm_client);
-    builder.build(output_path, vibe_source, &generated_code, false)?;
+    let builder = ProjectBuilder::new(&llm_client).with_suggested_names(true);
+    builder.build(output_path, CAPITAL_SOURCE, &generated_code, false)?;
 
     // 3. Verification: Check that the expected files were created.
     let cargo_toml_path = output_path.join("Cargo.toml");
@@ -35,13 +49,72 @@ fn test_end_to_end_compilation() -> Result<()> {
     assert!(cargo_toml_path.exists(), "Cargo.toml was not created");
     assert!(main_rs_path.exists(), "src/main.rs was not created");
 
+    let manifest = std::fs::read_to_string(cargo_toml_path)?;
+    assert!(manifest.contains(r#"name = "capital-finder""#));
+    assert!(manifest.contains(r#"name = "capitals""#));
+
     let main_content = std::fs::read_to_string(main_rs_path)?;
     assert!(
         main_content.contains("pub fn get_capital"),
         "get_capital function not found in generated code"
     );
 
-    // You could even try to compile and run the generated project here for a full-cycle test.
+    Ok(())
+}
+
+#[test]
+fn test_fixture_matrix_builds_offline() -> Result<()> {
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let mut sources: Vec<_> = std::fs::read_dir(&fixtures)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    sources.retain(|path| path.extension().is_some_and(|ext| ext == "vibe"));
+    sources.sort();
+    assert!(!sources.is_empty(), "no fixtures in {}", fixtures.display());
+
+    let llm_client = mock_namer();
+    for source_path in &sources {
+        let source = std::fs::read_to_string(source_path)?;
+        let ast = parse_source(&source)?;
+        for (as_lib, entry_point) in [(false, "src/main.rs"), (true, "src/lib.rs")] {
+            let temp_dir = tempdir()?;
+            let generated_code = CodeGenerator::new().generate(&ast, as_lib)?;
+            ProjectBuilder::new(&llm_client).build(
+                temp_dir.path(),
+                &source,
+                &generated_code,
+                as_lib,
+            )?;
+
+            let written = std::fs::read_to_string(temp_dir.path().join(entry_point))?;
+            check_rust_syntax(&written).map_err(|e| {
+                anyhow::anyhow!("{} ({}): {}", source_path.display(), entry_point, e)
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "live-llm")]
+#[test]
+fn test_end_to_end_compilation_with_live_llm() -> Result<()> {
+    use vibelang::config::Config;
+    use vibelang::runtime::client::LlmClient;
+
+    let temp_dir = tempdir()?;
+    let llm_client = LlmClient::new(Config::from_env())?;
+    let ast = parse_source(CAPITAL_SOURCE)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    ProjectBuilder::new(&llm_client).build(
+        temp_dir.path(),
+        CAPITAL_SOURCE,
+        &generated_code,
+        false,
+    )?;
 
+    assert!(temp_dir.path().join("Cargo.toml").exists());
+    assert!(temp_dir.path().join("src/main.rs").exists());
     Ok(())
 }
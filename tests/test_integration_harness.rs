@@ -0,0 +1,83 @@
+use anyhow::Result;
+use vibelang::testing::{check_rust_syntax, compile_and_check};
+
+#[test]
+fn test_harness_accepts_weather_program() -> Result<()> {
+    let vibe_source = r#"
+        type Weather = Meaning<String>("weather description");
+
+        fn get_weather(city: String) -> Weather {
+            prompt "What is the weather like in {city}? Provide a brief description.";
+        }
+
+        fn get_temperature(city: String) -> Meaning<Int>("temperature in Celsius") {
+            prompt "What is the current temperature in {city}? Reply with just the number.";
+        }
+    "#;
+
+    let generated_code = compile_and_check(vibe_source)?;
+
+    assert!(generated_code.contains("pub fn get_weather"));
+    assert!(generated_code.contains("pub fn get_temperature"));
+
+    Ok(())
+}
+
+#[test]
+fn test_harness_accepts_joke_program() -> Result<()> {
+    let vibe_source = r#"
+        type Joke = Meaning<String>("a short humorous line");
+        type Topic = Meaning<String>("topic for the joke");
+
+        fn tellJoke(topic: Topic) -> Joke {
+            prompt "Tell me a short joke about {topic}.";
+        }
+    "#;
+
+    let generated_code = compile_and_check(vibe_source)?;
+
+    assert!(generated_code.contains("pub fn tellJoke"));
+
+    Ok(())
+}
+
+// The sample programs shipped with the repository, compiled in full and parsed with `syn`.
+
+#[test]
+fn test_joke_sample_generates_valid_rust() -> Result<()> {
+    let generated_code = compile_and_check(include_str!("fixtures/joke.vibe"))?;
+
+    assert!(generated_code.contains("fn main()"));
+    Ok(())
+}
+
+#[test]
+fn test_greeting_sample_generates_valid_rust() -> Result<()> {
+    let generated_code = compile_and_check(include_str!("../templates/starter.vibe"))?;
+
+    assert!(generated_code.contains("pub fn greet"));
+    Ok(())
+}
+
+#[test]
+fn test_weather_sample_generates_valid_rust() -> Result<()> {
+    let generated_code = compile_and_check(include_str!("fixtures/weather.vibe"))?;
+
+    assert!(generated_code.contains("pub fn get_temperature"));
+    Ok(())
+}
+
+#[test]
+fn test_knowledge_sample_generates_valid_rust() -> Result<()> {
+    let generated_code = compile_and_check(include_str!("../examples/knowledge_retrieval.vibe"))?;
+
+    assert!(generated_code.contains("fn main()"));
+    Ok(())
+}
+
+#[test]
+fn test_harness_rejects_invalid_rust() {
+    let result = check_rust_syntax("pub fn broken( -> i32 {}");
+
+    assert!(result.is_err(), "Invalid Rust should be rejected");
+}
@@ -1,5 +1,11 @@
 use anyhow::Result;
-use vibelang::compiler::{codegen::CodeGenerator, parser::parse_source};
+use regex::Regex;
+use std::collections::HashSet;
+use vibelang::compiler::{
+    codegen::{CodeGenerator, IndentStyle, RuntimeMode, TypeMapping},
+    parser::parse_source,
+};
+use vibelang::runtime::types::FloatFormat;
 
 #[test]
 fn test_joke_generation_payload() -> Result<()> {
@@ -51,7 +57,7 @@ fn test_joke_generation_payload() -> Result<()> {
 
     // Verify the correct conversion method is called.
     assert!(
-        generated_code.contains("result.into_string()"),
+        generated_code.contains(".coerce(\"String\")"),
         "Incorrect return type conversion method was used."
     );
 
@@ -109,3 +115,1885 @@ fn test_greeting_generation_payload_with_inline_meaning() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_generated_header_includes_source_file_name() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Joke = Meaning<String>("a short humorous line");
+
+        fn tellJoke(topic: String) -> Joke {
+            prompt "Tell me a short joke about {topic}.";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+
+    // --- Act ---
+    let with_header = CodeGenerator::new()
+        .with_header("jokes.vibe", vibe_source)
+        .generate(&ast, false)?;
+    let without_header = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    let first_line = with_header.lines().next().unwrap_or_default();
+    assert!(
+        first_line.starts_with("// Generated by vibelang"),
+        "Generated file should start with the header comment, got: {}",
+        first_line
+    );
+    assert!(first_line.contains("jokes.vibe"));
+    assert!(with_header.contains("source hash"));
+    assert!(!without_header.starts_with("// Generated by vibelang"));
+
+    Ok(())
+}
+
+#[test]
+fn test_tab_indent_style() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn get_year() -> Int {
+            prompt "What year is it?";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new()
+        .with_indent_style(IndentStyle::Tabs)
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("\n\tlet meaning = None;"));
+    assert!(!generated_code.contains("\n    let meaning"));
+
+    Ok(())
+}
+
+#[test]
+fn test_stopword_only_meanings_get_valid_unique_extractors() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type First = Meaning<Int>("of the");
+        type Second = Meaning<Int>("in a");
+        type Third = Meaning<Int>("count of the items");
+
+        fn get_first() -> First { prompt "First?"; }
+        fn get_second() -> Second { prompt "Second?"; }
+        fn get_third() -> Third { prompt "Third?"; }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        !generated_code.contains("fn extract__value"),
+        "An empty normalized meaning produced an invalid extractor name."
+    );
+
+    let extractor = Regex::new(r"fn extract_(\w+)_value\(")?;
+    let names: Vec<String> = extractor
+        .captures_iter(&generated_code)
+        .map(|cap| cap[1].to_string())
+        .collect();
+    let unique: HashSet<&String> = names.iter().collect();
+
+    assert_eq!(names.len(), 3);
+    assert_eq!(
+        unique.len(),
+        3,
+        "Extractor names must be unique: {:?}",
+        names
+    );
+    assert!(names.contains(&"count_items".to_string()));
+    assert_eq!(
+        names.iter().filter(|n| n.starts_with("meaning_")).count(),
+        2
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_call_argument_type_mismatch_is_reported() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn greet(name: String) -> String {
+            prompt "Say hello to {name}.";
+        }
+
+        fn welcome() -> String {
+            greet(42);
+            prompt "Welcome everyone.";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let error = CodeGenerator::new()
+        .generate(&ast, false)
+        .expect_err("A mismatched argument should not generate code");
+    let message = error.to_string();
+
+    // --- Assert ---
+    assert!(message.starts_with("Line 7: Type mismatch:"), "{}", message);
+    assert!(message.contains("`name` of `greet`"), "{}", message);
+    assert!(message.contains("an Int literal"), "{}", message);
+
+    Ok(())
+}
+
+#[test]
+fn test_call_with_matching_argument_types_generates() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type City = Meaning<String>("a city name");
+
+        fn get_weather(city: City, days: Float) -> String {
+            prompt "Weather in {city} for {days} days?";
+        }
+
+        fn plan_trip(destination: String) -> String {
+            get_weather(destination, 3);
+            prompt "Plan a trip to {destination}.";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub fn plan_trip"));
+
+    Ok(())
+}
+
+#[test]
+fn test_async_function_generates_awaited_client_call() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Weather = Meaning<String>("weather description");
+
+        async fn get_weather(city: String) -> Weather {
+            prompt "What is the weather like in {city}?";
+        }
+
+        fn get_year() -> Int {
+            prompt "What year is it?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        generated_code
+            .contains("pub async fn get_weather(llm: &LlmClient, city: String) -> Weather"),
+        "Async function signature is incorrect."
+    );
+    assert!(generated_code.contains(
+        "let result = vibe_execute_prompt_async(llm, &template, meaning, return_type_str, temperature).await;"
+    ));
+    assert!(
+        generated_code
+            .contains("llm.generate_async_with_temperature(&enhanced_prompt, temperature).await")
+    );
+    assert!(generated_code.contains("pub fn get_year(llm: &LlmClient"));
+    assert!(generated_code.contains("#[tokio::main]"));
+
+    Ok(())
+}
+
+#[test]
+fn test_explain_lists_type_mappings() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Population = Meaning<Int>("population count in millions");
+        type Year = Int;
+
+        fn get_population(country: String) -> Population {
+            prompt "What is the population of {country}?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let explanation = CodeGenerator::new().explain(&ast);
+
+    // --- Assert ---
+    assert!(explanation.contains("Population -> i32"), "{}", explanation);
+    assert!(explanation.contains("meaning:   population count in millions"));
+    assert!(explanation.contains("extractor: extract_population_count_millions_value"));
+    assert!(explanation.contains("Year -> i32"));
+
+    Ok(())
+}
+
+#[test]
+fn test_raw_response_companions() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Population = Meaning<Int>("population count in millions");
+
+        fn get_population(country: String) -> Population {
+            prompt "What is the population of {country}?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let with_raw = CodeGenerator::new()
+        .with_raw_responses(true)
+        .generate(&ast, false)?;
+    let without_raw = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    // The companion returns the response text without any conversion.
+    assert!(
+        with_raw.contains("pub fn get_population_raw(llm: &LlmClient, country: String) -> String")
+    );
+    assert!(with_raw.contains("vibe_fetch_raw(llm, &template, meaning, temperature)"));
+    // The typed function still converts the parsed value.
+    assert!(
+        with_raw.contains("pub fn get_population(llm: &LlmClient, country: String) -> Population")
+    );
+    assert!(with_raw.contains(".coerce(\"i32\")"));
+    assert!(with_raw.contains("vibe_log_raw_response(prompt, &response);"));
+    vibelang::testing::check_rust_syntax(&with_raw)?;
+
+    assert!(!without_raw.contains("_raw("));
+    assert!(!without_raw.contains("VIBELANG_DEBUG"));
+
+    Ok(())
+}
+
+#[test]
+fn test_strict_types_requires_meaning_on_non_string_returns() -> Result<()> {
+    // --- Arrange ---
+    let bare_source = r#"
+        fn calculate_age(birth_year: Int) -> Int {
+            prompt "How old is someone born in {birth_year}?";
+        }
+    "#;
+    let meaning_source = r#"
+        type Age = Meaning<Int>("age in years");
+
+        fn calculate_age(birth_year: Int) -> Meaning<Int>("age calculation") {
+            prompt "How old is someone born in {birth_year}?";
+        }
+
+        fn get_age(birth_year: Int) -> Age {
+            prompt "How old is someone born in {birth_year}?";
+        }
+
+        fn get_name() -> String {
+            prompt "Pick a name.";
+        }
+    "#;
+    let strict = CodeGenerator::new().with_strict_types(true);
+
+    // --- Act ---
+    let bare_error = strict
+        .generate(&parse_source(bare_source)?, false)
+        .expect_err("A bare Int return should be rejected in strict mode");
+    let with_meanings = strict.generate(&parse_source(meaning_source)?, false);
+    let not_strict = CodeGenerator::new().generate(&parse_source(bare_source)?, false);
+
+    // --- Assert ---
+    assert!(
+        bare_error.to_string().starts_with(
+            "Line 2, column 46: Function `calculate_age` returns `Int` without a meaning"
+        ),
+        "{}",
+        bare_error
+    );
+    // Meanings, inline or inherited from an alias, satisfy strict mode; String needs none.
+    with_meanings?;
+    // Without strict mode the bare return is still accepted.
+    not_strict?;
+
+    Ok(())
+}
+
+#[test]
+fn test_triple_quoted_prompt_is_dedented() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn describe(city: String) -> String {
+            prompt """
+                Describe {city} in one sentence.
+                  Mention its "best" landmark.
+                Keep it short.
+            """;
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        generated_code.contains(
+            "let mut template = \"Describe {city} in one sentence.\n  Mention its \\\"best\\\" landmark.\nKeep it short.\".to_string();"
+        ),
+        "Triple-quoted prompt was not dedented:\n{}",
+        generated_code
+    );
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_backslashes_in_prompts_are_escaped() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn find_numbers(text: String) -> String {
+            prompt """Match \d+ in {text}, and keep \" as it is.""";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+    let generator = CodeGenerator::new();
+
+    // --- Act ---
+    let generated_code = generator.generate(&ast, false)?;
+    let prompt = generator.render_prompt(
+        &ast,
+        "find_numbers",
+        &[("text".to_string(), "a1 b22".to_string())].into(),
+    )?;
+
+    // --- Assert ---
+    assert!(
+        generated_code.contains(
+            r#"let mut template = "Match \\d+ in {text}, and keep \\\" as it is.".to_string();"#
+        ),
+        "{}",
+        generated_code
+    );
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert_eq!(prompt, r#"Match \d+ in a1 b22, and keep \" as it is."#);
+    Ok(())
+}
+
+#[test]
+fn test_parameterized_meaning_substitutes_arguments() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn get_age(person: String) -> Meaning<Int>("age of {person} in years") {
+            prompt "How old is {person}?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        generated_code
+            .contains(r#"let mut meaning_text = "age of {person} in years".to_string();"#)
+    );
+    assert!(
+        generated_code
+            .contains(r#"meaning_text = meaning_text.replace("{person}", &person.to_string());"#)
+    );
+    assert!(generated_code.contains("let meaning = Some(meaning_text.as_str());"));
+    assert!(generated_code.contains(
+        "let result = vibe_execute_prompt(llm, &template, meaning, return_type_str, temperature);"
+    ));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_generate_matches_two_pass_reference_output() -> Result<()> {
+    // --- Arrange ---
+    // The expected file was generated by the earlier two-pass `generate`, which processed all
+    // type declarations before any function, and is only updated for intentional changes to
+    // the generated runtime.
+    let vibe_source = include_str!("fixtures/forward_references.vibe");
+    let expected = include_str!("fixtures/forward_references.main.rs.expected");
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert_eq!(generated_code, expected);
+    Ok(())
+}
+
+#[test]
+fn test_inline_parameter_meaning_is_passed_as_prompt_context() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Language = Meaning<String>("language name");
+
+        fn greet(name: Meaning<String>("person's name"), language: Language) -> String {
+            prompt "Greet {name} in {language}.";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains(r#"template.push_str("\n\nParameter meanings:");"#));
+    assert!(generated_code.contains(r#"template.push_str("\n- name (person's name): ");"#));
+    assert!(generated_code.contains("template.push_str(&name.to_string());"));
+    // Only inline meanings are added; aliases carry their meaning in the type name.
+    assert!(!generated_code.contains("- language ("));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_inline_union_return_type_generates_enum() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn classify(text: String) -> "positive" | "negative" | "neutral" {
+            prompt "Classify the sentiment of: {text}";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub enum ClassifyOutput {"));
+    assert!(generated_code.contains("    Positive,\n    Negative,\n    Neutral,\n}"));
+    assert!(generated_code.contains("impl std::str::FromStr for ClassifyOutput {"));
+    assert!(generated_code.contains(r#""positive" => Ok(Self::Positive),"#));
+    assert!(generated_code.contains(r#""negative" => Ok(Self::Negative),"#));
+    assert!(generated_code.contains(r#""neutral" => Ok(Self::Neutral),"#));
+    assert!(
+        generated_code.contains("pub fn classify(llm: &LlmClient, text: String) -> ClassifyOutput")
+    );
+    assert!(
+        generated_code.contains(r#"let meaning = Some("one of: positive, negative, neutral");"#)
+    );
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_inline_union_rejects_colliding_variants() {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn classify(text: String) -> "not sure" | "not-sure" {
+            prompt "Classify: {text}";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source).unwrap();
+    let error = CodeGenerator::new().generate(&ast, false).unwrap_err();
+
+    // --- Assert ---
+    assert!(error.to_string().contains("`NotSure`"));
+}
+
+#[test]
+fn test_custom_template_with_unknown_variable_is_rejected() {
+    // --- Arrange ---
+    let template = r#"
+        // {{ header.version | default(value="dev") }}
+        {% for func in functions %}fn {{ func.name }}() {}{% endfor %}
+        {{ bogus }}
+    "#;
+
+    // --- Act ---
+    let error = match CodeGenerator::new().with_template(template) {
+        Ok(_) => panic!("A template using `bogus` should be rejected"),
+        Err(e) => e.to_string(),
+    };
+
+    // --- Assert ---
+    assert!(error.contains("`bogus`"), "Unexpected error: {}", error);
+    assert!(
+        !error.contains("`func`"),
+        "Loop variables are not context variables"
+    );
+}
+
+#[test]
+fn test_custom_template_renders_known_variables() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn get_year() -> Int { prompt "What year is it?"; }
+    "#;
+    let template = "{% for func in functions %}// {{ func.name }} ({{ loop.index }})\n{% endfor %}";
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new()
+        .with_template(template)?
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert_eq!(generated_code, "// get_year (1)\n");
+
+    // The built-in templates only use the documented variables.
+    CodeGenerator::new().with_template(include_str!("../templates/main.rs.tera"))?;
+    CodeGenerator::new().with_template(include_str!("../templates/runtime.rs.tera"))?;
+    Ok(())
+}
+
+#[test]
+fn test_custom_type_mapping_is_used_in_aliases_and_signatures() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Birthday = Meaning<Date>("date of birth");
+
+        fn get_birthday(person: String) -> Birthday {
+            prompt "When was {person} born? Reply in YYYY-MM-DD format.";
+        }
+
+        fn days_until(day: Date) -> Int {
+            prompt "How many days from today until {day}?";
+        }
+    "#;
+    let date = TypeMapping::new(
+        "chrono::NaiveDate",
+        "chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()",
+        "text.trim().parse::<chrono::NaiveDate>().expect(\"Failed to parse date\")",
+    );
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new()
+        .with_type_mapping("Date", date)
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub type Birthday = chrono::NaiveDate;"));
+    assert!(
+        generated_code.contains("pub fn get_birthday(llm: &LlmClient, person: String) -> Birthday")
+    );
+    assert!(
+        generated_code
+            .contains("pub fn days_until(llm: &LlmClient, day: chrono::NaiveDate) -> i32")
+    );
+    assert!(generated_code.contains("chrono::NaiveDate::from_ymd_opt(2024, 1, 1).unwrap()"));
+    assert!(generated_code.contains(
+        "let text: &str = &text;\n    text.trim().parse::<chrono::NaiveDate>().expect(\"Failed to parse date\")"
+    ));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_unit_return_runs_prompt_without_conversion() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn log_event(event: String) {
+            prompt "Record this event: {event}";
+        }
+
+        fn notify(user: String) -> () {
+            prompt "Send a notification to {user}.";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new()
+        .with_strict_types(true)
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub fn log_event(llm: &LlmClient, event: String) {"));
+    assert!(generated_code.contains("pub fn notify(llm: &LlmClient, user: String) {"));
+    assert_eq!(
+        generated_code
+            .matches("let result = vibe_execute_prompt(llm, &template, meaning, return_type_str, temperature);")
+            .count(),
+        2
+    );
+    assert_eq!(generated_code.matches("let _ = result;").count(), 2);
+    assert!(!generated_code.contains("result.into_"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_let_typed_with_alias_uses_resolved_type() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type City = Meaning<String>("a city name");
+        type Celsius = Meaning<Float>("temperature in Celsius");
+
+        fn forecast(day: String) -> Celsius {
+            let city: City = "Rome";
+            let offset: Float = 2;
+            prompt "What is the temperature in {city} on {day}, plus {offset}?";
+        }
+    "#;
+    let undeclared_source = r#"
+        fn forecast() -> String {
+            let city: Town = "Rome";
+            prompt "Weather in {city}?";
+        }
+    "#;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&parse_source(undeclared_source)?, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains("let city: City = \"Rome\".to_string();"));
+    assert!(generated_code.contains("let offset: f64 = 2.0;"));
+    assert!(generated_code.contains("template = template.replace(\"{city}\", &city.to_string());"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(
+        error.contains("Unknown type `Town` for `let city`"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_float_format_applies_to_test_values() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn convert(celsius: Float) -> String {
+            prompt "Convert {celsius} degrees Celsius to Fahrenheit.";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+
+    // --- Act ---
+    let shortest = CodeGenerator::new().generate(&ast, false)?;
+    let fixed = CodeGenerator::new()
+        .with_float_format(FloatFormat::Fixed(2))
+        .generate(&ast, false)?;
+    let whole = CodeGenerator::new()
+        .with_float_format(FloatFormat::Fixed(0))
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(shortest.contains("45.6  // <-------------- CHANGE THE TEST VALUE"));
+    assert!(fixed.contains("45.60  // <-------------- CHANGE THE TEST VALUE"));
+    assert!(whole.contains("46.0  // <-------------- CHANGE THE TEST VALUE"));
+    vibelang::testing::check_rust_syntax(&whole)?;
+    Ok(())
+}
+
+#[test]
+fn test_float_literals_are_written_exactly_whatever_the_float_format() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        class Recipe {
+            let scale: Float = 0.25;
+        }
+
+        fn convert(celsius: Float) -> String {
+            let offset: Float = 32.5;
+            prompt "Add {offset} to {celsius} times 1.8.";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new()
+        .with_float_format(FloatFormat::Fixed(0))
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        generated_code.contains("scale: 0.25,"),
+        "{}",
+        generated_code
+    );
+    assert!(generated_code.contains("let offset: f64 = 32.5;"));
+    assert!(generated_code.contains("46.0  // <-------------- CHANGE THE TEST VALUE"));
+    Ok(())
+}
+
+#[test]
+fn test_temperature_statement_reaches_the_execute_call() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn brainstorm(topic: String) -> String {
+            temperature 1;
+            prompt "List ideas about {topic}.";
+        }
+
+        fn summarize(text: String) -> String {
+            prompt "Summarize: {text}";
+        }
+    "#;
+    let precise_source = r#"
+        fn brainstorm() -> String {
+            temperature 0.2;
+            prompt "List ideas.";
+        }
+    "#;
+    let out_of_range_source = r#"
+        fn brainstorm() -> String {
+            temperature 3.5;
+            prompt "List ideas.";
+        }
+    "#;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new()
+        .with_raw_responses(true)
+        .generate(&parse_source(vibe_source)?, false)?;
+    let whole_numbers = CodeGenerator::new()
+        .with_float_format(FloatFormat::Fixed(0))
+        .generate(&parse_source(precise_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&parse_source(out_of_range_source)?, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert_eq!(
+        generated_code
+            .matches("let temperature = Some(1.0);")
+            .count(),
+        2
+    );
+    assert_eq!(generated_code.matches("let temperature = None;").count(), 2);
+    assert!(
+        generated_code.contains("llm.generate_with_temperature(&enhanced_prompt, temperature)")
+    );
+    assert!(generated_code.contains("vibe_fetch_raw(llm, &template, meaning, temperature)"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(whole_numbers.contains("let temperature = Some(0.2);"));
+    assert!(
+        error.contains("Invalid temperature 3.5 in `brainstorm`"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_percentage_and_price_meanings_get_lenient_extractors() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Share = Meaning<Float>("market share percentage");
+        type Price = Meaning<Int>("ticket price in dollars");
+        type Year = Meaning<Int>("year of release");
+
+        fn share(company: String) -> Share {
+            prompt "What is the market share of {company}?";
+        }
+
+        fn price(show: String) -> Price {
+            prompt "How much is a ticket for {show}?";
+        }
+
+        fn released(show: String) -> Year {
+            prompt "When was {show} released?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains(
+        "fn extract_market_share_percentage_value(text: &str) -> f64 {\n    \n    // A percentage such as \"45%\""
+    ));
+    assert!(generated_code.contains(
+        "fn extract_ticket_price_dollars_value(text: &str) -> i32 {\n    \n    // An amount such as \"$1,200\""
+    ));
+    assert_eq!(generated_code.matches("NumberStyle::Currency;").count(), 1);
+    assert!(generated_code.contains(".coerce(\"i32\").ok())"));
+    assert!(generated_code.contains(
+        "fn extract_year_release_value(text: &str) -> i32 {\n    \n    extract_generic_number(text)"
+    ));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_function_without_prompt_or_return_is_reported() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Age = Meaning<Int>("age in years");
+
+        fn get_age() -> Age {}
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let error = CodeGenerator::new()
+        .generate(&ast, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(
+        error.starts_with("Line 4: Function `get_age` has no prompt and no return expression"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_unused_meanings_get_no_extractor() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Population = Meaning<Int>("population count in millions");
+        type Area = Meaning<Int>("area in square kilometers");
+        type Country = Meaning<String>("country name");
+
+        fn get_population(country: Country) -> Population {
+            prompt "What is the population of {country}?";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("fn extract_population_count_millions_value(text: &str)"));
+    assert!(!generated_code.contains("extract_area_square_kilometers_value"));
+    assert!(!generated_code.contains("extract_country_name_value"));
+    // The unused types are still declared.
+    assert!(generated_code.contains("pub type Area = i32;"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_range_constraint_validates_the_result() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Age = Meaning<Int>("age in years", range: 0..150);
+
+        fn get_age(person: String) -> Age {
+            prompt "How old is {person}?";
+        }
+
+        fn get_ratio(x: String) -> Meaning<Float>("ratio", max: 1.5) {
+            prompt "What is the ratio of {x}?";
+        }
+    "#;
+    let on_string_source = r#"
+        fn get_name() -> Meaning<String>("name", max: 3) {
+            prompt "Name?";
+        }
+    "#;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&parse_source(on_string_source)?, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains(
+        "let bounds = vibelang::runtime::validation::Bounds { min: Some(0.0), max: Some(150.0) };"
+    ));
+    assert!(generated_code.contains(
+        "let bounds = vibelang::runtime::validation::Bounds { min: None, max: Some(1.5) };"
+    ));
+    assert!(generated_code.contains("panic!(\"Invalid LLM response for `get_age`: {}\", e);"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(
+        error.starts_with(
+            "Line 2, column 26: Function `get_name` returns `String`, which has range constraints"
+        ),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_class_fields_with_defaults_get_default_impl() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type City = Meaning<String>("a city name");
+
+        class Forecast {
+            let city: City = "Paris";
+            let days: Float = 3;
+            verbose: Bool;
+        }
+
+        fn get_weather(city: City) -> String {
+            prompt "What is the weather in {city}?";
+        }
+    "#;
+    let with_method = parse_source("class Empty { fn size() -> Int { prompt \"Size?\"; } }")?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&with_method, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub struct Forecast {"));
+    assert!(generated_code.contains("pub city: City,"));
+    assert!(generated_code.contains("impl Default for Forecast {"));
+    assert!(generated_code.contains("city: \"Paris\".to_string(),"));
+    assert!(generated_code.contains("days: 3.0,"));
+    assert!(generated_code.contains("verbose: Default::default(),"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(error.contains("Methods are not supported yet"), "{}", error);
+    Ok(())
+}
+
+#[test]
+fn test_class_parameters_are_reported() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"class Person { name: String; } fn greet(p: Person) -> String { prompt "Hi {p}"; }"#,
+    )?;
+
+    // --- Act ---
+    let binary = CodeGenerator::new().generate(&ast, false);
+    let library = CodeGenerator::new().generate(&ast, true);
+
+    // --- Assert ---
+    assert_eq!(
+        binary.unwrap_err().to_string(),
+        "Line 1, column 41: Parameter `p` of `greet` has the class type `Person`, which cannot be put in a prompt. Pass the fields it needs as parameters instead."
+    );
+    assert!(library.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_generate_modules_with_cross_module_types() -> Result<()> {
+    // --- Arrange ---
+    let geography = parse_source(
+        r#"
+        type City = Meaning<String>("the name of a city");
+
+        fn get_capital(country: String) -> City {
+            prompt "What is the capital of {country}?";
+        }
+    "#,
+    )?;
+    let travel = parse_source(
+        r#"
+        fn describe(city: City) -> String {
+            prompt "Describe {city} in one sentence.";
+        }
+    "#,
+    )?;
+    let modules = vec![
+        ("geography".to_string(), geography),
+        ("travel".to_string(), travel),
+    ];
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate_modules(&modules, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub mod geography {"));
+    assert!(generated_code.contains("pub mod travel {"));
+    assert_eq!(generated_code.matches("pub type City = String;").count(), 1);
+    assert!(generated_code.contains("use super::geography::City;"));
+    assert!(generated_code.contains("pub fn describe(llm: &LlmClient, city: City) -> String"));
+    assert!(generated_code.contains("= travel::describe("));
+    assert_eq!(generated_code.matches("fn main()").count(), 1);
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_numeric_answers_are_retried_a_configurable_number_of_times() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn get_population(city: String) -> Meaning<Int>("population count") {
+            prompt "How many people live in {city}?";
+        }
+
+        async fn get_temperature(city: String) -> Float {
+            prompt "What is the temperature in {city}?";
+        }
+    "#;
+    let ast = parse_source(vibe_source)?;
+
+    // --- Act ---
+    let default_code = CodeGenerator::new().generate(&ast, false)?;
+    let five = CodeGenerator::new()
+        .with_number_retries(5)
+        .generate(&ast, false)?;
+    let disabled = CodeGenerator::new()
+        .with_number_retries(0)
+        .generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        default_code.contains(
+            "vibelang::runtime::retry::retry_until_number(&enhanced_prompt, 2, |prompt| {"
+        )
+    );
+    assert!(default_code.contains(
+        "vibelang::runtime::retry::retry_until_number_async(&enhanced_prompt, 2, |prompt| async move {"
+    ));
+    vibelang::testing::check_rust_syntax(&default_code)?;
+    assert!(five.contains("retry_until_number(&enhanced_prompt, 5,"));
+    assert!(!disabled.contains("retry_until_number"));
+    Ok(())
+}
+
+#[test]
+fn test_alias_chains_resolve_to_the_base_type() -> Result<()> {
+    // --- Arrange ---
+    // `Celsius` is declared before the alias it refers to.
+    let vibe_source = r#"
+        type Celsius = Degrees;
+        type Degrees = Reading;
+        type Reading = Int;
+
+        fn get_temperature(city: String) -> Celsius {
+            prompt "What is the temperature in {city}?";
+        }
+    "#;
+    let cycle = parse_source("type A = B;\ntype B = A;")?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&cycle, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub type Celsius = i32;"));
+    assert!(generated_code.contains("pub type Degrees = i32;"));
+    assert!(
+        generated_code.contains("pub fn get_temperature(llm: &LlmClient, city: String) -> Celsius")
+    );
+    assert!(generated_code.contains("let return_type_str = \"i32\";"));
+    assert!(generated_code.contains(".coerce(\"i32\")"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert_eq!(
+        error,
+        "Line 1: Type alias `A` refers to itself: A -> B -> A"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_import_generates_use_statement() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        import "std::collections::HashMap";
+
+        fn greet(name: String) -> String {
+            prompt "Say hello to {name}.";
+        }
+    "#;
+    let invalid = parse_source("import \"not a path\";")?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&invalid, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains("\nuse std::collections::HashMap;\n"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(
+        error.starts_with("Line 1: The import `not a path`"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_length_annotation_extends_prompt() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        @concise
+        fn summarize(text: String) -> String {
+            prompt "Summarize {text}.";
+        }
+
+        @detailed async fn explain(topic: String) -> String {
+            prompt "Explain {topic}.";
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("\"Summarize {text}. Answer in one sentence.\""));
+    assert!(
+        generated_code.contains("\"Explain {topic}. Answer in detail, over a few paragraphs.\"")
+    );
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_tuple_return_type_is_split_into_values() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn capital_and_population(country: String) -> (String, Int) {
+            prompt "Name the capital of {country} and its population.";
+        }
+    "#;
+    let tuple_param =
+        parse_source(r#"fn describe(pair: (Int, Int)) -> String { prompt "Describe {pair}."; }"#)?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&tuple_param, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains(
+        "pub fn capital_and_population(llm: &LlmClient, country: String) -> (String, i32)"
+    ));
+    assert!(
+        generated_code
+            .contains("Reply with only a JSON array of 2 values: a string, then an integer.")
+    );
+    assert!(generated_code.contains("VibeValue::parse_tuple(&text, &[\"String\", \"i32\"])"));
+    assert!(
+        generated_code
+            .contains("(values.next().unwrap().into_string(), values.next().unwrap().into_i32())")
+    );
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(
+        error.starts_with("Line 1: A tuple type is not allowed as a parameter"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_meaning_unit_extends_prompt_and_metadata() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        type Temperature = Meaning<Float>("outdoor temperature", unit: "celsius");
+        type Reading = Temperature;
+
+        fn get_temperature(city: String) -> Reading {
+            prompt "What is the temperature in {city}?";
+        }
+    "#;
+    let text_unit = parse_source(
+        r#"fn name() -> Meaning<String>("a name", unit: "letters") { prompt "Name?"; }"#,
+    )?;
+
+    // --- Act ---
+    let generated_code = CodeGenerator::new().generate(&parse_source(vibe_source)?, false)?;
+    let error = CodeGenerator::new()
+        .generate(&text_unit, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(generated_code.contains("\"What is the temperature in {city}? Reply in celsius.\""));
+    assert!(generated_code.contains("// Unit: \"celsius\"\npub type Temperature = f64;"));
+    assert!(generated_code.contains("pub const GET_TEMPERATURE_UNIT: &str = \"celsius\";"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    assert!(
+        error.starts_with("Line 1, column 14: Function `name` returns `String`, which has a unit"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn test_zero_parameter_function_signature_and_demo_call() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"fn get_year() -> Int { prompt "What year is it?"; }"#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(generated_code.contains("pub fn get_year(llm: &LlmClient) -> i32 {"));
+    // Nothing is substituted into the prompt, so it is not declared mutable.
+    assert!(generated_code.contains("let template = \"What year is it?\".to_string();"));
+    let demo_call = Regex::new(r"let result_get_year = get_year\(\s*&client,\s*\);")?;
+    assert!(demo_call.is_match(&generated_code), "{}", generated_code);
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_prompt_note_lines_are_stripped() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn describe(city: String) -> String {
+            prompt """
+                #! Keep this prompt under two lines; the model rambles otherwise.
+                Describe {city} in one sentence.
+                    #! {country} is not a parameter yet.
+                Keep it short.
+            """;
+        }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let generated_code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    assert!(
+        generated_code.contains(
+            "let mut template = \"Describe {city} in one sentence.\nKeep it short.\".to_string();"
+        ),
+        "Note lines were not stripped:\n{}",
+        generated_code
+    );
+    assert!(!generated_code.contains("#!"));
+    Ok(())
+}
+
+#[test]
+fn test_meta_functions_wrap_each_function() -> Result<()> {
+    // --- Arrange ---
+    let vibe_source = r#"
+        fn get_year() -> Int { prompt "What year is it?"; }
+        async fn greet(name: String) -> String { prompt "Say hello to {name}."; }
+    "#;
+
+    // --- Act ---
+    let ast = parse_source(vibe_source)?;
+    let plain_code = CodeGenerator::new().generate(&ast, true)?;
+    let generated_code = CodeGenerator::new()
+        .with_meta_functions(true)
+        .generate(&ast, true)?;
+
+    // --- Assert ---
+    assert!(!plain_code.contains("_with_meta"));
+    assert!(generated_code.contains(
+        "pub fn get_year_with_meta(llm: &LlmClient) -> (i32, vibelang::runtime::meta::CallMeta) {"
+    ));
+    assert!(generated_code.contains("let value = get_year(llm);"));
+    assert!(generated_code.contains(
+        "pub async fn greet_with_meta(llm: &LlmClient, name: String) -> (String, vibelang::runtime::meta::CallMeta) {"
+    ));
+    assert!(generated_code.contains("let value = greet(llm, name).await;"));
+    vibelang::testing::check_rust_syntax(&generated_code)?;
+    Ok(())
+}
+
+#[test]
+fn test_duplicate_parameter_names_are_rejected() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source("fn f(x: Int,\n     x: String) -> String { prompt \"Use {x}.\"; }")?;
+
+    // --- Act ---
+    let error = CodeGenerator::new()
+        .generate(&ast, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert_eq!(
+        error,
+        "Line 1, column 6: Parameter `x` of `f` is declared twice, again at line 2, column 6"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_long_and_uint_map_to_wider_integers() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        type Population = Meaning<Long>("population in absolute numbers");
+
+        fn population(country: String) -> Population {
+            prompt "How many people live in {country}?";
+        }
+
+        fn tickets(year: Long) -> UInt {
+            prompt "How many tickets were sold in {year}?";
+        }
+        "#,
+    )?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+
+    // --- Assert ---
+    assert!(code.contains("pub type Population = i64;"), "{}", code);
+    assert!(code.contains("fn extract_population_absolute_numbers_value(text: &str) -> i64"));
+    assert!(code.contains("pub fn tickets(llm: &LlmClient, year: i64) -> u32"));
+    assert!(code.contains(".coerce(\"i64\")"));
+    assert!(code.contains(".coerce(\"u32\")"));
+    // Both are parsed as floats, which hold populations in the billions without overflow.
+    assert!(!code.contains("let return_type_str = \"i64\""));
+    Ok(())
+}
+
+#[test]
+fn test_external_runtime_omits_bundled_helpers() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        type Temperature = Meaning<Int>("temperature in Celsius");
+
+        fn get_temperature(city: String) -> Temperature {
+            prompt "What is the temperature in {city}?";
+        }
+        "#,
+    )?;
+
+    // --- Act ---
+    let code = CodeGenerator::new()
+        .with_external_runtime(true)
+        .generate(&ast, true)?;
+    let raw = CodeGenerator::new()
+        .with_external_runtime(true)
+        .with_raw_responses(true)
+        .generate(&ast, true);
+
+    // --- Assert ---
+    assert!(!code.contains("fn extract_"), "{}", code);
+    assert!(!code.contains("fn parse_semantic_response"));
+    assert!(!code.contains("\nfn vibe_execute_prompt("));
+    assert!(code.contains("pub type Temperature = i32;"));
+    assert!(code.contains("pub fn get_temperature(llm: &LlmClient, city: String) -> Temperature"));
+    assert!(
+        code.contains(
+            "= vibe_execute_prompt(llm, &template, meaning, return_type_str, temperature);"
+        )
+    );
+    assert!(code.contains("// fn vibe_execute_prompt(llm: &LlmClient, prompt: &str"));
+    assert!(raw.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_names_colliding_with_rust_are_reported() -> Result<()> {
+    // --- Arrange ---
+    let error = |source: &str| -> Result<String> {
+        let ast = parse_source(source)?;
+        Ok(CodeGenerator::new()
+            .generate(&ast, true)
+            .unwrap_err()
+            .to_string())
+    };
+
+    // --- Act ---
+    let function = error("fn match() -> Int { prompt \"Pick a number between 1 and 10.\"; }")?;
+    let type_alias = error("type Option = Meaning<Int>(\"a choice\");")?;
+    let field = error("class Point { loop: Int; }")?;
+
+    // --- Assert ---
+    assert_eq!(
+        function,
+        "Line 1: The function `match` is a reserved word in Rust; choose another name, such as `match_`"
+    );
+    assert_eq!(
+        type_alias,
+        "Line 1: The type `Option` would shadow Rust's `Option` in the generated code; choose another name, such as `MyOption`"
+    );
+    assert!(field.starts_with("Line 1: The field `loop` is a reserved word"));
+    Ok(())
+}
+
+#[test]
+fn test_render_prompt_adds_arguments_and_meaning_context() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        type Temperature = Meaning<Int>("temperature in Celsius");
+
+        fn get_temperature(city: String, day: String) -> Temperature {
+            prompt "How warm is it in {city} on {day}?";
+        }
+        "#,
+    )?;
+    let generator = CodeGenerator::new();
+    let args = [("city".to_string(), "Oslo".to_string())].into();
+    let unknown = [("town".to_string(), "Oslo".to_string())].into();
+
+    // --- Act ---
+    let prompt = generator.render_prompt(&ast, "get_temperature", &args)?;
+    let unknown_error = generator
+        .render_prompt(&ast, "get_temperature", &unknown)
+        .unwrap_err();
+    let missing_function = generator.render_prompt(&ast, "forecast", &args);
+
+    // --- Assert ---
+    assert!(
+        prompt.starts_with("Context: You are an AI assistant"),
+        "{}",
+        prompt
+    );
+    assert!(prompt.contains("semantic meaning of \"temperature in Celsius\""));
+    assert!(prompt.ends_with("Query: How warm is it in Oslo on Test Topic?"));
+    assert_eq!(
+        unknown_error.to_string(),
+        "`get_temperature` has no parameter `town`"
+    );
+    assert!(missing_function.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_example_prompts_use_the_text_of_the_literals() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        fn describe(weight: Float) -> String {
+            let height: Float = 2;
+            let order = "a tall order";
+            let copy = order;
+            prompt "Describe {weight} kg, {height} m and {copy}.";
+        }
+        "#,
+    )?;
+    let generator = CodeGenerator::new().with_float_format(FloatFormat::Fixed(2));
+
+    // --- Act ---
+    let prompts = generator.example_prompts(&ast)?;
+
+    // --- Assert ---
+    assert_eq!(
+        prompts,
+        vec![(
+            "describe".to_string(),
+            "Describe 45.6 kg, 2 m and a tall order.".to_string()
+        )]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_doc_comments_are_emitted_above_declarations() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        /// A temperature as the weather service reports it.
+        type Temperature = Meaning<Int>("temperature in Celsius");
+
+        /// A city to ask about.
+        class City {
+            /// The name in English.
+            name: String;
+        }
+
+        /// Asks for the current temperature.
+        ///
+        /// The answer is rounded to whole degrees.
+        fn get_temperature(city: String) -> Temperature {
+            prompt "What is the temperature in {city}?";
+        }
+        "#,
+    )?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+
+    // --- Assert ---
+    assert!(code.contains(
+        "/// A temperature as the weather service reports it.\npub type Temperature = i32;"
+    ));
+    assert!(code.contains("/// A city to ask about.\n#[derive(Debug, Clone)]\npub struct City {"));
+    assert!(code.contains("    /// The name in English.\n    pub name: String,"));
+    assert!(
+        code.contains(
+            "/// Asks for the current temperature.\n///\n/// The answer is rounded to whole degrees.\npub fn get_temperature("
+        ),
+        "{}",
+        code
+    );
+    Ok(())
+}
+
+#[test]
+fn test_constants_are_folded_into_prompts_and_defaults() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        const YEAR = 2025;
+        const THIS_YEAR = YEAR;
+        const GREETING = "Hello";
+
+        class Person {
+            born: Int = THIS_YEAR;
+        }
+
+        fn get_age(name: String) -> Int {
+            prompt "How old is {name} in {YEAR}? Start with {GREETING}.";
+        }
+
+        fn shadowed(YEAR: Int) -> String {
+            prompt "What happened in {YEAR}?";
+        }
+        "#,
+    )?;
+    let unknown = parse_source("const A = B;")?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+    let error = CodeGenerator::new()
+        .generate(&unknown, true)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(
+        code.contains(r#"template = "How old is {name} in 2025? Start with Hello.".to_string();"#),
+        "{}",
+        code
+    );
+    assert!(code.contains("born: 2025,"));
+    assert!(code.contains(r#"template = "What happened in {YEAR}?".to_string();"#));
+    assert_eq!(
+        error,
+        "Line 1: The constant `A` refers to `B`, which is not a constant declared before it"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_generated_helpers_allow_dead_code() -> Result<()> {
+    // --- Arrange ---
+    // Only the String dispatch is used, so the numeric extractors go unused.
+    let ast = parse_source(
+        r#"
+        type Topic = Meaning<String>("a topic");
+        fn describe(topic: Topic) -> String {
+            prompt "Describe {topic}.";
+        }
+        "#,
+    )?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, false)?;
+
+    // --- Assert ---
+    for helper in [
+        "fn extract_generic_number(",
+        "fn extract_generic_float(",
+        "fn vibe_enhance_prompt(",
+        "fn parse_semantic_response(",
+        "fn vibe_execute_prompt(",
+    ] {
+        assert!(
+            code.contains(&format!("#[allow(dead_code)]\n{}", helper)),
+            "{} is not allowed to be unused",
+            helper
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_timeout_and_retries_annotations_override_the_client_config() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        @timeout(10) @retries(3)
+        fn summarize(text: String) -> String {
+            prompt "Summarize {text}.";
+        }
+
+        fn get_year() -> Int {
+            prompt "What year is it?";
+        }
+        "#,
+    )?;
+    let zero = parse_source(r#"@timeout(0) fn f() -> String { prompt "Hi there, you."; }"#)?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+    let error = CodeGenerator::new()
+        .generate(&zero, true)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    let (summarize, get_year) = code.split_once("pub fn get_year").unwrap();
+    assert!(
+        summarize.contains("let llm = &llm.clone().with_timeout_secs(10).with_retries(3);"),
+        "{}",
+        summarize
+    );
+    assert!(!get_year.contains("with_timeout_secs"));
+    assert_eq!(
+        error,
+        "Line 1: Invalid timeout 0 in `f`: expected at least 1 second"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dollar_placeholders_leave_braces_in_the_prompt() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        @placeholders("${name}")
+
+        fn forecast(city: String) -> String {
+            prompt """
+            Reply with JSON such as {"city": "${city}", "sky": "clear"}.
+            Do not fill in {city}.
+            """;
+        }
+        "#,
+    )?;
+    let unknown = parse_source(r#"@placeholders("<name>") fn f() -> String { prompt "Hi"; }"#)?;
+    let generator = CodeGenerator::new();
+
+    // --- Act ---
+    let code = generator.generate(&ast, true)?;
+    let prompt = generator.render_prompt(
+        &ast,
+        "forecast",
+        &[("city".to_string(), "Oslo".to_string())].into(),
+    )?;
+    let unknown_style = generator.generate(&unknown, true);
+
+    // --- Assert ---
+    assert!(
+        code.contains(r#"template = template.replace("${city}", &city.to_string());"#),
+        "{}",
+        code
+    );
+    assert!(!code.contains(r#"replace("{city}""#));
+    assert_eq!(
+        prompt,
+        "Reply with JSON such as {\"city\": \"Oslo\", \"sky\": \"clear\"}.\nDo not fill in {city}."
+    );
+    assert!(unknown_style.is_err());
+    Ok(())
+}
+
+#[test]
+fn test_where_guards_are_checked_at_the_top_of_the_function() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        fn advise(age: Int where age >= 0 && age < 150) -> String {
+            prompt "Give advice to someone aged {age}.";
+        }
+        "#,
+    )?;
+    let other = parse_source(
+        r#"fn f(age: Int, name: String where age > 0) -> String { prompt "Hi {name}, {age}."; }"#,
+    )?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+    let error = CodeGenerator::new()
+        .generate(&other, true)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    let body = code
+        .split_once("pub fn advise(llm: &LlmClient, age: i32) -> String {\n")
+        .unwrap()
+        .1;
+    assert!(
+        body.starts_with(
+            "    assert!(age >= 0 && age < 150, \"`advise` requires `age >= 0 && age < 150`, got age = {:?}\", age);\n"
+        ),
+        "{}",
+        body
+    );
+    assert_eq!(
+        error,
+        "Line 1: The check `age > 0` on parameter `name` of `f` must compare `name` itself"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_guarded_parameters_get_demo_values_that_pass_their_checks() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        fn rate(ratio: Float where ratio < 0.5) -> String {
+            prompt "Describe a ratio of {ratio}.";
+        }
+
+        fn advise(age: Int where age >= 0 && age < 100) -> String {
+            prompt "Give advice to someone aged {age}.";
+        }
+
+        fn greet(name: String where name != "Test Topic") -> String {
+            prompt "Greet {name} warmly.";
+        }
+        "#,
+    )?;
+    let contradictory =
+        parse_source(r#"fn f(x: Int where x > 5 && x < 3) -> String { prompt "Pick {x}."; }"#)?;
+
+    // --- Act ---
+    let code = CodeGenerator::new()
+        .with_float_format(FloatFormat::Fixed(0))
+        .generate(&ast, false)?;
+    let error = CodeGenerator::new()
+        .generate(&contradictory, false)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(code.contains("assert!(ratio < 0.5, "), "{}", code);
+    assert!(code.contains("-0.5  // <-------------- CHANGE THE TEST VALUE"));
+    assert!(code.contains("0  // <-------------- CHANGE THE TEST VALUE"));
+    assert!(!code.contains("123  // <-------------- CHANGE THE TEST VALUE"));
+    assert!(code.contains("\"Other Topic\".to_string()  // <-------------- CHANGE THE TEST VALUE"));
+    vibelang::testing::check_rust_syntax(&code)?;
+    assert_eq!(
+        error,
+        "Line 1: No value passes the checks on parameter `x` of `f`: they contradict each other"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_runtime_mode_overrides_declared_async() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        fn get_year() -> Meaning<Int>("the current year") { prompt "What year is it now?"; }
+        async fn greet(name: String) -> String { prompt "Greet {name} warmly."; }
+        "#,
+    )?;
+    let blocking = CodeGenerator::new().with_runtime_mode(RuntimeMode::Blocking);
+
+    // --- Act ---
+    let async_code = CodeGenerator::new()
+        .with_runtime_mode(RuntimeMode::Async)
+        .generate(&ast, false)?;
+    let blocking_code = blocking.generate(&ast, false)?;
+
+    // --- Assert ---
+    let async_main = async_code.split_once("fn main()").unwrap().1;
+    assert!(async_code.contains("pub async fn get_year(llm: &LlmClient) -> i32 {"));
+    assert!(async_code.contains("#[tokio::main]\nasync fn main()"));
+    assert!(async_main.contains(".await"), "{}", async_main);
+    let blocking_main = blocking_code.split_once("fn main()").unwrap().1;
+    assert!(blocking_code.contains("pub fn greet(llm: &LlmClient, name: String) -> String {"));
+    assert!(!blocking_code.contains("#[tokio::main]"));
+    assert!(!blocking_main.contains(".await"), "{}", blocking_main);
+    assert!(CodeGenerator::new().has_async_functions(&ast));
+    assert!(!blocking.has_async_functions(&ast));
+    Ok(())
+}
+
+#[test]
+fn test_undeclared_identifiers_are_reported_at_their_use() -> Result<()> {
+    // --- Arrange ---
+    let error_for = |source: &str| -> Result<String> {
+        let ast = parse_source(source)?;
+        Ok(CodeGenerator::new()
+            .generate(&ast, true)
+            .unwrap_err()
+            .to_string())
+    };
+
+    // --- Act ---
+    let in_let = error_for(
+        "fn greet(name: String) -> String {\n    let who = nmae;\n    prompt \"Say hello to {who} kindly.\";\n}",
+    )?;
+    let in_prompt = error_for(
+        "fn greet(name: String) -> String {\n    prompt \"Say hello to {name} and {other}.\";\n    let other = \"Bo\";\n}",
+    )?;
+
+    // --- Assert ---
+    assert_eq!(
+        in_let,
+        "Line 2, column 15: `nmae` is used in `greet` before it is declared: only parameters and earlier `let` bindings can be used"
+    );
+    assert_eq!(
+        in_prompt,
+        "Line 2: The prompt of `greet` uses `{other}`, which is not a parameter or a `let` binding declared before it"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_schema_describes_the_knowledge_sample_meaning_types() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(include_str!("../examples/knowledge_retrieval.vibe"))?;
+
+    // --- Act ---
+    let schema = CodeGenerator::new().schema(&ast);
+
+    // --- Assert ---
+    let types = schema["types"].as_array().unwrap();
+    let population = types
+        .iter()
+        .find(|entry| entry["name"] == "Population")
+        .unwrap();
+    assert_eq!(population["base_type"], "i32");
+    assert_eq!(population["meaning"], "population count in millions");
+    assert_eq!(
+        population["extractor"],
+        "extract_population_count_millions_value"
+    );
+    assert!(types.iter().all(|entry| entry["meaning"].is_string()));
+    Ok(())
+}
+
+#[test]
+fn test_with_confidence_returns_a_confident_value() -> Result<()> {
+    // --- Arrange ---
+    let ast = parse_source(
+        r#"
+        @with_confidence
+        fn guess_year(event: String) -> Int {
+            prompt "In which year did {event} happen?";
+        }
+
+        fn plain_year() -> Int { prompt "What year is it now?"; }
+        "#,
+    )?;
+    let no_prompt = parse_source("@with_confidence fn f() -> Int { return 1; }")?;
+
+    // --- Act ---
+    let code = CodeGenerator::new().generate(&ast, true)?;
+    let error = CodeGenerator::new()
+        .generate(&no_prompt, true)
+        .unwrap_err()
+        .to_string();
+
+    // --- Assert ---
+    assert!(code.contains(
+        "pub fn guess_year(llm: &LlmClient, event: String) -> vibelang::runtime::confidence::Confident<i32> {"
+    ));
+    assert!(code.contains("happen? On a last line of its own, write Confidence: and how sure you are of the answer, from 0 to 1."));
+    assert!(code.contains(
+        "let (result, confidence) = vibe_execute_prompt_with_confidence(llm, &template, meaning, return_type_str, temperature);"
+    ));
+    assert!(code.contains("vibelang::runtime::confidence::Confident { value, confidence }"));
+    assert!(code.contains("pub fn plain_year(llm: &LlmClient) -> i32 {"));
+    assert!(
+        error.starts_with("Line 1: `@with_confidence` on `f` needs a prompt and a return type")
+    );
+    Ok(())
+}